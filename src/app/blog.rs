@@ -6,8 +6,8 @@ use leptos_router::{components::*, hooks::*};
 use server_fn::codec::GetUrl;
 
 #[cfg(feature = "ssr")]
-use crate::blog::{get_meta, get_post};
-use crate::blog::{Post, PostMeta, GLOBAL_META_CACHE, GLOBAL_POST_CACHE};
+use crate::blog::{get_meta, get_post, get_post_source};
+use crate::blog::{Post, PostMeta, PostSource, GLOBAL_META_CACHE, GLOBAL_POST_CACHE};
 
 #[component]
 pub fn BlogWrapper() -> impl IntoView {
@@ -193,6 +193,15 @@ pub async fn get_post_server(name: String) -> Result<Post, ServerFnError> {
     })
 }
 
+#[server(input = GetUrl)]
+pub async fn get_post_source_server(name: String) -> Result<PostSource, ServerFnError> {
+    let opts = expect_context::<leptos_axum::ResponseOptions>();
+    get_post_source(&name).ok_or_else(|| {
+        opts.set_status(StatusCode::NOT_FOUND);
+        ServerFnError::new("Couldn't get blog post")
+    })
+}
+
 #[component]
 pub fn BlogPage() -> impl IntoView {
     let params = use_params_map();
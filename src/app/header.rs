@@ -1,13 +1,14 @@
 use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "hydrate")]
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 use leptos::{
     either::*,
     ev::{Event, KeyboardEvent},
     html,
     prelude::*,
+    web_sys::HtmlInputElement,
 };
 use leptos_router::{
     components::*,
@@ -21,8 +22,17 @@ use codee::string::JsonSerdeWasmCodec;
 use leptos_use::storage::use_local_storage;
 
 use crate::blog::Assets;
+#[cfg(feature = "hydrate")]
+use crate::blog::GLOBAL_META_CACHE;
 
-use super::terminal::{ColumnarView, CommandRes, TabCompletionItem, Terminal};
+#[cfg(feature = "hydrate")]
+use super::blog::get_meta_server;
+#[cfg(feature = "hydrate")]
+use super::terminal::VfsSnapshot;
+use super::terminal::{
+    render_prompt, ColumnarView, CommandRes, EditAction, LineEditor, Pager, PagerSegment,
+    StderrText, StdoutText, TabCompletionItem, Terminal, PAGE_LINES,
+};
 
 #[component]
 fn MobileFloatingButton(on_click: impl Fn() + 'static) -> impl IntoView {
@@ -59,6 +69,8 @@ fn InputSection(
     set_cursor_position: WriteSignal<usize>,
     ghost_text: ReadSignal<Option<String>>,
     is_err: ReadSignal<bool>,
+    prompt: ReadSignal<Option<String>>,
+    search_state: ReadSignal<Option<SearchState>>,
     keydown_handler: impl Fn(KeyboardEvent) + 'static,
     input_handler: impl Fn(Event) + 'static,
     submit_handler: impl Fn() + 'static,
@@ -80,10 +92,27 @@ fn InputSection(
         <div class="flex flex-col sm:flex-row sm:items-center gap-3 sm:gap-4">
             <div class="text-lg sm:text-xl lg:text-2xl font-bold min-w-0 flex-shrink-0">
                 {move || {
+                    // Reverse-i-search replaces the prompt while it's active
+                    if let Some(search) = search_state.get() {
+                        let label = if search.failed {
+                            "(failing reverse-i-search)`"
+                        } else {
+                            "(reverse-i-search)`"
+                        };
+                        return view! {
+                            <span class="font-normal">
+                                <span class="text-muted">{label}</span>
+                                <span class="text-yellow">{search.query}</span>
+                                <span class="text-muted">"':"</span>
+                            </span>
+                        }
+                            .into_any();
+                    }
                     let err = is_err.get();
                     let pathname = use_location().pathname.get();
                     let dir = dir_from_pathname(pathname);
-                    view! { <Ps1 is_err=err path=dir with_links=true /> }
+                    let prompt = prompt.get();
+                    view! { <Ps1 is_err=err path=dir with_links=true prompt /> }.into_any()
                 }}
             </div>
             <form
@@ -125,7 +154,7 @@ fn InputSection(
                         class="w-full px-4 py-2 rounded-md border focus:outline-none focus:ring-2 focus:ring-cyan bg-background text-foreground placeholder-muted transition-all duration-200 ease-out hover:border-subtle focus:border-cyan focus:shadow-lg focus:shadow-cyan/20 font-mono caret-transparent empty-placeholder"
                     />
                     <div id=aria_describedby class="sr-only">
-                        "Type terminal commands like 'help', 'ls', 'cd /blog', or 'neofetch'. Use Tab for autocomplete and arrow keys for history. Right arrow to accept suggestions."
+                        "Type terminal commands like 'help', 'ls', 'cd /blog', or 'neofetch'. Use Tab for autocomplete, arrow keys for history and Ctrl+R to search it. Right arrow to accept suggestions."
                     </div>
                     <div class="absolute inset-y-0 left-0 px-4 py-2 pointer-events-none overflow-hidden flex items-center text-foreground terminal-overlay whitespace-nowrap">
                         {move || {
@@ -143,6 +172,15 @@ fn InputSection(
                             } else {
                                 let before_cursor = &curr[..cursor_pos.min(curr.len())];
                                 let after_cursor = &curr[cursor_pos.min(curr.len())..];
+                                // Reverse-i-search puts the cursor at the start of the match
+                                let match_len = search_state
+                                    .get()
+                                    .filter(|search| {
+                                        !search.failed && after_cursor.starts_with(&search.query)
+                                    })
+                                    .map(|search| search.query.len())
+                                    .unwrap_or_default();
+                                let (matched, after_cursor) = after_cursor.split_at(match_len);
                                 view! {
                                     <>
                                         <span class="invisible font-mono whitespace-pre empty-placeholder">
@@ -153,8 +191,11 @@ fn InputSection(
                                                 " "
                                             </span>
                                         </span>
+                                        <span class="text-transparent bg-yellow/40 rounded-sm font-mono whitespace-pre empty-placeholder">
+                                            {matched.to_string()}
+                                        </span>
                                         <span class="invisible font-mono whitespace-pre empty-placeholder">
-                                            {after_cursor}
+                                            {after_cursor.to_string()}
                                         </span>
                                         {move || {
                                             if cursor_position.get() >= input_value.get().len() {
@@ -200,21 +241,45 @@ struct HistState {
     index: usize,
 }
 
+/// Ctrl+R reverse-i-search, showing `opts[index]` in the input while active
+#[derive(Debug, Clone)]
+struct SearchState {
+    query: String,
+    original: String, // The input before searching, restored on cancel
+    opts: Arc<Vec<String>>,
+    index: usize,
+    failed: bool,
+}
+
 #[component]
 pub fn Header() -> impl IntoView {
     let blog_posts = Assets::iter()
         .map(|s| s[..s.len() - 3].to_string())
         .collect::<Vec<_>>();
 
-    let terminal = StoredValue::new(Arc::new(Mutex::new(Terminal::new(&blog_posts, None))));
+    let initial_terminal = Terminal::new(&blog_posts, None);
+    let (prompt, set_prompt) = signal(initial_terminal.prompt());
+    // Problems in .zshrc are shown as the first thing in the output
+    let mut initial_output = Vec::<ChildrenFn>::new();
+    if let Some(errors) = initial_terminal.startup_errors() {
+        initial_output.push(Arc::new(move || {
+            view! { <StderrText text=errors.clone() /> }.into_any()
+        }));
+    }
+
+    let terminal = StoredValue::new(Arc::new(Mutex::new(initial_terminal)));
     let input_ref = NodeRef::<html::Input>::new();
     let floating_input_ref = NodeRef::<html::Input>::new();
     let header_ref = NodeRef::<html::Header>::new();
-    let (output_history, set_output_history) =
-        signal(Arc::new(Mutex::new(Vec::<ChildrenFn>::new())));
+    let (output_history, set_output_history) = signal(Arc::new(Mutex::new(initial_output)));
     let (is_err, set_is_err) = signal(false);
     let (tab_state, set_tab_state) = signal(None::<TabState>);
     let (hist_state, set_hist_state) = signal(None::<HistState>);
+    let (search_state, set_search_state) = signal(None::<SearchState>);
+    // less, or output too tall for the panel, shown instead of the scrollback until closed
+    let (pager, set_pager) = signal(None::<Pager>);
+    // Readline editing state (the kill ring), shared by the static and floating inputs
+    let line_editor = StoredValue::new(LineEditor::default());
     let (input_value, set_input_value) = signal(String::new());
     let (cursor_position, set_cursor_position) = signal(0usize);
     let (ghost_text, set_ghost_text) = signal(None::<String>);
@@ -252,15 +317,57 @@ pub fn Header() -> impl IntoView {
     let (cmd_history, set_cmd_history, _) =
         use_local_storage::<VecDeque<String>, JsonSerdeWasmCodec>("cmd_history");
 
+    #[cfg(feature = "hydrate")]
+    let (env_vars, set_env_vars, _) =
+        use_local_storage::<HashMap<String, String>, JsonSerdeWasmCodec>("env_vars");
+    #[cfg(feature = "hydrate")]
+    let (aliases, set_aliases, _) =
+        use_local_storage::<Option<HashMap<String, String>>, JsonSerdeWasmCodec>("aliases");
+    // A save that no longer decodes comes back as the default, empty snapshot
+    #[cfg(feature = "hydrate")]
+    let (vfs_snapshot, set_vfs_snapshot, _) =
+        use_local_storage::<VfsSnapshot, JsonSerdeWasmCodec>("vfs");
+
     #[cfg(feature = "hydrate")]
     Effect::watch(
         || (),
         move |_, _, _| {
             let history = cmd_history.get_untracked();
+            let env_vars = env_vars.get_untracked();
+            let aliases = aliases.get_untracked();
+            let snapshot = vfs_snapshot.get_untracked();
             terminal.with_value(|t| {
-                t.lock()
-                    .expect("should be able to unlock terminal")
-                    .set_history(history);
+                let mut t = t.lock().expect("should be able to unlock terminal");
+                t.set_history(history);
+                t.set_env_vars(env_vars);
+                t.set_aliases(aliases);
+                t.restore_vfs(snapshot);
+            });
+        },
+        true,
+    );
+
+    // Date the terminal's blog posts from their metadata, which the blog may already have fetched
+    #[cfg(feature = "hydrate")]
+    Effect::watch(
+        || (),
+        move |_, _, _| {
+            leptos::task::spawn_local(async move {
+                let cached = GLOBAL_META_CACHE.get("").map(|posts| posts.clone());
+                let posts = match cached {
+                    Some(posts) => posts,
+                    None => match get_meta_server(String::new()).await {
+                        Ok(posts) => {
+                            GLOBAL_META_CACHE.insert(String::new(), posts.clone());
+                            posts
+                        }
+                        Err(_) => return,
+                    },
+                };
+                terminal.try_with_value(|t| {
+                    let mut t = t.lock().expect("should be able to unlock terminal");
+                    t.seed_post_times(&posts);
+                });
             });
         },
         true,
@@ -312,11 +419,17 @@ pub fn Header() -> impl IntoView {
             let was_err = is_err.get_untracked();
             let prev_pathname = use_location().pathname.get();
             let prev_dir = dir_from_pathname(prev_pathname);
+            let prev_prompt = prompt.get_untracked();
             let cmd = cmd.clone();
             history_vec.push(Arc::new(move || {
                 view! {
                     <div>
-                        <Ps1 is_err=was_err path=prev_dir.clone() with_links=false />
+                        <Ps1
+                            is_err=was_err
+                            path=prev_dir.clone()
+                            with_links=false
+                            prompt=prev_prompt.clone()
+                        />
                         " "
                         {cmd.clone()}
                     </div>
@@ -343,32 +456,38 @@ pub fn Header() -> impl IntoView {
             }
         });
 
+        // Paged output is shown by the pager and never lands in the scrollback
+        let paged = res
+            .pager_text(PAGE_LINES)
+            .map(|text| Pager::new(text, PAGE_LINES));
+
         match res {
             CommandRes::Output {
                 is_err,
                 stdout_view,
                 stdout_text,
                 stderr_text,
+                ..
             } => {
                 set_is_err(is_err);
                 // Convert stderr text to view with consistent error styling
                 if let Some(stderr_msg) = stderr_text {
                     if !stderr_msg.is_empty() {
                         let error_view = Arc::new(move || {
-                            view! { <div class="text-red whitespace-pre-wrap">{stderr_msg.clone()}</div> }
-                            .into_any()
+                            view! { <StderrText text=stderr_msg.clone() /> }.into_any()
                         });
                         history_vec.push(error_view);
                     }
                 }
                 // Use stdout_view if available, otherwise convert stdout_text to view
-                if let Some(view) = stdout_view {
+                if paged.is_some() {
+                    set_pager(paged);
+                } else if let Some(view) = stdout_view {
                     history_vec.push(view);
                 } else if let Some(stdout_msg) = stdout_text {
                     if !stdout_msg.is_empty() {
                         let text_view = Arc::new(move || {
-                            view! { <div class="whitespace-pre-wrap" inner_html=stdout_msg.clone()></div> }
-                            .into_any()
+                            view! { <StdoutText text=stdout_msg.clone() /> }.into_any()
                         });
                         history_vec.push(text_view);
                     }
@@ -381,13 +500,25 @@ pub fn Header() -> impl IntoView {
             }
         }
 
+        // `source` or an assignment may have changed PROMPT
+        let new_prompt = terminal.with_value(|t| {
+            t.lock()
+                .expect("should be able to unlock terminal")
+                .prompt()
+        });
+        if new_prompt != prompt.get_untracked() {
+            set_prompt(new_prompt);
+        }
+
         #[cfg(feature = "hydrate")]
         terminal.with_value(|t| {
-            set_cmd_history.set(
-                t.lock()
-                    .expect("should be able to unlock terminal")
-                    .history(),
-            );
+            let mut t = t.lock().expect("should be able to unlock terminal");
+            set_cmd_history.set(t.history());
+            set_env_vars.set(t.env_vars());
+            set_aliases.set(Some(t.aliases()));
+            if let Some(snapshot) = t.vfs_snapshot() {
+                set_vfs_snapshot.set(snapshot);
+            }
         });
     };
 
@@ -503,6 +634,101 @@ pub fn Header() -> impl IntoView {
         }
     };
 
+    // Show the current reverse-i-search match in the input, with the cursor on the match
+    let show_search = move |el: &HtmlInputElement, mut search: SearchState| {
+        match search.opts.get(search.index) {
+            Some(cmd) => {
+                let pos = cmd.find(&search.query).unwrap_or_default();
+                el.set_value(cmd);
+                let _ = el.set_selection_range(pos as u32, pos as u32);
+                set_input_value.set(cmd.clone());
+                set_cursor_position.set(pos);
+                search.failed = false;
+            }
+            None => search.failed = !search.query.is_empty(),
+        }
+        set_search_state(Some(search));
+    };
+
+    let cancel_search = move |el: &HtmlInputElement, search: SearchState| {
+        el.set_value(&search.original);
+        set_input_value.set(search.original.clone());
+        set_cursor_position.set(search.original.len());
+        set_search_state(None);
+    };
+
+    // Keys while reverse-i-search is active. Returns false if the key should then be
+    // handled normally, in which case the search ends with the match left in the input.
+    let handle_search_key =
+        move |ev: &KeyboardEvent, el: &HtmlInputElement, search: SearchState| {
+            let key = ev.key();
+            match key.as_str() {
+                "r" if ev.ctrl_key() => {
+                    ev.prevent_default();
+                    if search.index + 1 < search.opts.len() {
+                        show_search(
+                            el,
+                            SearchState {
+                                index: search.index + 1,
+                                ..search
+                            },
+                        );
+                    } else {
+                        set_search_state(Some(SearchState {
+                            failed: true,
+                            ..search
+                        }));
+                    }
+                    true
+                }
+                "g" if ev.ctrl_key() => {
+                    ev.prevent_default();
+                    cancel_search(el, search);
+                    true
+                }
+                "Escape" => {
+                    ev.prevent_default();
+                    cancel_search(el, search);
+                    true
+                }
+                _ if key == "Backspace"
+                    || (key.chars().count() == 1
+                        && !ev.ctrl_key()
+                        && !ev.meta_key()
+                        && !ev.alt_key()) =>
+                {
+                    ev.prevent_default();
+                    let mut query = search.query;
+                    if key == "Backspace" {
+                        query.pop();
+                    } else {
+                        query.push_str(&key);
+                    }
+                    let opts = terminal.with_value(|t| {
+                        t.lock()
+                            .expect("should be able to access terminal")
+                            .handle_reverse_search(&query)
+                    });
+                    show_search(
+                        el,
+                        SearchState {
+                            query,
+                            opts: opts.into(),
+                            index: 0,
+                            ..search
+                        },
+                    );
+                    true
+                }
+                "Shift" | "Control" | "Alt" | "Meta" => true,
+                // Enter runs the match, and anything else edits it as a normal command line
+                _ => {
+                    set_search_state(None);
+                    false
+                }
+            }
+        };
+
     let keydown_handler = move |ev: KeyboardEvent| {
         // Get the currently focused input (original or floating)
         let el = if let Some(floating_el) = floating_input_ref.get_untracked() {
@@ -520,6 +746,33 @@ pub fn Header() -> impl IntoView {
             set_is_err(true);
             return;
         };
+        if let Some(mut active) = pager.get_untracked() {
+            // The pager takes every key until it's closed with q
+            ev.prevent_default();
+            let is_open = active.handle_key(&ev.key());
+            set_pager(is_open.then_some(active));
+            return;
+        }
+        if let Some(search) = search_state.get_untracked() {
+            if handle_search_key(&ev, &el, search) {
+                return;
+            }
+        }
+        if ev.ctrl_key() && ev.key() == "r" {
+            ev.prevent_default();
+            handle_scroll_and_focus_transfer();
+            set_ghost_text.set(None);
+            set_hist_state(None);
+            set_tab_state(None);
+            set_search_state(Some(SearchState {
+                query: String::new(),
+                original: el.value(),
+                opts: Arc::new(Vec::new()),
+                index: 0,
+                failed: false,
+            }));
+            return;
+        }
         if ev.ctrl_key() && ev.key() == "c" {
             handle_cmd(el.value(), true);
             el.set_value("");
@@ -538,6 +791,27 @@ pub fn Header() -> impl IntoView {
             set_tab_state(None);
             return;
         }
+        if let Some(action) =
+            EditAction::from_key(&ev.key(), &ev.code(), ev.ctrl_key(), ev.alt_key())
+                .filter(|_| !ev.meta_key())
+        {
+            ev.prevent_default();
+            let (line, cursor) = line_editor
+                .try_update_value(|editor| {
+                    editor.sync(&el.value(), cursor_position.get_untracked());
+                    editor.apply(action);
+                    (editor.line().to_string(), editor.cursor())
+                })
+                .expect("line editor should not be disposed");
+            el.set_value(&line);
+            let _ = el.set_selection_range(cursor as u32, cursor as u32);
+            set_input_value.set(line);
+            set_cursor_position.set(cursor);
+            set_ghost_text.set(None);
+            set_hist_state(None);
+            set_tab_state(None);
+            return;
+        }
         if ev.meta_key() || ev.alt_key() || ev.ctrl_key() {
             return;
         }
@@ -850,6 +1124,9 @@ pub fn Header() -> impl IntoView {
             <header node_ref=header_ref class="shadow-lg border-b border-muted/30">
                 <div class="mx-auto px-3 sm:px-4 md:px-6 lg:px-8 py-3 sm:py-4">
                     {move || {
+                        if let Some(pager) = pager.get() {
+                            return Some(view! { <PagerView pager /> }.into_any());
+                        }
                         let history = output_history.get();
                         let views = {
                             let history = history.lock().expect("should be able to acquire lock");
@@ -865,7 +1142,8 @@ pub fn Header() -> impl IntoView {
                                             {views}
                                         </pre>
                                     </div>
-                                },
+                                }
+                                    .into_any(),
                             )
                         }
                     }}
@@ -877,6 +1155,8 @@ pub fn Header() -> impl IntoView {
                         set_cursor_position=set_cursor_position
                         ghost_text=ghost_text
                         is_err=is_err
+                        prompt=prompt
+                        search_state=search_state
                         keydown_handler=keydown_handler
                         input_handler=input_handler
                         submit_handler=shared_submit_handler
@@ -949,6 +1229,8 @@ pub fn Header() -> impl IntoView {
                                         set_cursor_position=set_cursor_position
                                         ghost_text=ghost_text
                                         is_err=is_err
+                                        prompt=prompt
+                                        search_state=search_state
                                         keydown_handler=keydown_handler
                                         input_handler=input_handler
                                         submit_handler=floating_submit_handler
@@ -964,8 +1246,46 @@ pub fn Header() -> impl IntoView {
     }
 }
 
+/// One screen of the pager, with search matches highlighted and less's status line
+#[component]
+fn PagerView(pager: Pager) -> impl IntoView {
+    let segment_view = |segment: PagerSegment| {
+        let class = if segment.matched {
+            "bg-foreground text-background"
+        } else {
+            ""
+        };
+        view! { <span class=class>{segment.text}</span> }
+    };
+    let lines = pager
+        .visible_lines()
+        .into_iter()
+        .map(|segments| {
+            view! {
+                <div class="min-h-[1lh]">
+                    {segments.into_iter().map(segment_view).collect_view()}
+                </div>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="max-h-[480px] overflow-hidden mb-2 p-3 rounded-md bg-black/20 border border-muted/30 backdrop-blur-sm">
+            <pre class="whitespace-pre terminal-output leading-tight">
+                {lines}
+                <span class="bg-foreground text-background">{pager.status()}</span>
+            </pre>
+        </div>
+    }
+}
+
 #[component]
-fn Ps1(is_err: bool, path: String, with_links: bool) -> impl IntoView {
+fn Ps1(is_err: bool, path: String, with_links: bool, prompt: Option<String>) -> impl IntoView {
+    // A PROMPT set in .zshrc replaces the built-in theme
+    if let Some(template) = prompt {
+        return Either::Left(view! { <span>{render_prompt(&template, &path, is_err)}</span> });
+    }
+
     let path_git = view! {
         <span class="text-cyan">{path.to_string()}</span>
         " "
@@ -975,7 +1295,7 @@ fn Ps1(is_err: bool, path: String, with_links: bool) -> impl IntoView {
             <span>")"</span>
         </span>
     };
-    view! {
+    Either::Right(view! {
         <span class=move || { if is_err { "text-red" } else { "text-green" } }>"➜"</span>
         " "
         {if with_links {
@@ -985,5 +1305,5 @@ fn Ps1(is_err: bool, path: String, with_links: bool) -> impl IntoView {
         }}
         " "
         <span class="text-yellow">"✗"</span>
-    }
+    })
 }
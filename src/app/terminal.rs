@@ -1,31 +1,75 @@
 mod command;
 mod components;
+mod disk_tools;
+mod env_tools;
 mod fs_tools;
+mod glob;
+mod history_expansion;
+mod lexer;
+mod pager;
+mod perm_tools;
+mod prompt;
 mod ps_tools;
+mod readline;
+mod redirect;
+mod search_tools;
 mod simple_tools;
 mod system_tools;
+mod text_tools;
+mod usage;
 pub mod vfs;
 
 pub use command::CommandRes;
-pub use components::ColumnarView;
+pub use components::{ColumnarView, StderrText, StdoutText};
+pub use pager::{Pager, PagerSegment, PAGE_LINES};
+pub use prompt::render_prompt;
+pub use readline::{EditAction, LineEditor};
+#[cfg(feature = "hydrate")]
+pub use vfs::VfsSnapshot;
 
 use std::collections::{HashMap, VecDeque};
 
-use command::{Cmd, CmdAlias, Command, VfsCommand};
+#[cfg(feature = "hydrate")]
+use crate::blog::PostMeta;
+
+use command::{Cmd, Command, VfsCommand};
 use components::TextContent;
+use disk_tools::{DfCommand, DuCommand};
+use env_tools::{
+    AliasCommand, EnvCommand, ExportCommand, PrintenvCommand, SetCommand, UnaliasCommand,
+    UnsetCommand,
+};
 use fs_tools::{
-    CatCommand, CdCommand, CpCommand, LsCommand, MkdirCommand, MvCommand, RmCommand, TouchCommand,
+    CatCommand, CdCommand, CpCommand, LnCommand, LsCommand, MkdirCommand, MvCommand,
+    ReadlinkCommand, RmCommand, TouchCommand, TreeCommand,
 };
+use history_expansion::expand_history;
 use indextree::NodeId;
+use lexer::{tokenize, tokenize_partial, Token, Word, WordPart};
+use pager::LessCommand;
+use perm_tools::{ChmodCommand, ChownCommand, StatCommand, UmaskCommand};
 use ps_tools::{KillCommand, Process, PsCommand};
+use redirect::{apply_redirections, open_redirections, parse_redirections, Sink};
+use search_tools::{FindCommand, GrepCommand};
 use simple_tools::{
-    ClearCommand, DateCommand, EchoCommand, HelpCommand, HistoryCommand, MinesCommand,
+    ClearCommand, DateCommand, EchoCommand, HelpCommand, HistoryCommand, ManCommand, MinesCommand,
     NeofetchCommand, PwdCommand, SudoCommand, UptimeCommand, WhoAmICommand,
 };
 use system_tools::{UnknownCommand, WhichCommand};
-use vfs::VirtualFilesystem;
+use text_tools::{
+    CutCommand, HeadCommand, SortCommand, TailCommand, TrCommand, UniqCommand, WcCommand,
+};
+use vfs::{FileContent, VfsNodeType, VirtualFilesystem, DEFAULT_PROMPT, DEFAULT_USER};
 
 static HISTORY_SIZE: usize = 1000;
+static RC_FILE: &str = "/.zshrc";
+static MAX_SCRIPT_DEPTH: usize = 16;
+/// zsh and oh-my-zsh setup commands in the rc file, which this shell accepts and ignores
+static IGNORED_BUILTINS: [&str; 6] = [
+    "setopt", "unsetopt", "autoload", "compinit", "bindkey", "plugins",
+];
+/// The terminal's own process, which `/proc/self` points at
+const TERMINAL_PID: u32 = 99;
 
 #[derive(Debug, Clone)]
 pub struct TabCompletionItem {
@@ -42,18 +86,25 @@ impl TextContent for TabCompletionItem {
 
 pub struct Terminal {
     history: VecDeque<String>,
-    env_vars: HashMap<String, String>,
+    env_vars: HashMap<String, String>,   // Exported variables
+    shell_vars: HashMap<String, String>, // Variables set with NAME=value but not exported
+    aliases: HashMap<String, String>,
+    last_exit_status: i32,
     processes: Vec<Process>,
     commands: HashMap<Cmd, Box<dyn Command>>,
     vfs_commands: HashMap<Cmd, Box<dyn VfsCommand>>,
     vfs: VirtualFilesystem,
+    positional_params: Vec<String>, // `$0` followed by `$1`, `$2`, ...
+    errexit: bool,                  // `set -e`: stop at the first failing command
+    script_depth: usize,            // How many scripts or sourced files are currently running
+    startup_errors: Option<String>, // Errors from the rc file, shown on the first render
 }
 
 impl Terminal {
     pub fn new(blog_posts: &[String], history: Option<VecDeque<String>>) -> Self {
         let history = history.unwrap_or_default();
         let mut env_vars = HashMap::new();
-        env_vars.insert("USER".to_string(), "user".to_string());
+        env_vars.insert("USER".to_string(), DEFAULT_USER.to_string());
         env_vars.insert("HOME".to_string(), "/".to_string());
         env_vars.insert("SITE".to_string(), "hansbaker.com".to_string());
         env_vars.insert("VERSION".to_string(), env!("CARGO_PKG_VERSION").to_string());
@@ -62,27 +113,107 @@ impl Terminal {
         let commands = HashMap::new(); // Will be populated after construction
         let vfs_commands = HashMap::new(); // Will be populated after construction
 
-        let vfs = VirtualFilesystem::new(blog_posts.to_owned());
+        let mut vfs = VirtualFilesystem::new(blog_posts.to_owned());
+        vfs.mount_proc(processes.clone(), TERMINAL_PID);
 
         let mut terminal = Self {
             history,
             env_vars,
+            shell_vars: HashMap::new(),
+            aliases: Self::default_aliases(),
+            last_exit_status: 0,
             processes,
             commands,
             vfs_commands,
             vfs,
+            positional_params: vec!["zsh".to_string()],
+            errexit: false,
+            script_depth: 0,
+            startup_errors: None,
         };
 
         terminal.initialize_commands();
         terminal.initialize_vfs_commands();
+
+        // Run the rc file, keeping its errors for the header rather than failing to start
+        let errors = match terminal.source_file("/", RC_FILE, &[]) {
+            Ok(results) => results
+                .into_iter()
+                .filter_map(|res| match res {
+                    CommandRes::Output { stderr_text, .. } => stderr_text,
+                    CommandRes::Redirect(_) => None,
+                })
+                .collect(),
+            Err(e) => vec![e],
+        };
+        if !errors.is_empty() {
+            terminal.startup_errors = Some(errors.join("\n"));
+        }
+
         terminal
     }
 
+    pub fn startup_errors(&self) -> Option<String> {
+        self.startup_errors.clone()
+    }
+
+    /// A custom PROMPT to show instead of the header's built-in theme, if one is set
+    pub fn prompt(&self) -> Option<String> {
+        self.get_var("PROMPT")
+            .filter(|prompt| *prompt != DEFAULT_PROMPT)
+            .cloned()
+    }
+
+    fn history_size(&self) -> usize {
+        self.get_var("HISTSIZE")
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(HISTORY_SIZE)
+    }
+
     #[cfg(feature = "hydrate")]
     pub fn set_history(&mut self, history: VecDeque<String>) {
         self.history = history;
     }
 
+    fn default_aliases() -> HashMap<String, String> {
+        [("ll", "ls -la"), ("la", "ls -a"), ("h", "history")]
+            .into_iter()
+            .map(|(name, expansion)| (name.to_string(), expansion.to_string()))
+            .collect()
+    }
+
+    #[cfg(feature = "hydrate")]
+    pub fn aliases(&self) -> HashMap<String, String> {
+        self.aliases.clone()
+    }
+
+    /// Restore aliases saved by a previous visit
+    #[cfg(feature = "hydrate")]
+    pub fn set_aliases(&mut self, aliases: Option<HashMap<String, String>>) {
+        // Without a save, keep the defaults. A saved empty table means `unalias -a` was run.
+        if let Some(aliases) = aliases {
+            self.aliases = aliases;
+        }
+    }
+
+    #[cfg(feature = "hydrate")]
+    pub fn env_vars(&self) -> HashMap<String, String> {
+        self.env_vars.clone()
+    }
+
+    /// Restore exported variables saved by a previous visit
+    #[cfg(feature = "hydrate")]
+    pub fn set_env_vars(&mut self, env_vars: HashMap<String, String>) {
+        // Nothing has been saved yet
+        if env_vars.is_empty() {
+            return;
+        }
+        self.env_vars = env_vars;
+        // VERSION describes the running build, not the saved one
+        self.env_vars
+            .insert("VERSION".to_string(), env!("CARGO_PKG_VERSION").to_string());
+    }
+
     fn initialize_processes() -> Vec<Process> {
         vec![
             Process {
@@ -100,7 +231,7 @@ impl Terminal {
                 command: "blog-renderer".to_string(),
             },
             Process {
-                pid: 99,
+                pid: TERMINAL_PID,
                 user: "app".to_string(),
                 cpu_percent: 0.2,
                 mem_percent: 3.1,
@@ -126,7 +257,7 @@ impl Terminal {
     fn initialize_commands(&mut self) {
         // Simple commands (no context needed)
         self.commands.insert(Cmd::Help, Box::new(HelpCommand));
-        self.commands.insert(Cmd::Pwd, Box::new(PwdCommand));
+        self.commands.insert(Cmd::Man, Box::new(ManCommand));
         self.commands.insert(Cmd::WhoAmI, Box::new(WhoAmICommand));
         self.commands.insert(Cmd::Clear, Box::new(ClearCommand));
         self.commands
@@ -136,6 +267,7 @@ impl Terminal {
         self.commands.insert(Cmd::Echo, Box::new(EchoCommand));
         self.commands.insert(Cmd::Date, Box::new(DateCommand));
         self.commands.insert(Cmd::Uptime, Box::new(UptimeCommand));
+        self.commands.insert(Cmd::Tr, Box::new(TrCommand));
 
         // Process commands
         self.commands
@@ -152,18 +284,50 @@ impl Terminal {
         // VFS-aware commands that need direct filesystem access
         self.vfs_commands
             .insert(Cmd::Ls, Box::new(LsCommand::new()));
-        self.vfs_commands
-            .insert(Cmd::Cd, Box::new(CdCommand::new()));
         self.vfs_commands
             .insert(Cmd::Cat, Box::new(CatCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Tree, Box::new(TreeCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Less, Box::new(LessCommand::new(Cmd::Less)));
+        self.vfs_commands
+            .insert(Cmd::More, Box::new(LessCommand::new(Cmd::More)));
         self.vfs_commands
             .insert(Cmd::Touch, Box::new(TouchCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Ln, Box::new(LnCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Readlink, Box::new(ReadlinkCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Df, Box::new(DfCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Du, Box::new(DuCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Chmod, Box::new(ChmodCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Chown, Box::new(ChownCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Umask, Box::new(UmaskCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Stat, Box::new(StatCommand::new()));
         self.vfs_commands
             .insert(Cmd::MkDir, Box::new(MkdirCommand::new()));
         self.vfs_commands
-            .insert(Cmd::Rm, Box::new(RmCommand::new()));
+            .insert(Cmd::Grep, Box::new(GrepCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Head, Box::new(HeadCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Tail, Box::new(TailCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Wc, Box::new(WcCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Sort, Box::new(SortCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Uniq, Box::new(UniqCommand::new()));
         self.vfs_commands
-            .insert(Cmd::Which, Box::new(WhichCommand::new()));
+            .insert(Cmd::Cut, Box::new(CutCommand::new()));
+        self.vfs_commands
+            .insert(Cmd::Rm, Box::new(RmCommand::new()));
         self.vfs_commands
             .insert(Cmd::Cp, Box::new(CpCommand::new()));
         self.vfs_commands
@@ -175,86 +339,536 @@ impl Terminal {
         self.history.clone()
     }
 
-    fn process_aliases(&self, input: &str) -> String {
-        let trimmed = input.trim();
-
-        for alias in CmdAlias::all() {
-            let alias_str = alias.as_str();
-            if let Some(args) = trimmed.strip_prefix(alias_str) {
-                if trimmed == alias_str {
-                    return alias.expand("");
-                } else if trimmed.starts_with(&format!("{alias_str} ")) {
-                    return alias.expand(args);
-                }
-            }
-        }
+    /// The user's files and directories to save, if they've changed since last asked
+    #[cfg(feature = "hydrate")]
+    pub fn vfs_snapshot(&mut self) -> Option<VfsSnapshot> {
+        self.vfs.take_changed().then(|| self.vfs.snapshot())
+    }
 
-        input.to_string()
+    /// Restore files and directories saved by a previous visit
+    #[cfg(feature = "hydrate")]
+    pub fn restore_vfs(&mut self, snapshot: VfsSnapshot) {
+        self.vfs.restore(snapshot);
     }
 
-    fn expand_env_vars(&self, path: &str, input: &str) -> String {
-        let mut result = input.to_string();
-        let mut env_vars = self.env_vars.clone();
-        env_vars.insert("PWD".to_string(), path.to_string());
+    /// Date the blog posts' files from when they were published
+    #[cfg(feature = "hydrate")]
+    pub fn seed_post_times(&mut self, posts: &[PostMeta]) {
+        self.vfs.seed_post_times(posts);
+    }
 
-        while let Some(start) = result.find('$') {
-            let remaining = &result[start + 1..];
-            let end = remaining
-                .find(|c: char| !c.is_alphanumeric() && c != '_')
-                .unwrap_or(remaining.len());
-            let var_name = &remaining[..end];
+    /// Replace unquoted alias names in command position with their expansions.
+    /// `seen` holds the aliases already being expanded, so `alias ls='ls -a'` doesn't loop.
+    fn process_aliases(&self, tokens: Vec<Token>, seen: &[&str]) -> Vec<Token> {
+        let mut result = Vec::new();
+        let mut command_position = true;
+
+        for token in tokens {
+            let alias = match &token {
+                Token::Word(Word(parts)) if command_position => match parts.as_slice() {
+                    [WordPart::Bare(name)] if !seen.contains(&name.as_str()) => self
+                        .aliases
+                        .get_key_value(name)
+                        .map(|(name, expansion)| (name.as_str(), expansion)),
+                    _ => None,
+                },
+                _ => None,
+            };
 
-            if let Some(value) = env_vars.get(var_name) {
-                let var_ref = format!("${var_name}");
-                result = result.replace(&var_ref, value);
+            if let Some((name, expansion)) = alias {
+                let seen = [seen, &[name]].concat();
+                let expanded = tokenize(expansion).unwrap_or_default();
+                result.extend(self.process_aliases(expanded, &seen));
             } else {
-                // Replace with empty string if variable not found
-                let var_ref = format!("${var_name}");
-                result = result.replace(&var_ref, "");
+                result.push(token);
             }
+
+            // The next word is a command after an operator or a NAME=value assignment
+            command_position = match result.last() {
+                Some(Token::Word(word)) => command_position && word.assignment().is_some(),
+                Some(Token::Pipe | Token::Semi | Token::Newline | Token::And | Token::Or) => true,
+                _ => false,
+            };
         }
+
         result
     }
 
+    fn get_var(&self, name: &str) -> Option<&String> {
+        self.env_vars
+            .get(name)
+            .or_else(|| self.shell_vars.get(name))
+    }
+
+    fn expand_env_vars(&self, path: &str, word: &Word) -> String {
+        word.expand(|name| self.lookup_var(path, name))
+    }
+
+    fn lookup_var(&self, path: &str, name: &str) -> Option<String> {
+        match name {
+            "PWD" => Some(path.to_string()),
+            "?" => Some(self.last_exit_status.to_string()),
+            "#" => Some((self.positional_params.len() - 1).to_string()),
+            "@" => Some(self.positional_params[1..].join(" ")),
+            _ if name.chars().all(|c| c.is_ascii_digit()) => name
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| self.positional_params.get(n))
+                .cloned(),
+            _ => self.get_var(name).cloned(),
+        }
+    }
+
     pub fn handle_command(&mut self, path: &str, input: &str) -> CommandRes {
         if input.trim().is_empty() {
             return CommandRes::new();
         }
+
+        // History expansion happens first, and it's the expanded line that's remembered
+        let expanded = match expand_history(input, &self.history) {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                self.last_exit_status = 1;
+                return CommandRes::new()
+                    .with_error()
+                    .with_stderr(format!("zsh: {e}"));
+            }
+        };
+        let input = expanded.as_deref().unwrap_or(input);
         self.history.push_back(input.to_string());
-        if self.history.len() > HISTORY_SIZE {
+        while self.history.len() > self.history_size() {
             self.history.pop_front();
         }
 
-        // Process command aliases first
-        let aliased_input = self.process_aliases(input);
+        let tokens = match tokenize(input) {
+            Ok(tokens) => self.process_aliases(tokens, &[]),
+            Err(e) => {
+                return CommandRes::new()
+                    .with_error()
+                    .with_stderr(format!("zsh: {e}"))
+            }
+        };
+        let res = if tokens.is_empty() {
+            // Nothing but a comment
+            CommandRes::new()
+        } else {
+            self.execute_list(path, tokens)
+        };
 
-        // Expand environment variables in the input
-        let expanded_input = self.expand_env_vars(path, &aliased_input);
+        // Like a real shell, show the expanded command before its output
+        match expanded {
+            Some(line) => res.with_echo(line),
+            None => res,
+        }
+    }
 
-        let mut parts = expanded_input.split_whitespace();
-        let cmd_text = if let Some(word) = parts.next() {
-            word
-        } else {
-            unreachable!("Should have returned early if empty");
+    /// Run each command in `file` in the current shell, so the aliases and variables it sets stick.
+    /// Any `args` replace the positional parameters while the file runs.
+    fn source_file(
+        &mut self,
+        path: &str,
+        file: &str,
+        args: &[&str],
+    ) -> Result<Vec<CommandRes>, String> {
+        let current_node = self.current_node(path);
+        let content = self
+            .vfs
+            .resolve_path(current_node, file)
+            .and_then(|node| self.vfs.read_file(node))
+            .map_err(|e| format!("source: {e}: {file}"))?;
+
+        let saved_params = (!args.is_empty()).then(|| {
+            let params = self.positional_params[..1]
+                .iter()
+                .cloned()
+                .chain(args.iter().map(|arg| arg.to_string()))
+                .collect();
+            std::mem::replace(&mut self.positional_params, params)
+        });
+        let results = self.run_script(path, file, &content);
+        if let Some(params) = saved_params {
+            self.positional_params = params;
+        }
+
+        results
+    }
+
+    /// Run an executable file the way a child shell would: with its own positional
+    /// parameters and options, and without leaking variables or aliases back.
+    fn execute_script(
+        &mut self,
+        path: &str,
+        script: &str,
+        content: &str,
+        args: Vec<&str>,
+    ) -> CommandRes {
+        let params = std::iter::once(script)
+            .chain(args)
+            .map(String::from)
+            .collect();
+        let saved_params = std::mem::replace(&mut self.positional_params, params);
+        let saved_errexit = std::mem::take(&mut self.errexit);
+        let saved_env_vars = self.env_vars.clone();
+        let saved_shell_vars = self.shell_vars.clone();
+        let saved_aliases = self.aliases.clone();
+
+        let results = self.run_script(path, script, content);
+
+        self.positional_params = saved_params;
+        self.errexit = saved_errexit;
+        self.env_vars = saved_env_vars;
+        self.shell_vars = saved_shell_vars;
+        self.aliases = saved_aliases;
+        self.sync_vfs();
+
+        match results {
+            Ok(results) => Self::combine_script_results(results),
+            Err(e) => CommandRes::new().with_error().with_stderr(e),
+        }
+    }
+
+    /// Run `content` one line at a time, so aliases defined on one line apply to the next.
+    /// Stops early on navigation, or at the first failure once `set -e` is on.
+    fn run_script(
+        &mut self,
+        path: &str,
+        name: &str,
+        content: &str,
+    ) -> Result<Vec<CommandRes>, String> {
+        if self.script_depth >= MAX_SCRIPT_DEPTH {
+            return Err(format!("{name}: maximum nested function level reached"));
+        }
+
+        self.script_depth += 1;
+        let mut results = Vec::new();
+        // The shebang line is just a comment to the lexer
+        for (number, line) in content.lines().enumerate() {
+            let tokens = match tokenize(line) {
+                Ok(tokens) if tokens.is_empty() => continue,
+                Ok(tokens) => self.process_aliases(tokens, &[]),
+                Err(e) => {
+                    self.last_exit_status = 1;
+                    let error_msg = format!("{name}:{}: {e}", number + 1);
+                    results.push(CommandRes::new().with_error().with_stderr(error_msg));
+                    break;
+                }
+            };
+            let res = self.execute_list(path, tokens);
+            let is_redirect = matches!(res, CommandRes::Redirect(_));
+            results.push(res);
+            if is_redirect || (self.errexit && self.last_exit_status != 0) {
+                break;
+            }
+        }
+        self.script_depth -= 1;
+
+        Ok(results)
+    }
+
+    /// Navigation from a script wins over its output, as it would for a single command
+    fn combine_script_results(mut results: Vec<CommandRes>) -> CommandRes {
+        match results.last() {
+            Some(CommandRes::Redirect(_)) => results.pop().expect("checked last"),
+            _ => CommandRes::combine(results),
+        }
+    }
+
+    /// The contents of `cmd_text` if it names an executable text file, i.e. a script
+    fn script_content(&mut self, current_node: NodeId, cmd_text: &str) -> Option<String> {
+        if !cmd_text.contains('/') {
+            return None;
+        }
+        let node_id = self.vfs.resolve_path(current_node, cmd_text).ok()?;
+        let node = self.vfs.get_node(node_id)?;
+        match &node.node_type {
+            VfsNodeType::File {
+                content: FileContent::Static(_) | FileContent::Dynamic(_),
+            } if node.is_executable() => self.vfs.read_file(node_id).ok(),
+            _ => None,
+        }
+    }
+
+    /// Run a command list such as `a; b && c || d`. `&&` and `||` check the exit status of
+    /// the previous pipeline, and navigating away (a `Redirect`) ends the list early, as does
+    /// a failure once `set -e` is on.
+    fn execute_list(&mut self, path: &str, tokens: Vec<Token>) -> CommandRes {
+        let parse_error = |op: &Token| {
+            let op = match op {
+                Token::And => "&&",
+                Token::Or => "||",
+                _ => ";",
+            };
+            CommandRes::new()
+                .with_error()
+                .with_stderr(format!("zsh: parse error near `{op}'"))
+        };
+
+        // Each pipeline is paired with the operator that precedes it
+        let mut pipelines = vec![(Token::Semi, Vec::new())];
+        for token in tokens {
+            match token {
+                // Blank lines, comments and a newline after `&&` or `||` separate nothing
+                Token::Newline => {
+                    if pipelines
+                        .last()
+                        .is_some_and(|(_, tokens)| !tokens.is_empty())
+                    {
+                        pipelines.push((Token::Semi, Vec::new()));
+                    }
+                }
+                Token::Semi | Token::And | Token::Or => {
+                    if pipelines
+                        .last()
+                        .is_some_and(|(_, tokens)| tokens.is_empty())
+                    {
+                        return parse_error(&token);
+                    }
+                    pipelines.push((token, Vec::new()));
+                }
+                token => pipelines
+                    .last_mut()
+                    .expect("There is always a pipeline to add to")
+                    .1
+                    .push(token),
+            }
+        }
+        // A trailing `;` is fine, a trailing `&&` or `||` is not
+        match pipelines.last() {
+            Some((Token::Semi, tokens)) if tokens.is_empty() => {
+                pipelines.pop();
+            }
+            Some((op, tokens)) if tokens.is_empty() => return parse_error(op),
+            _ => {}
+        }
+
+        let mut results = Vec::new();
+        let mut pipelines = pipelines.into_iter().peekable();
+        while let Some((op, pipeline)) = pipelines.next() {
+            let should_run = match op {
+                Token::And => self.last_exit_status == 0,
+                Token::Or => self.last_exit_status != 0,
+                _ => true,
+            };
+            if !should_run {
+                continue;
+            }
+
+            let res = self.execute_pipeline(path, pipeline);
+            self.last_exit_status = if res.is_error() { 1 } else { 0 };
+            if matches!(res, CommandRes::Redirect(_)) {
+                return res;
+            }
+            results.push(res);
+            // As with `sh -e`, failures that `&&` or `||` go on to test don't count
+            let is_tested = matches!(pipelines.peek(), Some((Token::And | Token::Or, _)));
+            if self.errexit && self.last_exit_status != 0 && !is_tested {
+                break;
+            }
+        }
+
+        CommandRes::combine(results)
+    }
+
+    /// Run each stage of `a | b | c`, feeding one stage's stdout_text into the next stage's stdin.
+    /// Only the final stage writes to the TTY; stderr from earlier stages is kept in order.
+    fn execute_pipeline(&mut self, path: &str, tokens: Vec<Token>) -> CommandRes {
+        let stages = tokens
+            .split(|token| *token == Token::Pipe)
+            .map(<[Token]>::to_vec)
+            .collect::<Vec<_>>();
+        if stages.iter().any(Vec::is_empty) {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("zsh: parse error near `|'");
+        }
+
+        let last_stage = stages.len() - 1;
+        let mut stdin: Option<String> = None;
+        let mut stderr_parts = Vec::new();
+
+        for (i, stage) in stages.into_iter().enumerate() {
+            let is_last = i == last_stage;
+            let mut res = self.execute_redirected(path, stage, stdin.as_deref(), is_last);
+
+            if is_last {
+                if let CommandRes::Output { stderr_text, .. } = &mut res {
+                    stderr_parts.extend(stderr_text.take());
+                    if !stderr_parts.is_empty() {
+                        *stderr_text = Some(stderr_parts.join("\n"));
+                    }
+                }
+                return res;
+            }
+
+            match res {
+                CommandRes::Output {
+                    stdout_text,
+                    stderr_text,
+                    ..
+                } => {
+                    stdin = stdout_text;
+                    stderr_parts.extend(stderr_text);
+                }
+                // Navigation only makes sense for the final stage
+                CommandRes::Redirect(_) => stdin = None,
+            }
+        }
+
+        unreachable!("The final pipeline stage always returns");
+    }
+
+    /// Run a single command, sending its output to any `>`, `>>`, `2>` or `2>&1` targets
+    fn execute_redirected(
+        &mut self,
+        path: &str,
+        stage: Vec<Token>,
+        stdin: Option<&str>,
+        is_last: bool,
+    ) -> CommandRes {
+        let expand = |word: &Word| self.expand_env_vars(path, word);
+        let (words, redirections) = match parse_redirections(stage, expand) {
+            Ok(parsed) => parsed,
+            Err(e) => return CommandRes::new().with_error().with_stderr(e),
+        };
+
+        // Leading NAME=value words are variable assignments
+        let mut words = words.into_iter().peekable();
+        let mut assignments = Vec::new();
+        while let Some((name, value)) = words.peek().and_then(Word::assignment) {
+            assignments.push((name, self.expand_env_vars(path, &value)));
+            words.next();
+        }
+
+        // Unquoted variables that expand to nothing disappear entirely,
+        // `$@` becomes one word per positional parameter, and globs become the
+        // paths they match (or stay as they are if nothing matches)
+        let current_node = self.current_node(path);
+        let words = words
+            .flat_map(|word| {
+                if word.0 == [WordPart::Var("@".to_string())] {
+                    return self.positional_params[1..].to_vec();
+                }
+                if let Some(pattern) = word.glob_pattern(|name| self.lookup_var(path, name)) {
+                    let paths = glob::expand(&self.vfs, current_node, &pattern);
+                    if !paths.is_empty() {
+                        return paths;
+                    }
+                }
+                let text = self.expand_env_vars(path, &word);
+                (!text.is_empty() || !word.is_only_vars())
+                    .then_some(text)
+                    .into_iter()
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        self.sync_vfs();
+        let (stdout, stderr) = match open_redirections(&mut self.vfs, current_node, &redirections) {
+            Ok(sinks) => sinks,
+            Err(e) => return CommandRes::new().with_error().with_stderr(e),
+        };
+
+        // A bare `> file` only creates/truncates the file, and bare assignments set
+        // shell variables (updating the environment if the variable is already exported)
+        let Some((cmd_text, args)) = words.split_first() else {
+            for (name, value) in assignments {
+                if let Some(exported) = self.env_vars.get_mut(&name) {
+                    *exported = value;
+                } else {
+                    self.shell_vars.insert(name, value);
+                }
+            }
+            self.sync_vfs();
+            return CommandRes::new();
         };
+
+        // `NAME=value cmd` only sets NAME in the environment for that one command
+        let saved_vars = assignments
+            .into_iter()
+            .map(|(name, value)| {
+                let previous = self.env_vars.insert(name.clone(), value);
+                (name, previous)
+            })
+            .collect::<Vec<_>>();
+
+        let is_tty = is_last && stdout == Sink::Stdout;
+        let args = args.iter().map(String::as_str).collect();
+        let has_prefixes = !saved_vars.is_empty();
+        if has_prefixes {
+            self.sync_vfs();
+        }
+        let res = self.execute_command(path, cmd_text, args, stdin, is_tty);
+
+        for (name, previous) in saved_vars.into_iter().rev() {
+            match previous {
+                Some(value) => self.env_vars.insert(name, value),
+                None => self.env_vars.remove(&name),
+            };
+        }
+        // Only these builtins change the environment
+        let changes_env = matches!(
+            Cmd::from(cmd_text.as_str()),
+            Cmd::Export | Cmd::Unset | Cmd::Set | Cmd::Source
+        );
+        if has_prefixes || changes_env {
+            self.sync_vfs();
+        }
+
+        apply_redirections(&mut self.vfs, res, stdout, stderr)
+    }
+
+    /// Check file permissions as whoever `$USER` says the user is, and show the current
+    /// environment in `/proc`
+    fn sync_vfs(&mut self) {
+        let user = self
+            .env_vars
+            .get("USER")
+            .map_or(DEFAULT_USER, String::as_str);
+        self.vfs.set_user(user);
+        self.vfs.set_environment(&self.env_vars);
+    }
+
+    fn current_node(&self, path: &str) -> NodeId {
+        self.vfs
+            .resolve_path(self.vfs.get_root(), path)
+            .unwrap_or_else(|_| self.vfs.get_root())
+    }
+
+    fn execute_command(
+        &mut self,
+        path: &str,
+        cmd_text: &str,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        is_tty: bool,
+    ) -> CommandRes {
+        if IGNORED_BUILTINS.contains(&cmd_text) {
+            return CommandRes::new();
+        }
+
         // Convert string to Command enum for type-safe lookup
         let cmd = Cmd::from(cmd_text);
 
+        // `--help` prints the command's usage instead of running it; echo just prints it back
+        let wants_help = args
+            .iter()
+            .take_while(|arg| **arg != "--")
+            .any(|arg| *arg == "--help");
+        if wants_help && cmd != Cmd::Echo {
+            if let Some(usage) = cmd.usage() {
+                return CommandRes::new().with_stdout_text(usage.help_text());
+            }
+        }
+
         // Try VFS commands first (they have priority)
         if let Some(vfs_command) = self.vfs_commands.get(&cmd) {
-            let current_node = if let Ok(node_id) = self.vfs.resolve_path(self.vfs.get_root(), path)
-            {
-                node_id
-            } else {
-                self.vfs.get_root()
-            };
-            return vfs_command.execute(&mut self.vfs, current_node, parts.collect(), None, true);
+            let current_node = self.current_node(path);
+            return vfs_command.execute(&mut self.vfs, current_node, args, stdin, is_tty);
         }
 
         // Try non-VFS commands
         if let Some(command) = self.commands.get(&cmd) {
-            // For now, assume not piped and output to TTY
-            return command.execute(path, parts.collect(), None, true);
+            return command.execute(path, args, stdin, is_tty);
         }
 
         // Fall back to special command handling for some commands
@@ -265,25 +879,95 @@ impl Terminal {
             // Therefore, we handle -c here in the terminal and update the HistoryCommand
             // with current history for other operations.
             Cmd::History => {
-                let args: Vec<&str> = parts.collect();
                 if args.len() == 1 && args[0] == "-c" {
                     self.history.clear();
                     return CommandRes::new().with_stdout_text("history cleared");
                 }
                 self.history.make_contiguous();
                 // For non-clear history commands, update the command with current history before executing
-                HistoryCommand::new(self.history.as_slices().0).execute(path, args, None, true)
+                HistoryCommand::new(self.history.as_slices().0).execute(path, args, stdin, is_tty)
+            }
+            // Variable builtins read or update the terminal's own variables
+            Cmd::Export => {
+                ExportCommand::new(&mut self.env_vars, &mut self.shell_vars).execute(args)
+            }
+            Cmd::Unset => UnsetCommand::new(&mut self.env_vars, &mut self.shell_vars).execute(args),
+            Cmd::Env => EnvCommand::new(&self.env_vars).execute(path, args, stdin, is_tty),
+            Cmd::Printenv => {
+                PrintenvCommand::new(&self.env_vars).execute(path, args, stdin, is_tty)
+            }
+            Cmd::Set => {
+                SetCommand::new(&self.env_vars, &self.shell_vars, &mut self.errexit).execute(args)
+            }
+            Cmd::Source => {
+                let Some((file, args)) = args.split_first() else {
+                    return CommandRes::new()
+                        .with_error()
+                        .with_stderr("source: not enough arguments");
+                };
+                match self.source_file(path, file, args) {
+                    Ok(results) => Self::combine_script_results(results),
+                    Err(e) => CommandRes::new().with_error().with_stderr(e),
+                }
+            }
+            // cd and pwd work on the logical path, which only the terminal knows
+            Cmd::Cd => CdCommand::new(path).execute(&self.vfs, args),
+            Cmd::Pwd => {
+                let physical = self.vfs.get_node_path(self.current_node(path));
+                PwdCommand::new(physical).execute(path, args, stdin, is_tty)
+            }
+            Cmd::Alias => AliasCommand::new(&mut self.aliases).execute(args),
+            Cmd::Unalias => UnaliasCommand::new(&mut self.aliases).execute(args),
+            Cmd::Which => {
+                // which needs the alias table as well as the VFS
+                let current_node = self.current_node(path);
+                WhichCommand::new(&self.aliases).execute(
+                    &mut self.vfs,
+                    current_node,
+                    args,
+                    stdin,
+                    is_tty,
+                )
+            }
+            Cmd::Find => {
+                // find searches the VFS itself, but -exec runs commands through the terminal
+                let current_node = self.current_node(path);
+                let found = match FindCommand::new().search(&self.vfs, current_node, args) {
+                    Ok(found) => found,
+                    Err(res) => return res,
+                };
+                let Some(exec) = &found.exec else {
+                    return found.into_command_res();
+                };
+
+                let mut results = Vec::new();
+                if !found.errors.is_empty() {
+                    results.push(CommandRes::new().with_stderr(found.errors.join("\n")));
+                }
+                for line in exec.command_lines(&found.paths) {
+                    let Some((cmd_text, args)) = line.split_first() else {
+                        continue;
+                    };
+                    let args = args.iter().map(String::as_str).collect();
+                    results.push(self.execute_command(path, cmd_text, args, None, is_tty));
+                }
+                // find's status only reflects its own errors, not the commands it ran
+                let mut res = CommandRes::combine(results);
+                if let CommandRes::Output { is_err, .. } = &mut res {
+                    *is_err = !found.errors.is_empty();
+                }
+                res
             }
             Cmd::Unknown => {
-                // Handle unknown commands through VFS
+                // Executable files are interpreted as shell scripts
+                let current_node = self.current_node(path);
+                if let Some(content) = self.script_content(current_node, cmd_text) {
+                    return self.execute_script(path, cmd_text, &content, args);
+                }
+
+                // Handle other unknown commands through VFS
                 let unknown_cmd = UnknownCommand::new(cmd_text.to_string());
-                let current_node =
-                    if let Ok(node_id) = self.vfs.resolve_path(self.vfs.get_root(), path) {
-                        node_id
-                    } else {
-                        self.vfs.get_root()
-                    };
-                unknown_cmd.execute(&mut self.vfs, current_node, parts.collect(), None, true)
+                unknown_cmd.execute(&mut self.vfs, current_node, args, stdin, is_tty)
             }
             // All commands should now be handled by the trait system
             _ => {
@@ -304,8 +988,36 @@ impl Terminal {
         }
     }
 
+    /// Commands containing `query` for reverse-i-search, newest first and without repeats
+    pub fn handle_reverse_search(&self, query: &str) -> Vec<String> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = Vec::<String>::new();
+        for cmd in self.history.iter().rev() {
+            if cmd.contains(query) && !matches.contains(cmd) {
+                matches.push(cmd.clone());
+            }
+        }
+        matches
+    }
+
     pub fn handle_start_tab(&mut self, path: &str, input: &str) -> Vec<TabCompletionItem> {
-        let mut parts = input.split_whitespace();
+        // Only the command after the last `|` matters for completion
+        let tokens = tokenize_partial(input);
+        let stage = tokens
+            .rsplit(|token| *token == Token::Pipe)
+            .next()
+            .unwrap_or_default();
+        let words = stage
+            .iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word.literal()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut parts = words.iter().map(String::as_str);
         let cmd_text = if let Some(word) = parts.next() {
             word
         } else {
@@ -315,10 +1027,7 @@ impl Terminal {
         let mut parts = parts.peekable();
 
         // Get current directory in VFS
-        let current_dir = match self.vfs.resolve_path(self.vfs.get_root(), path) {
-            Ok(node_id) => node_id,
-            Err(_) => self.vfs.get_root(),
-        };
+        let current_dir = self.current_node(path);
 
         match cmd {
             Cmd::Unknown if parts.peek().is_none() && !input.ends_with(" ") => {
@@ -346,11 +1055,10 @@ impl Terminal {
             .collect::<Vec<_>>();
 
         // Add aliases
-        for alias in CmdAlias::all() {
-            let alias_str = alias.as_str();
-            if alias_str.starts_with(cmd_text) {
+        for alias in self.aliases.keys() {
+            if alias.starts_with(cmd_text) {
                 commands.push(TabCompletionItem {
-                    completion_text: alias_str.to_string(),
+                    completion_text: alias.to_string(),
                     is_directory: false,
                     is_executable: true,
                 });
@@ -412,9 +1120,18 @@ impl Terminal {
                 continue;
             }
 
+            // Links complete like what they point at, so a link to a directory gets its slash
+            let is_directory = entry.is_directory
+                || self
+                    .vfs
+                    .resolve_path(target_dir, &entry.name)
+                    .ok()
+                    .and_then(|node_id| self.vfs.get_node(node_id))
+                    .is_some_and(|node| node.is_directory());
+
             // The completion text should just be the entry name, not the full path
             // Add appropriate suffix for display
-            let completion_text = if entry.is_directory {
+            let completion_text = if is_directory {
                 format!("{}/", entry.name)
             } else if entry.is_executable {
                 format!("{}*", entry.name)
@@ -424,7 +1141,7 @@ impl Terminal {
 
             results.push(TabCompletionItem {
                 completion_text,
-                is_directory: entry.is_directory,
+                is_directory,
                 is_executable: entry.is_executable,
             });
         }
@@ -445,6 +1162,7 @@ impl Terminal {
 
 #[cfg(test)]
 mod tests {
+    use super::vfs::Quota;
     use super::*;
 
     // Helper function to extract stdout text from CommandRes
@@ -466,7 +1184,7 @@ mod tests {
     // Helper function to check if a file exists in VFS
     fn vfs_file_exists(terminal: &mut Terminal, path: &str) -> bool {
         // Create a temporary VFS command to access the filesystem
-        let result = terminal.handle_command("/", &format!("cat {}", path));
+        let result = terminal.handle_command("/", &format!("cat '{path}'"));
 
         // If cat succeeds and no "Is a directory" error, it's a file
         if !result.is_error() {
@@ -490,13 +1208,13 @@ mod tests {
     fn vfs_dir_exists(terminal: &mut Terminal, path: &str) -> bool {
         // Try to cd to the directory and back
         let current_path = "/"; // We'll assume we're testing from root
-        let cd_result = terminal.handle_command(current_path, &format!("cd {}", path));
+        let cd_result = terminal.handle_command(current_path, &format!("cd '{path}'"));
 
         match cd_result {
             CommandRes::Redirect(_) => true,
             _ => {
                 // Check if it's a file by trying cat
-                let cat_result = terminal.handle_command(current_path, &format!("cat {}", path));
+                let cat_result = terminal.handle_command(current_path, &format!("cat '{path}'"));
                 if let Some(stderr) = get_stderr_text(&cat_result) {
                     stderr.contains("Is a directory")
                 } else {
@@ -1203,4 +1921,857 @@ mod tests {
         assert!(!vfs_file_exists(&mut terminal, "/file3.txt"));
         assert!(vfs_file_exists(&mut terminal, "/file1.txt"));
     }
+
+    #[test]
+    fn test_pipelines() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // stdout of one stage becomes stdin of the next
+        let echo_cat = terminal.handle_command("/", "echo hello | cat");
+        assert!(!echo_cat.is_error());
+        assert_eq!(get_stdout_text(&echo_cat).unwrap_or_default(), "hello");
+
+        let cat_cat = terminal.handle_command("/", "cat thanks.txt | cat | cat");
+        assert!(!cat_cat.is_error());
+        assert!(get_stdout_text(&cat_cat)
+            .unwrap_or_default()
+            .contains("Thank you"));
+
+        // Non-final stages produce plain text rather than views
+        let ls_cat = terminal.handle_command("/", "ls | cat");
+        assert!(!ls_cat.is_error());
+        assert!(get_stdout_text(&ls_cat)
+            .unwrap_or_default()
+            .contains("blog"));
+        let neofetch = terminal.handle_command("/", "neofetch | cat");
+        let text = get_stdout_text(&neofetch).unwrap_or_default();
+        assert!(!text.contains('<') && !text.contains("&amp;"));
+        assert!(text.contains('&'));
+
+        // stderr from earlier stages is still reported
+        let missing = terminal.handle_command("/", "cat nonexistent.txt | cat");
+        let error_msg = get_stderr_text(&missing).unwrap_or_default();
+        assert!(error_msg.contains("No such file or directory"));
+
+        // Empty stages are a parse error
+        let trailing = terminal.handle_command("/", "ls |");
+        assert!(trailing.is_error());
+        let error_msg = get_stderr_text(&trailing).unwrap_or_default();
+        assert!(error_msg.contains("parse error"));
+    }
+
+    #[test]
+    fn test_output_redirection() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // > creates and truncates, >> appends
+        let res = terminal.handle_command("/", "echo hello > notes.txt");
+        assert!(!res.is_error());
+        assert!(get_stdout_text(&res).is_none());
+        terminal.handle_command("/", "echo world >> notes.txt");
+        let cat = terminal.handle_command("/", "cat notes.txt");
+        assert_eq!(get_stdout_text(&cat).unwrap_or_default(), "hello\nworld\n");
+
+        terminal.handle_command("/", "echo again >notes.txt");
+        let cat = terminal.handle_command("/", "cat notes.txt");
+        assert_eq!(get_stdout_text(&cat).unwrap_or_default(), "again\n");
+
+        // Views are rendered as text when redirected
+        terminal.handle_command("/", "ls > listing.txt");
+        let cat = terminal.handle_command("/", "cat listing.txt");
+        assert!(get_stdout_text(&cat).unwrap_or_default().contains("blog"));
+
+        // 2> captures stderr, 2>&1 follows stdout
+        let res = terminal.handle_command("/", "cat missing.txt 2> err.txt");
+        assert!(res.is_error());
+        assert!(get_stderr_text(&res).is_none());
+        let cat = terminal.handle_command("/", "cat err.txt");
+        assert!(get_stdout_text(&cat)
+            .unwrap_or_default()
+            .contains("No such file or directory"));
+
+        terminal.handle_command("/", "cat thanks.txt missing.txt > both.txt 2>&1");
+        let both = get_stdout_text(&terminal.handle_command("/", "cat both.txt")).unwrap();
+        assert!(both.contains("Thank you"));
+        assert!(both.contains("No such file or directory"));
+
+        // System files and directories are protected
+        let res = terminal.handle_command("/", "echo hi > nav.rs");
+        assert!(res.is_error());
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "zsh: permission denied: nav.rs"
+        );
+        let res = terminal.handle_command("/", "echo hi >> thanks.txt");
+        assert!(res.is_error());
+        let res = terminal.handle_command("/", "echo hi > blog");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "zsh: is a directory: blog"
+        );
+
+        // Redirection works inside a pipeline
+        terminal.handle_command("/blog", "cat ../thanks.txt | cat > ~/copy.txt");
+        assert!(vfs_file_exists(&mut terminal, "/copy.txt"));
+    }
+
+    #[test]
+    fn test_quoting_and_escapes() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        let res = terminal.handle_command("/", r#"echo "hello   world""#);
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "hello   world");
+
+        let res = terminal.handle_command("/", "echo '$HOME' \\$USER ${USER}s $UNSET");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "$HOME $USER users"
+        );
+
+        // Quoted and escaped spaces stay inside one argument
+        terminal.handle_command("/", "touch 'my file.txt' other\\ file.txt");
+        assert!(vfs_file_exists(&mut terminal, "/my file.txt"));
+        assert!(vfs_file_exists(&mut terminal, "/other file.txt"));
+
+        let res = terminal.handle_command("/", "echo 'unterminated");
+        assert!(res.is_error());
+        assert!(get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("unmatched"));
+
+        // Tab completion understands quoting too
+        let completions = terminal.handle_start_tab("/", "cat 'my fi");
+        assert!(completions
+            .iter()
+            .any(|item| item.completion_text == "my file.txt"));
+        let completions = terminal.handle_start_tab("/", "ls | ca");
+        assert!(completions.iter().any(|item| item.completion_text == "cat"));
+    }
+
+    #[test]
+    fn test_command_lists() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // ; runs everything, combining output in order
+        let res = terminal.handle_command("/", "echo one; echo two;");
+        assert!(!res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "one\ntwo");
+
+        // && stops after a failure, || only runs after one
+        let res = terminal.handle_command("/", "cat missing.txt && echo nope");
+        assert!(res.is_error());
+        assert!(!get_stdout_text(&res).unwrap_or_default().contains("nope"));
+
+        let res = terminal.handle_command("/", "cat missing.txt || echo fallback");
+        assert!(!res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "fallback");
+
+        let res = terminal.handle_command("/", "echo ok || echo skipped && echo after");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "ok\nafter");
+
+        // $? holds the previous exit status
+        terminal.handle_command("/", "cat missing.txt");
+        let res = terminal.handle_command("/", "echo $?; echo $?");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "1\n0");
+
+        // Navigation ends the list
+        let res = terminal.handle_command("/", "mkdir a && cd a && mkdir never");
+        assert!(matches!(res, CommandRes::Redirect(ref url) if url == "/a"));
+        assert!(vfs_dir_exists(&mut terminal, "/a"));
+        assert!(!vfs_dir_exists(&mut terminal, "/a/never"));
+
+        // Newlines separate commands, but blank lines and comments are skipped
+        let res = terminal.handle_command("/", "# c\necho a\n\necho b\n");
+        assert!(!res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "a\nb");
+        let res = terminal.handle_command("/", "echo a &&\necho b");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "a\nb");
+
+        // Dangling operators are parse errors
+        for input in ["&& ls", "ls ;; ls", "ls &&", "ls ||", "ls\n; ls"] {
+            let res = terminal.handle_command("/", input);
+            assert!(res.is_error(), "{input} should fail");
+            assert!(get_stderr_text(&res)
+                .unwrap_or_default()
+                .contains("parse error"));
+        }
+    }
+
+    #[test]
+    fn test_env_builtins() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        let res = terminal.handle_command("/", "export GREETING='hello world'");
+        assert!(!res.is_error());
+        let res = terminal.handle_command("/", "echo $GREETING");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "hello world");
+        let res = terminal.handle_command("/", "printenv GREETING USER");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "hello world\nuser"
+        );
+
+        let env = get_stdout_text(&terminal.handle_command("/", "env")).unwrap_or_default();
+        assert!(env.contains("GREETING=hello world"));
+        assert!(env.contains("USER=user"));
+
+        // Plain assignments are shell variables until exported
+        terminal.handle_command("/", "COLOR=blue");
+        let res = terminal.handle_command("/", "echo $COLOR");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "blue");
+        let res = terminal.handle_command("/", "printenv COLOR");
+        assert!(res.is_error());
+        let set = get_stdout_text(&terminal.handle_command("/", "set")).unwrap_or_default();
+        assert!(set.contains("COLOR=blue"));
+        terminal.handle_command("/", "export COLOR");
+        let res = terminal.handle_command("/", "printenv COLOR");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "blue");
+
+        // NAME=value prefixes only last for one command
+        let res = terminal.handle_command("/", "USER=guest printenv USER");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "guest");
+        let res = terminal.handle_command("/", "TEMP=1 env");
+        assert!(get_stdout_text(&res).unwrap_or_default().contains("TEMP=1"));
+        let res = terminal.handle_command("/", "printenv USER TEMP");
+        assert!(res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "user");
+
+        terminal.handle_command("/", "unset GREETING COLOR");
+        let res = terminal.handle_command("/", "echo $GREETING$COLOR");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "");
+
+        let res = terminal.handle_command("/", "export 1BAD=x");
+        assert!(res.is_error());
+        assert!(get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("not an identifier"));
+
+        // The variable builtins are shell builtins
+        let res = terminal.handle_command("/", "which export env");
+        let which = get_stdout_text(&res).unwrap_or_default();
+        assert!(which.contains("export: shell builtin"));
+        assert!(which.contains("/usr/bin/env"));
+    }
+
+    #[test]
+    fn test_aliases() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // Defaults are seeded and expand with extra arguments
+        let res = terminal.handle_command("/", "which ll");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "ll: aliased to ls -la"
+        );
+        let res = terminal.handle_command("/", "ll blog | cat");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .contains("test-post"));
+
+        // User aliases, including ones that reuse their own name
+        terminal.handle_command("/", "alias greet='echo hello' ls='ls -a'");
+        let res = terminal.handle_command("/", "greet world && cd blog");
+        assert!(matches!(res, CommandRes::Redirect(_)));
+        let res = terminal.handle_command("/", "greet world; echo greet");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "hello world\ngreet"
+        );
+        let res = terminal.handle_command("/", "ls | cat");
+        assert!(get_stdout_text(&res).unwrap_or_default().contains(".zshrc"));
+
+        // Quoted names are never aliases
+        let res = terminal.handle_command("/", "'greet'");
+        assert!(res.is_error());
+
+        let res = terminal.handle_command("/", "alias greet");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "greet='echo hello'"
+        );
+        let res = terminal.handle_command("/", "alias");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .contains("h=history"));
+
+        // Aliases show up in tab completion
+        let completions = terminal.handle_start_tab("/", "gr");
+        assert!(completions
+            .iter()
+            .any(|item| item.completion_text == "greet"));
+
+        terminal.handle_command("/", "unalias greet");
+        let res = terminal.handle_command("/", "greet");
+        assert!(res.is_error());
+        let res = terminal.handle_command("/", "unalias greet");
+        assert!(get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("no such hash table element"));
+    }
+
+    #[test]
+    fn test_rc_file_and_source() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // The default .zshrc runs cleanly and sets things up
+        assert!(terminal.startup_errors().is_none());
+        assert!(terminal.prompt().is_none());
+        let res = terminal.handle_command("/", "setopt SHARE_HISTORY && echo $HISTFILE");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "window.localStorage[cmd_history]"
+        );
+        let res = terminal.handle_command("/", "echo $HISTSIZE $EDITOR");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "1000 vim");
+
+        // Source a custom rc built with redirection
+        terminal.handle_command("/", "echo \"alias hi='echo hello'\" > myrc");
+        terminal.handle_command("/", "echo 'hi there' >> myrc");
+        terminal.handle_command("/", "echo \"PROMPT='%c %# '\" >> myrc");
+        terminal.handle_command("/", "echo HISTSIZE=2 >> myrc");
+        let res = terminal.handle_command("/", ". myrc");
+        assert!(!res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "hello there");
+        assert_eq!(terminal.prompt().as_deref(), Some("%c %# "));
+
+        // HISTSIZE takes effect on the next command
+        terminal.handle_command("/", "echo a");
+        terminal.handle_command("/", "echo b");
+        assert_eq!(terminal.history.len(), 2);
+
+        let res = terminal.handle_command("/", "source missing");
+        assert!(res.is_error());
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "source: no such file or directory: missing"
+        );
+
+        // An rc that sources itself stops instead of overflowing
+        terminal.handle_command("/", "echo 'source loop' > loop");
+        let res = terminal.handle_command("/", "source loop");
+        assert!(res.is_error());
+    }
+
+    #[test]
+    fn test_scripts() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // mines.sh is interpreted rather than special-cased
+        let res = terminal.handle_command("/", "./mines.sh");
+        assert!(
+            matches!(res, CommandRes::Redirect(ref url) if url == "https://mines.hansbaker.com")
+        );
+        let res = terminal.handle_command("/blog", "../mines.sh");
+        assert!(matches!(res, CommandRes::Redirect(_)));
+
+        // Positional parameters
+        terminal.handle_command("/", "echo '#!/bin/zsh' > args.sh");
+        terminal.handle_command("/", "echo 'echo $# \"$1\" $2 # not printed' >> args.sh");
+        terminal.handle_command("/", "echo 'echo $@ > out.txt' >> args.sh");
+        let res = terminal.handle_command("/", "source args.sh 'a b' c");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "2 a b c");
+        assert_eq!(
+            get_stdout_text(&terminal.handle_command("/", "cat out.txt")).unwrap_or_default(),
+            "a b c\n"
+        );
+        let res = terminal.handle_command("/", "echo $# $1");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "0");
+
+        // `$@` keeps each parameter as its own argument
+        terminal.handle_command("/", "echo 'hello' > one.txt");
+        terminal.handle_command("/", "echo 'cat $@' > cat.sh");
+        let res = terminal.handle_command("/", ". cat.sh one.txt missing");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "hello\n");
+        assert!(!get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("one.txt"));
+
+        // set -e stops at the first failure
+        terminal.handle_command("/", "echo 'echo start' > strict.sh");
+        terminal.handle_command("/", "echo 'set -e' >> strict.sh");
+        terminal.handle_command("/", "echo 'cat missing' >> strict.sh");
+        terminal.handle_command("/", "echo 'echo unreachable' >> strict.sh");
+        let res = terminal.handle_command("/", "source strict.sh");
+        assert!(res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "start");
+
+        // Failing commands stop a line too, unless `&&` or `||` tests them
+        terminal.handle_command("/", "echo 'cat missing || echo tested' > line.sh");
+        terminal.handle_command("/", "echo 'cat missing; echo no' >> line.sh");
+        let res = terminal.handle_command("/", "source line.sh");
+        assert!(res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "tested");
+        terminal.handle_command("/", "set +e");
+
+        // Without it, later lines still run
+        terminal.handle_command("/", "echo 'cat missing' > lax.sh");
+        terminal.handle_command("/", "echo 'echo done' >> lax.sh");
+        let res = terminal.handle_command("/", "source lax.sh");
+        assert!(!res.is_error());
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "done");
+
+        // Errors report the line they happened on
+        terminal.handle_command("/", "echo '# header' > bad.sh");
+        terminal.handle_command("/", "echo \"echo 'oops\" >> bad.sh");
+        terminal.handle_command("/", "echo 'echo after' >> bad.sh");
+        let res = terminal.handle_command("/", "source bad.sh");
+        assert!(res.is_error());
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "bad.sh:2: unmatched '"
+        );
+
+        // Non-executable files still can't be run directly
+        let res = terminal.handle_command("/", "./lax.sh");
+        assert!(res.is_error());
+        assert!(get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("permission denied"));
+    }
+
+    #[test]
+    fn test_glob_expansion() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+        let echo = |terminal: &mut Terminal, path: &str, cmd: &str| {
+            get_stdout_text(&terminal.handle_command(path, cmd)).unwrap_or_default()
+        };
+
+        terminal.handle_command("/", "mkdir notes");
+        terminal.handle_command("/", "touch notes/b.txt notes/a.txt notes/c.md");
+
+        assert_eq!(
+            echo(&mut terminal, "/", "echo notes/*.txt"),
+            "notes/a.txt notes/b.txt"
+        );
+        assert_eq!(echo(&mut terminal, "/notes", "echo *"), "a.txt b.txt c.md");
+        assert_eq!(echo(&mut terminal, "/", "echo .zsh*"), ".zshrc");
+
+        // Quoted or unmatched patterns stay literal
+        assert_eq!(echo(&mut terminal, "/notes", "echo '*'.txt"), "*.txt");
+        assert_eq!(echo(&mut terminal, "/notes", "echo \\*"), "*");
+        assert_eq!(echo(&mut terminal, "/notes", "echo *.rs"), "*.rs");
+
+        // Globs work with any command that takes multiple targets
+        let res = terminal.handle_command("/", "rm notes/*.txt");
+        assert!(!res.is_error());
+        assert_eq!(echo(&mut terminal, "/notes", "echo *"), "c.md");
+    }
+
+    #[test]
+    fn test_history_expansion() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        terminal.handle_command("/", "echo one");
+        terminal.handle_command("/", "echo two");
+
+        let res = terminal.handle_command("/", "!!");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "two");
+        let res = terminal.handle_command("/", "!1 && !-2");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "one\ntwo");
+        let res = terminal.handle_command("/", "^one^three");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "three\ntwo");
+
+        // The expanded command is what's remembered
+        assert_eq!(
+            terminal.history.iter().collect::<Vec<_>>(),
+            vec![
+                "echo one",
+                "echo two",
+                "echo two",
+                "echo one && echo two",
+                "echo three && echo two"
+            ]
+        );
+
+        // Failed expansions report the event and aren't remembered
+        let res = terminal.handle_command("/", "!nope");
+        assert!(res.is_error());
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "zsh: event not found: !nope"
+        );
+        let res = terminal.handle_command("/", "^four^five");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "zsh: substitution failed"
+        );
+        assert_eq!(terminal.history.len(), 5);
+    }
+
+    #[test]
+    fn test_reverse_search() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+        for cmd in [
+            "cat thanks.txt",
+            "ls blog",
+            "echo cat",
+            "cat thanks.txt",
+            "pwd",
+        ] {
+            terminal.handle_command("/", cmd);
+        }
+
+        assert_eq!(
+            terminal.handle_reverse_search("cat"),
+            vec!["cat thanks.txt", "echo cat"]
+        );
+        assert_eq!(terminal.handle_reverse_search("s b"), vec!["ls blog"]);
+        assert!(terminal.handle_reverse_search("vim").is_empty());
+        assert!(terminal.handle_reverse_search("").is_empty());
+    }
+
+    #[test]
+    fn test_find_exec() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+        terminal.handle_command("/", "mkdir notes");
+        terminal.handle_command("/", "echo one > notes/a.txt");
+        terminal.handle_command("/", "echo two > notes/b.txt");
+
+        let res = terminal.handle_command("/", "find notes -name '*.txt' > found.txt");
+        assert!(!res.is_error());
+        let res = terminal.handle_command("/", "cat found.txt");
+        assert_eq!(
+            get_stdout_text(&res),
+            Some("/notes/a.txt\n/notes/b.txt\n".to_string())
+        );
+
+        let res = terminal.handle_command("/", "find notes -type f -exec cat {} \\;");
+        assert!(!res.is_error());
+        assert_eq!(get_stdout_text(&res), Some("one\ntwo\n".to_string()));
+        terminal.handle_command("/", "find /notes -name a.txt -exec rm {} +");
+        let res = terminal.handle_command("/", "find notes -type f");
+        assert_eq!(get_stdout_text(&res), Some("/notes/b.txt".to_string()));
+    }
+
+    #[test]
+    fn test_text_pipeline() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+        terminal.handle_command("/", "echo b > words.txt");
+        terminal.handle_command("/", "echo a >> words.txt");
+        terminal.handle_command("/", "echo b >> words.txt");
+
+        let res = terminal.handle_command("/", "sort words.txt | uniq -c | sort -rn | head -n 1");
+        assert_eq!(get_stdout_text(&res), Some("      2 b".to_string()));
+        let res = terminal.handle_command("/", "cat words.txt | tr a-z A-Z | tail -1");
+        assert_eq!(get_stdout_text(&res), Some("B".to_string()));
+        let res = terminal.handle_command("/", "cat words.txt | wc -l");
+        assert_eq!(get_stdout_text(&res), Some("3".to_string()));
+
+        let completions = terminal.handle_start_tab("/", "un");
+        assert!(completions
+            .iter()
+            .any(|item| item.completion_text == "uniq"));
+    }
+
+    #[test]
+    fn test_grep_pipeline() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        // Output to the terminal is highlighted, so check what lands in files
+        terminal.handle_command("/", "echo hello | grep -o ell > found.txt");
+        let res = terminal.handle_command("/", "cat found.txt");
+        assert_eq!(get_stdout_text(&res), Some("ell\n".to_string()));
+        let res = terminal.handle_command("/", "grep -n EDITOR .zshrc > found.txt");
+        assert!(!res.is_error());
+        let res = terminal.handle_command("/", "cat found.txt");
+        assert!(get_stdout_text(&res).is_some_and(|text| text.contains(":export EDITOR=vim")));
+
+        let res = terminal.handle_command("/", "grep nothing-here .zshrc");
+        assert!(res.is_error());
+    }
+
+    #[test]
+    fn test_help_man_and_usage() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        let res = terminal.handle_command("/", "help");
+        let help = get_stdout_text(&res).unwrap();
+        assert!(help.contains("    grep      print lines that match patterns"));
+
+        let res = terminal.handle_command("/", "man ls");
+        let page = get_stdout_text(&res).unwrap();
+        assert!(page.starts_with("LS(1)"));
+        assert!(page.contains("       -l     use a long listing format"));
+        let res = terminal.handle_command("/", "man nope");
+        assert!(res.is_error());
+        assert_eq!(
+            get_stderr_text(&res),
+            Some("No manual entry for nope".to_string())
+        );
+
+        // --help is uniform, even for builtins, but not for echo
+        let res = terminal.handle_command("/", "ls -l --help");
+        assert!(!res.is_error());
+        let usage = get_stdout_text(&res).unwrap();
+        assert!(usage.starts_with("usage: ls [-ahlrSt] [file ...]"));
+        let res = terminal.handle_command("/", "history --help");
+        assert!(get_stdout_text(&res).unwrap().starts_with("usage: history"));
+        let res = terminal.handle_command("/", "echo --help");
+        assert_eq!(get_stdout_text(&res), Some("--help".to_string()));
+
+        let res = terminal.handle_command("/", "sort -x");
+        assert_eq!(
+            get_stderr_text(&res),
+            Some(
+                "sort: invalid option -- 'x'\nThis version of sort only supports options 'r', 'n' and 'u'"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_paged_output() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        let res = terminal.handle_command("/", "less .zshrc");
+        let paged = res.pager_text(PAGE_LINES).unwrap_or_default();
+        assert!(paged.contains("EDITOR"));
+        let res = terminal.handle_command("/", "less .zshrc | head -n 1");
+        assert!(res.pager_text(PAGE_LINES).is_none());
+        let res = terminal.handle_command("/", "more .zshrc");
+        assert_eq!(res.pager_text(PAGE_LINES), Some(paged));
+        let res = terminal.handle_command("/", "man more");
+        assert!(res.pager_text(PAGE_LINES).is_some());
+        let res = terminal.handle_command("/", "man more | head -n 1");
+        assert!(res.pager_text(PAGE_LINES).is_none());
+        // Coloured output is paged as its plain text
+        let res = terminal.handle_command("/", "neofetch | less");
+        assert!(!res
+            .pager_text(PAGE_LINES)
+            .unwrap_or_default()
+            .contains("<span"));
+
+        // Output taller than a page is paged unless it's going somewhere else
+        let res = terminal.handle_command("/", "history");
+        assert!(res.pager_text(PAGE_LINES).is_none());
+        for i in 0..PAGE_LINES {
+            terminal.handle_command("/", &format!("echo {i}"));
+        }
+        let res = terminal.handle_command("/", "history");
+        assert!(res.pager_text(PAGE_LINES).is_some());
+        let res = terminal.handle_command("/", "history > history.txt");
+        assert!(res.pager_text(PAGE_LINES).is_none());
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        let res = terminal.handle_command("/", "ln -s thanks.txt ty && ln -s /blog posts");
+        assert!(!res.is_error());
+        let res = terminal.handle_command("/", "readlink ty posts thanks.txt");
+        assert!(res.is_error());
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "thanks.txt\n/blog"
+        );
+        let res = terminal.handle_command("/", "cat ty");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .starts_with("Thank you"));
+
+        let res = terminal.handle_command("/", "ls -l ty posts");
+        let listing = get_stdout_text(&res).unwrap_or_default();
+        assert!(listing.contains("lrwxrwxrwx"));
+        assert!(listing.contains(" posts -> /blog\n"));
+        assert!(listing.ends_with(" ty -> thanks.txt"));
+        let res = terminal.handle_command("/", "ls posts");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "nav.rs\ntest-post"
+        );
+
+        let res = terminal.handle_command("/", "ln -s thanks.txt ty");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "ln: failed to create symbolic link 'ty': File exists"
+        );
+        let res = terminal.handle_command("/", "ln thanks.txt hard");
+        assert!(res.is_error());
+
+        // cd keeps the link in the path, and pwd -P shows where it really is
+        let res = terminal.handle_command("/", "cd posts");
+        assert!(matches!(res, CommandRes::Redirect(ref url) if url == "/posts"));
+        let res = terminal.handle_command("/posts", "pwd");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "/posts");
+        let res = terminal.handle_command("/posts", "pwd -P");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "/blog");
+        let res = terminal.handle_command("/posts", "cat test-post/test-post.md");
+        assert!(!get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("No such file"));
+        let res = terminal.handle_command("/posts/test-post", "cd ..");
+        assert!(matches!(res, CommandRes::Redirect(ref url) if url == "/posts"));
+
+        // Dangling links and loops
+        terminal.handle_command("/", "ln -s nowhere dangling; ln -s loop loop");
+        let res = terminal.handle_command("/", "ls dangling");
+        assert_eq!(get_stdout_text(&res).unwrap_or_default(), "dangling");
+        let res = terminal.handle_command("/", "cat dangling loop");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "cat: dangling: No such file or directory\ncat: loop: Too many levels of symbolic links"
+        );
+        let res = terminal.handle_command("/", "cd loop");
+        assert!(get_stderr_text(&res)
+            .unwrap_or_default()
+            .contains("too many levels of symbolic links"));
+
+        // rm removes the link, not what it points at
+        terminal.handle_command("/", "rm ty posts");
+        assert!(vfs_file_exists(&mut terminal, "/thanks.txt"));
+        assert!(vfs_dir_exists(&mut terminal, "/blog"));
+        let res = terminal.handle_command("/", "readlink ty");
+        assert!(res.is_error());
+    }
+
+    #[test]
+    fn test_disk_quota() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+        terminal.vfs.set_quota(Quota {
+            max_bytes: 64,
+            max_nodes: 3,
+            max_depth: 8,
+        });
+
+        terminal.handle_command("/", "mkdir a; touch a/one; touch two");
+        let res = terminal.handle_command("/", "touch three");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "touch: cannot touch 'three': Disk quota exceeded"
+        );
+        let res = terminal.handle_command("/", "mkdir b");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "mkdir: cannot create directory 'b': Disk quota exceeded"
+        );
+        terminal.handle_command("/", "rm two");
+        let res = terminal.handle_command("/", "cp -r a b");
+        assert!(get_stderr_text(&res)
+            .unwrap_or_default()
+            .ends_with("Disk quota exceeded"));
+
+        let res = terminal.handle_command("/", "df -i");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .ends_with("localStorage        3     3     0  100% /"));
+    }
+
+    #[test]
+    fn test_proc() {
+        let mut terminal = Terminal::new(&[], None);
+        let mut stdout = |command: &str| {
+            get_stdout_text(&terminal.handle_command("/", command)).unwrap_or_default()
+        };
+
+        assert_eq!(stdout("cat /proc/42/cmdline"), "blog-renderer");
+        assert_eq!(
+            stdout("cat /proc/1/status"),
+            "Name:\tleptos-server\nState:\tS (sleeping)\nPid:\t1\nPPid:\t0\n"
+        );
+        assert!(stdout("cat /proc/self/status").contains("State:\tR (running)"));
+        assert!(stdout("cat /proc/version").contains(env!("CARGO_PKG_VERSION")));
+        assert!(stdout("ls /proc").contains("self"));
+
+        // The environment is read as it is when the file is read
+        assert!(stdout("cat /proc/self/environ").contains("SITE=hansbaker.com\n"));
+        assert!(!stdout("cat /proc/self/environ").contains("EDITOR=nano"));
+        assert!(stdout("EDITOR=nano cat /proc/self/environ").contains("EDITOR=nano\n"));
+        assert!(!stdout("cat /proc/self/environ").contains("EDITOR=nano"));
+        stdout("export GREETING=hi");
+        assert!(stdout("cat /proc/self/environ").contains("GREETING=hi\n"));
+        assert!(!stdout("unset GREETING; cat /proc/self/environ").contains("GREETING"));
+
+        // Copies keep what the file said when it was copied
+        stdout("cp /proc/version version");
+        assert_eq!(stdout("cat version"), stdout("cat /proc/version"));
+
+        let res = terminal.handle_command("/", "echo 0 > /proc/uptime");
+        assert!(get_stderr_text(&res).is_some());
+        let res = terminal.handle_command("/", "touch /proc/new");
+        assert!(get_stderr_text(&res).is_some());
+    }
+
+    #[test]
+    fn test_permissions_follow_user() {
+        let blog_posts = vec!["test-post".to_string()];
+        let mut terminal = Terminal::new(&blog_posts, None);
+
+        terminal.handle_command("/", "echo secret > diary; chmod 600 diary");
+        let res = terminal.handle_command("/", "ls -l diary");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .starts_with("-rw-------  0 user   user        7 "));
+
+        // Other users are held to the mode, for one command or until $USER changes back
+        let res = terminal.handle_command("/", "USER=hans cat diary");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "cat: diary: Permission denied"
+        );
+        assert_eq!(
+            get_stdout_text(&terminal.handle_command("/", "cat diary")).unwrap_or_default(),
+            "secret\n"
+        );
+        terminal.handle_command("/", "export USER=hans");
+        let res = terminal.handle_command("/", "rm diary");
+        assert!(get_stderr_text(&res).is_some());
+        let res = terminal.handle_command("/", "echo more >> diary");
+        assert!(get_stderr_text(&res).is_some());
+
+        // root can do anything but touch the site's own files
+        terminal.handle_command("/", "USER=root");
+        let res = terminal.handle_command("/", "chown hans diary && stat diary");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .contains("Uid: hans  Gid: user"));
+        let res = terminal.handle_command("/", "chmod 777 /blog");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "chmod: changing permissions of '/blog': Operation not permitted"
+        );
+
+        // New files follow the umask
+        terminal.handle_command("/", "USER=user; umask 077; touch private");
+        let res = terminal.handle_command("/", "stat private");
+        assert!(get_stdout_text(&res)
+            .unwrap_or_default()
+            .contains("Access: (0600/-rw-------)  Uid: user  Gid: user"));
+
+        // Entering a directory needs execute permission, not read
+        terminal.handle_command("/", "mkdir locked; chmod 0 locked");
+        let res = terminal.handle_command("/", "cd locked");
+        assert_eq!(
+            get_stderr_text(&res).unwrap_or_default(),
+            "cd: permission denied: locked"
+        );
+        terminal.handle_command("/", "chmod 100 locked");
+        let res = terminal.handle_command("/", "cd locked");
+        assert!(matches!(res, CommandRes::Redirect(path) if path == "/locked"));
+
+        // umask changes the shell's own state, so is a builtin
+        let res = terminal.handle_command("/", "which umask chmod");
+        assert_eq!(
+            get_stdout_text(&res).unwrap_or_default(),
+            "umask: shell builtin\n/bin/chmod"
+        );
+    }
 }
@@ -1,5 +1,8 @@
 #![allow(dead_code)]
 
+use std::sync::Arc;
+
+use super::components::{StderrText, StdoutText};
 use super::vfs::VirtualFilesystem;
 use indextree::NodeId;
 use leptos::prelude::*;
@@ -27,6 +30,7 @@ pub enum CommandRes {
         stdout_view: Option<ChildrenFn>, // stdout content for display (only set if is_tty)
         stdout_text: Option<String>,     // stdout for piping
         stderr_text: Option<String>,     // stderr text (Header converts to view)
+        paged: bool,                     // true if stdout should open in the pager (less)
     },
     Redirect(String),
 }
@@ -39,6 +43,7 @@ impl CommandRes {
             stdout_view: None,
             stdout_text: None,
             stderr_text: None,
+            paged: false,
         }
     }
 
@@ -84,6 +89,106 @@ impl CommandRes {
         self
     }
 
+    /// Open stdout in the pager rather than the scrollback
+    pub fn with_pager(mut self) -> Self {
+        if let Self::Output { paged, .. } = &mut self {
+            *paged = true
+        }
+        self
+    }
+
+    /// Show `line` before this result's output, as a shell does after history expansion
+    pub fn with_echo(self, line: String) -> Self {
+        if let Self::Redirect(_) = self {
+            return self;
+        }
+        let echo = Self::new().with_stdout_view(Arc::new(move || {
+            view! { <div class="whitespace-pre-wrap">{line.clone()}</div> }.into_any()
+        }));
+        Self::combine(vec![echo, self])
+    }
+
+    /// Merge the results of a command list (`a; b && c`) into one result.
+    /// Each command's stderr and stdout are rendered together, in the order the commands ran.
+    pub fn combine(results: Vec<CommandRes>) -> Self {
+        if results.len() == 1 {
+            return results.into_iter().next().expect("checked length");
+        }
+
+        let mut combined_err = false;
+        let mut combined_paged = false;
+        let mut views: Vec<ChildrenFn> = Vec::new();
+        let mut stdout_parts = Vec::new();
+
+        for res in results {
+            let Self::Output {
+                is_err,
+                stdout_view,
+                stdout_text,
+                stderr_text,
+                paged,
+            } = res
+            else {
+                continue;
+            };
+            // The list's status is that of the last command run
+            combined_err = is_err;
+            combined_paged |= paged;
+
+            if let Some(text) = stderr_text.filter(|text| !text.is_empty()) {
+                views.push(Arc::new(move || {
+                    view! { <StderrText text=text.clone() /> }.into_any()
+                }));
+            }
+            if let Some(view) = stdout_view {
+                views.push(view);
+            } else if let Some(text) = stdout_text.clone().filter(|text| !text.is_empty()) {
+                views.push(Arc::new(move || {
+                    view! { <StdoutText text=text.clone() /> }.into_any()
+                }));
+            }
+            stdout_parts.extend(stdout_text);
+        }
+
+        let mut combined = Self::new();
+        if combined_err {
+            combined = combined.with_error();
+        }
+        if !stdout_parts.is_empty() {
+            // Output that already ends its last line isn't followed by another newline
+            let mut stdout = String::new();
+            for (i, part) in stdout_parts.iter().enumerate() {
+                if i > 0 && !stdout.ends_with('\n') {
+                    stdout.push('\n');
+                }
+                stdout.push_str(part);
+            }
+            combined = combined.with_stdout_text(stdout);
+        }
+        if combined_paged {
+            combined = combined.with_pager();
+        }
+        if !views.is_empty() {
+            combined = combined.with_stdout_view(Arc::new(move || {
+                views.iter().map(|view| view()).collect_view().into_any()
+            }));
+        }
+        combined
+    }
+
+    /// The stdout to show in the pager: all of it for `less`, or output that is taller
+    /// than a page of `page_lines`
+    pub fn pager_text(&self, page_lines: usize) -> Option<&str> {
+        match self {
+            Self::Output {
+                stdout_text: Some(text),
+                paged,
+                ..
+            } if *paged || text.lines().count() > page_lines => Some(text),
+            _ => None,
+        }
+    }
+
     /// Check if this result represents an error
     pub fn is_error(&self) -> bool {
         match self {
@@ -96,6 +201,7 @@ impl CommandRes {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cmd {
     Help,
+    Man,
     Pwd,
     Ls,
     Cd,
@@ -117,6 +223,34 @@ pub enum Cmd {
     Uptime,
     Ps,
     Kill,
+    Export,
+    Unset,
+    Env,
+    Printenv,
+    Set,
+    Alias,
+    Unalias,
+    Source,
+    Grep,
+    Find,
+    Head,
+    Tail,
+    Wc,
+    Sort,
+    Uniq,
+    Cut,
+    Tr,
+    Tree,
+    Less,
+    More,
+    Ln,
+    Readlink,
+    Df,
+    Du,
+    Chmod,
+    Chown,
+    Umask,
+    Stat,
     Unknown,
 }
 
@@ -131,12 +265,16 @@ impl Cmd {
         vec![
             "help", "pwd", "ls", "cd", "cat", "clear", "cp", "date", "echo", "history", "mines",
             "mkdir", "mv", "rm", "touch", "which", "whoami", "neofetch", "uptime", "ps", "kill",
+            "export", "unset", "env", "printenv", "set", "alias", "unalias", "source", "grep",
+            "find", "head", "tail", "wc", "sort", "uniq", "cut", "tr", "tree", "man", "less",
+            "more", "ln", "readlink", "df", "du", "chmod", "chown", "umask", "stat",
         ]
     }
 
     pub fn from_str(s: &str) -> Option<Cmd> {
         match s {
             "help" => Some(Self::Help),
+            "man" => Some(Self::Man),
             "pwd" => Some(Self::Pwd),
             "ls" => Some(Self::Ls),
             "cd" => Some(Self::Cd),
@@ -158,6 +296,34 @@ impl Cmd {
             "uptime" => Some(Self::Uptime),
             "ps" => Some(Self::Ps),
             "kill" => Some(Self::Kill),
+            "export" => Some(Self::Export),
+            "unset" => Some(Self::Unset),
+            "env" => Some(Self::Env),
+            "printenv" => Some(Self::Printenv),
+            "set" => Some(Self::Set),
+            "alias" => Some(Self::Alias),
+            "unalias" => Some(Self::Unalias),
+            "source" | "." => Some(Self::Source),
+            "grep" => Some(Self::Grep),
+            "find" => Some(Self::Find),
+            "head" => Some(Self::Head),
+            "tail" => Some(Self::Tail),
+            "wc" => Some(Self::Wc),
+            "sort" => Some(Self::Sort),
+            "uniq" => Some(Self::Uniq),
+            "cut" => Some(Self::Cut),
+            "tr" => Some(Self::Tr),
+            "tree" => Some(Self::Tree),
+            "less" => Some(Self::Less),
+            "more" => Some(Self::More),
+            "ln" => Some(Self::Ln),
+            "readlink" => Some(Self::Readlink),
+            "df" => Some(Self::Df),
+            "du" => Some(Self::Du),
+            "chmod" => Some(Self::Chmod),
+            "chown" => Some(Self::Chown),
+            "umask" => Some(Self::Umask),
+            "stat" => Some(Self::Stat),
             _ => None,
         }
     }
@@ -166,18 +332,59 @@ impl Cmd {
     pub fn simulated_path(&self) -> Option<String> {
         match self {
             // Shell builtins don't have paths
-            Self::Pwd | Self::Cd | Self::Echo | Self::History => None,
+            Self::Pwd
+            | Self::Cd
+            | Self::Echo
+            | Self::History
+            | Self::Export
+            | Self::Unset
+            | Self::Set
+            | Self::Alias
+            | Self::Unalias
+            | Self::Umask
+            | Self::Source => None,
 
             // Core system utilities (typically in /bin)
-            Self::Ls | Self::Cat | Self::Cp | Self::Mv | Self::Rm | Self::MkDir | Self::Touch => {
-                Some(format!("/bin/{}", self.as_str()))
-            }
+            Self::Ls
+            | Self::Cat
+            | Self::Cp
+            | Self::Mv
+            | Self::Rm
+            | Self::MkDir
+            | Self::Touch
+            | Self::Ln
+            | Self::Chmod
+            | Self::Chown
+            | Self::Df => Some(format!("/bin/{}", self.as_str())),
 
-            // System administration and process tools (typically in /usr/bin)
-            Self::Ps | Self::Kill | Self::WhoAmI | Self::Which | Self::Uptime => {
+            // Directory listing utilities (typically in /usr/bin)
+            Self::Tree | Self::Readlink | Self::Du | Self::Stat => {
                 Some(format!("/usr/bin/{}", self.as_str()))
             }
 
+            // Pagers (typically in /usr/bin)
+            Self::Less | Self::More => Some(format!("/usr/bin/{}", self.as_str())),
+
+            // System administration and process tools (typically in /usr/bin)
+            Self::Ps
+            | Self::Kill
+            | Self::WhoAmI
+            | Self::Which
+            | Self::Uptime
+            | Self::Env
+            | Self::Printenv => Some(format!("/usr/bin/{}", self.as_str())),
+
+            // Search and text processing utilities (typically in /usr/bin)
+            Self::Grep
+            | Self::Find
+            | Self::Head
+            | Self::Tail
+            | Self::Wc
+            | Self::Sort
+            | Self::Uniq
+            | Self::Cut
+            | Self::Tr => Some(format!("/usr/bin/{}", self.as_str())),
+
             // Terminal/display utilities (typically in /usr/bin)
             Self::Clear | Self::Date => Some(format!("/usr/bin/{}", self.as_str())),
 
@@ -185,7 +392,7 @@ impl Cmd {
             Self::Neofetch | Self::Mines => Some(format!("/usr/local/bin/{}", self.as_str())),
 
             // Documentation/help (typically in /usr/bin)
-            Self::Help => Some(format!("/usr/bin/{}", self.as_str())),
+            Self::Help | Self::Man => Some(format!("/usr/bin/{}", self.as_str())),
 
             // System utilities (typically in /usr/bin)
             Self::Sudo => Some(format!("/usr/bin/{}", self.as_str())),
@@ -199,6 +406,7 @@ impl Cmd {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Help => "help",
+            Self::Man => "man",
             Self::Pwd => "pwd",
             Self::Ls => "ls",
             Self::Cd => "cd",
@@ -220,68 +428,119 @@ impl Cmd {
             Self::Uptime => "uptime",
             Self::Ps => "ps",
             Self::Kill => "kill",
+            Self::Export => "export",
+            Self::Unset => "unset",
+            Self::Env => "env",
+            Self::Printenv => "printenv",
+            Self::Set => "set",
+            Self::Alias => "alias",
+            Self::Unalias => "unalias",
+            Self::Source => "source",
+            Self::Grep => "grep",
+            Self::Find => "find",
+            Self::Head => "head",
+            Self::Tail => "tail",
+            Self::Wc => "wc",
+            Self::Sort => "sort",
+            Self::Uniq => "uniq",
+            Self::Cut => "cut",
+            Self::Tr => "tr",
+            Self::Tree => "tree",
+            Self::Less => "less",
+            Self::More => "more",
+            Self::Ln => "ln",
+            Self::Readlink => "readlink",
+            Self::Df => "df",
+            Self::Du => "du",
+            Self::Chmod => "chmod",
+            Self::Chown => "chown",
+            Self::Umask => "umask",
+            Self::Stat => "stat",
             Self::Unknown => "unknown",
         }
     }
 
     /// Returns true if this command is a shell builtin
     pub fn is_builtin(&self) -> bool {
-        matches!(self, Self::Pwd | Self::Cd | Self::Echo | Self::History)
+        matches!(
+            self,
+            Self::Pwd
+                | Self::Cd
+                | Self::Echo
+                | Self::History
+                | Self::Export
+                | Self::Unset
+                | Self::Set
+                | Self::Alias
+                | Self::Unalias
+                | Self::Umask
+                | Self::Source
+        )
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum CmdAlias {
-    Ll,
-    La,
-    H,
-}
+/// Helpers for running commands directly in tests
+#[cfg(test)]
+pub mod test_helpers {
+    use indextree::NodeId;
 
-impl CmdAlias {
-    pub fn all() -> Vec<CmdAlias> {
-        vec![CmdAlias::Ll, CmdAlias::La, CmdAlias::H]
-    }
+    use super::{CommandRes, VfsCommand};
+    use crate::app::terminal::vfs::{FileContent, VirtualFilesystem};
 
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            CmdAlias::Ll => "ll",
-            CmdAlias::La => "la",
-            CmdAlias::H => "h",
+    /// A filesystem holding `files`, given as (path from the root, contents), along with the
+    /// directories they're in
+    pub fn vfs_with_files(files: &[(&str, &str)]) -> (VirtualFilesystem, NodeId) {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        for (path, content) in files {
+            let (dirs, name) = path.rsplit_once('/').unwrap_or(("", path));
+            let mut dir = root;
+            for dir_name in dirs.split('/').filter(|name| !name.is_empty()) {
+                dir = match vfs.resolve_path(dir, dir_name) {
+                    Ok(existing) => existing,
+                    Err(_) => vfs.create_directory(dir, dir_name).unwrap(),
+                };
+            }
+            vfs.create_file(dir, name, FileContent::Dynamic(content.to_string()))
+                .unwrap();
         }
+        (vfs, root)
     }
 
-    pub fn expand(&self, args: &str) -> String {
-        match self {
-            CmdAlias::Ll => {
-                if args.is_empty() {
-                    "ls -la".to_string()
-                } else {
-                    format!("ls -la{args}")
-                }
-            }
-            CmdAlias::La => {
-                if args.is_empty() {
-                    "ls -a".to_string()
-                } else {
-                    format!("ls -a{args}")
-                }
-            }
-            CmdAlias::H => {
-                if args.is_empty() {
-                    "history".to_string()
-                } else {
-                    format!("history{args}")
-                }
-            }
+    /// Run `command` in `dir`, as if its output were piped
+    pub fn run(
+        command: &dyn VfsCommand,
+        vfs: &mut VirtualFilesystem,
+        dir: NodeId,
+        args: &[&str],
+        stdin: Option<&str>,
+    ) -> CommandRes {
+        command.execute(vfs, dir, args.to_vec(), stdin, false)
+    }
+
+    /// What the command printed to stdout, or nothing
+    pub fn stdout(res: &CommandRes) -> &str {
+        match res {
+            CommandRes::Output {
+                stdout_text: Some(text),
+                ..
+            } => text,
+            _ => "",
         }
     }
 
-    pub fn from_str(s: &str) -> Option<CmdAlias> {
-        match s {
-            "ll" => Some(CmdAlias::Ll),
-            "la" => Some(CmdAlias::La),
-            "h" => Some(CmdAlias::H),
-            _ => None,
+    /// What the command printed to stdout, or to stderr when it printed nothing there
+    pub fn output(res: &CommandRes) -> &str {
+        match res {
+            CommandRes::Output {
+                stdout_text,
+                stderr_text,
+                ..
+            } => stdout_text
+                .as_ref()
+                .or(stderr_text.as_ref())
+                .map_or("", |text| text),
+            CommandRes::Redirect(_) => panic!("command should not redirect"),
         }
     }
 }
@@ -22,6 +22,18 @@ impl TextContent for String {
     }
 }
 
+/// stderr text with the terminal's error styling
+#[component]
+pub fn StderrText(text: String) -> impl IntoView {
+    view! { <div class="text-red whitespace-pre-wrap">{text}</div> }
+}
+
+/// stdout text, shown as it is. Commands with markup to show use a stdout view instead.
+#[component]
+pub fn StdoutText(text: String) -> impl IntoView {
+    view! { <div class="whitespace-pre-wrap">{text}</div> }
+}
+
 #[component]
 pub fn ColumnarView<T, F>(items: Vec<T>, render_func: F) -> impl IntoView
 where
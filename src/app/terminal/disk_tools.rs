@@ -0,0 +1,345 @@
+use indextree::NodeId;
+
+use super::command::{Cmd, CommandRes, VfsCommand};
+use super::fs_tools::parse_multitarget;
+use super::vfs::{VfsNodeType, VirtualFilesystem};
+
+/// What `df` calls the filesystem, since that's where user files are kept between visits
+const FILESYSTEM_NAME: &str = "localStorage";
+
+/// A size as `-h` prints it, like `812`, `1.5K` or `12M`. Like coreutils, this rounds up.
+pub fn human_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = "";
+    for next_unit in ["K", "M", "G", "T"] {
+        size /= 1024.0;
+        unit = next_unit;
+        if size < 1024.0 {
+            break;
+        }
+    }
+
+    if size < 10.0 {
+        format!("{:.1}{unit}", (size * 10.0).ceil() / 10.0)
+    } else {
+        format!("{}{unit}", size.ceil())
+    }
+}
+
+/// Percentage of `total` that `used` is, rounded up as `df` does
+fn percent(used: u64, total: u64) -> u64 {
+    if total == 0 {
+        0
+    } else {
+        (used * 100).div_ceil(total)
+    }
+}
+
+pub struct DfCommand;
+
+impl DfCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for DfCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, targets) = parse_multitarget(args);
+
+        // Validate options
+        let usage = Cmd::Df.usage().expect("df has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        let human = options.contains(&'h');
+        let inodes = options.contains(&'i');
+
+        // Everything is on the one filesystem, so operands only need to exist
+        let missing = targets
+            .iter()
+            .filter(|target| vfs.resolve_path(current_dir, target).is_err())
+            .map(|target| format!("df: {target}: No such file or directory"))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() && missing.len() == targets.len() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr(missing.join("\n"));
+        }
+
+        let quota = vfs.quota();
+        let used = vfs.usage();
+        let table = if inodes {
+            let total = quota.max_nodes as u64;
+            let used = used.nodes as u64;
+            format!(
+                "{:<14} {:>6} {:>5} {:>5} {:>5} Mounted on\n{:<14} {:>6} {:>5} {:>5} {:>4}% /",
+                "Filesystem",
+                "Inodes",
+                "IUsed",
+                "IFree",
+                "IUse%",
+                FILESYSTEM_NAME,
+                total,
+                used,
+                total.saturating_sub(used),
+                percent(used, total)
+            )
+        } else if human {
+            let total = quota.max_bytes;
+            format!(
+                "{:<14} {:>5} {:>5} {:>5} {:>4} Mounted on\n{:<14} {:>5} {:>5} {:>5} {:>3}% /",
+                "Filesystem",
+                "Size",
+                "Used",
+                "Avail",
+                "Use%",
+                FILESYSTEM_NAME,
+                human_size(total),
+                human_size(used.bytes),
+                human_size(total.saturating_sub(used.bytes)),
+                percent(used.bytes, total)
+            )
+        } else {
+            let total = quota.max_bytes.div_ceil(1024);
+            let used = used.bytes.div_ceil(1024);
+            format!(
+                "{:<14} {:>9} {:>5} {:>9} {:>4} Mounted on\n{:<14} {:>9} {:>5} {:>9} {:>3}% /",
+                "Filesystem",
+                "1K-blocks",
+                "Used",
+                "Available",
+                "Use%",
+                FILESYSTEM_NAME,
+                total,
+                used,
+                total.saturating_sub(used),
+                percent(used, total)
+            )
+        };
+
+        let mut result = CommandRes::new().with_stdout_text(table);
+        if !missing.is_empty() {
+            result = result.with_error().with_stderr(missing.join("\n"));
+        }
+        result
+    }
+}
+
+pub struct DuCommand;
+
+impl DuCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for DuCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, mut targets) = parse_multitarget(args);
+
+        // Validate options
+        let usage = Cmd::Du.usage().expect("du has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        let walk = DuWalk {
+            all: options.contains(&'a'),
+            summarize: options.contains(&'s'),
+            human: options.contains(&'h'),
+        };
+        if walk.all && walk.summarize {
+            let error_msg = "du: cannot both summarize and show all entries";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+
+        if targets.is_empty() {
+            targets = vec!["."];
+        }
+
+        let mut lines = Vec::new();
+        let mut errors = Vec::new();
+        for target in targets {
+            // Like `du -P`, a link is measured rather than followed
+            match vfs.resolve_path_nofollow(current_dir, target) {
+                Ok(node_id) => {
+                    walk.walk(vfs, node_id, target, 0, &mut lines, &mut errors);
+                }
+                Err(_) => errors.push(format!(
+                    "du: cannot access '{target}': No such file or directory"
+                )),
+            }
+        }
+
+        let mut result = CommandRes::new();
+        if !lines.is_empty() {
+            result = result.with_stdout_text(lines.join("\n"));
+        }
+        if !errors.is_empty() {
+            result = result.with_error().with_stderr(errors.join("\n"));
+        }
+        result
+    }
+}
+
+/// Options for one `du` run
+struct DuWalk {
+    all: bool,
+    summarize: bool,
+    human: bool,
+}
+
+impl DuWalk {
+    /// Add the lines for `node_id` and everything below it, children first, returning its size.
+    /// Sizes are apparent sizes: what the files hold, not the blocks they'd take up.
+    fn walk(
+        &self,
+        vfs: &VirtualFilesystem,
+        node_id: NodeId,
+        path: &str,
+        depth: usize,
+        lines: &mut Vec<String>,
+        errors: &mut Vec<String>,
+    ) -> u64 {
+        let Some(node) = vfs.get_node(node_id) else {
+            return 0;
+        };
+
+        let size = match node.node_type {
+            VfsNodeType::Directory => match vfs.list_directory(node_id) {
+                Ok(entries) => entries
+                    .iter()
+                    .map(|entry| {
+                        let child_path = if path.ends_with('/') {
+                            format!("{path}{}", entry.name)
+                        } else {
+                            format!("{path}/{}", entry.name)
+                        };
+                        self.walk(vfs, entry.node_id, &child_path, depth + 1, lines, errors)
+                    })
+                    .sum(),
+                Err(_) => {
+                    errors.push(format!(
+                        "du: cannot read directory '{path}': Permission denied"
+                    ));
+                    0
+                }
+            },
+            VfsNodeType::File { .. } | VfsNodeType::Link { .. } => node.size(),
+        };
+
+        // Operands are always listed; below them, directories unless -s, and files with -a
+        let listed = depth == 0 || (!self.summarize && (node.is_directory() || self.all));
+        if listed {
+            let shown = if self.human {
+                human_size(size)
+            } else {
+                size.div_ceil(1024).to_string()
+            };
+            lines.push(format!("{shown}\t{path}"));
+        }
+
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::terminal::command::test_helpers::{run, stdout, vfs_with_files};
+    use crate::app::terminal::vfs::Quota;
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1500), "1.5K");
+        assert_eq!(human_size(20 * 1024 + 1), "21K");
+        assert_eq!(human_size(1024 * 1024), "1.0M");
+    }
+
+    #[test]
+    fn test_df() {
+        let (mut vfs, root) = vfs_with_files(&[
+            ("notes/a.txt", &"a".repeat(1500)),
+            ("notes/b.txt", &"b".repeat(600)),
+        ]);
+        let notes = vfs.resolve_path(root, "notes").unwrap();
+        vfs.set_quota(Quota {
+            max_bytes: 10 * 1024,
+            max_nodes: 10,
+            max_depth: 4,
+        });
+
+        let res = run(&DfCommand::new(), &mut vfs, notes, &[], None);
+        assert_eq!(
+            stdout(&res),
+            "Filesystem     1K-blocks  Used Available Use% Mounted on\n\
+             localStorage          10     3         7  30% /"
+        );
+        let res = run(&DfCommand::new(), &mut vfs, notes, &["-h"], None);
+        assert_eq!(
+            stdout(&res).lines().nth(1),
+            Some("localStorage     10K  2.1K  8.0K  21% /")
+        );
+        let res = run(&DfCommand::new(), &mut vfs, notes, &["-i"], None);
+        assert_eq!(
+            stdout(&res).lines().nth(1),
+            Some("localStorage       10     3     7   30% /")
+        );
+
+        let res = run(&DfCommand::new(), &mut vfs, notes, &["missing"], None);
+        assert!(res.is_error());
+        let res = run(&DfCommand::new(), &mut vfs, notes, &["-x"], None);
+        assert!(res.is_error());
+    }
+
+    #[test]
+    fn test_du() {
+        let (mut vfs, root) = vfs_with_files(&[
+            ("notes/a.txt", &"a".repeat(1500)),
+            ("notes/b.txt", &"b".repeat(600)),
+        ]);
+        let notes = vfs.resolve_path(root, "notes").unwrap();
+        vfs.create_directory(notes, "empty").unwrap();
+
+        let res = run(&DuCommand::new(), &mut vfs, root, &["notes"], None);
+        assert_eq!(stdout(&res), "0\tnotes/empty\n3\tnotes");
+        let res = run(&DuCommand::new(), &mut vfs, notes, &["-a"], None);
+        assert_eq!(stdout(&res), "2\t./a.txt\n1\t./b.txt\n0\t./empty\n3\t.");
+        let res = run(
+            &DuCommand::new(),
+            &mut vfs,
+            root,
+            &["-sh", "notes", "notes/a.txt"],
+            None,
+        );
+        assert_eq!(stdout(&res), "2.1K\tnotes\n1.5K\tnotes/a.txt");
+
+        let res = run(&DuCommand::new(), &mut vfs, root, &["missing"], None);
+        assert!(res.is_error());
+        let res = run(&DuCommand::new(), &mut vfs, root, &["-as"], None);
+        assert!(res.is_error());
+    }
+}
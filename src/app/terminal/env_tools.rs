@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+
+use super::command::{Command, CommandRes};
+
+/// Shell variable names are a letter or underscore followed by letters, digits or underscores
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn format_vars<'a>(vars: impl Iterator<Item = (&'a String, &'a String)>) -> String {
+    let mut lines = vars
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.join("\n")
+}
+
+pub struct ExportCommand<'a> {
+    env_vars: &'a mut HashMap<String, String>,
+    shell_vars: &'a mut HashMap<String, String>,
+}
+
+impl<'a> ExportCommand<'a> {
+    pub fn new(
+        env_vars: &'a mut HashMap<String, String>,
+        shell_vars: &'a mut HashMap<String, String>,
+    ) -> Self {
+        Self {
+            env_vars,
+            shell_vars,
+        }
+    }
+
+    // Takes self by value: exporting needs mutable access to the terminal's variables,
+    // so this can't go through the immutable Command trait
+    pub fn execute(self, args: Vec<&str>) -> CommandRes {
+        if args.is_empty() || args == ["-p"] {
+            return CommandRes::new().with_stdout_text(format_vars(self.env_vars.iter()));
+        }
+
+        let mut stderr_parts = Vec::new();
+        for arg in args {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg, None),
+            };
+            if !is_valid_name(name) {
+                stderr_parts.push(format!("export: not an identifier: {name}"));
+                continue;
+            }
+
+            // `export NAME` promotes an existing shell variable
+            let shell_value = self.shell_vars.remove(name);
+            let value = value
+                .or(shell_value)
+                .or_else(|| self.env_vars.get(name).cloned())
+                .unwrap_or_default();
+            self.env_vars.insert(name.to_string(), value);
+        }
+
+        if stderr_parts.is_empty() {
+            CommandRes::new()
+        } else {
+            CommandRes::new()
+                .with_error()
+                .with_stderr(stderr_parts.join("\n"))
+        }
+    }
+}
+
+pub struct UnsetCommand<'a> {
+    env_vars: &'a mut HashMap<String, String>,
+    shell_vars: &'a mut HashMap<String, String>,
+}
+
+impl<'a> UnsetCommand<'a> {
+    pub fn new(
+        env_vars: &'a mut HashMap<String, String>,
+        shell_vars: &'a mut HashMap<String, String>,
+    ) -> Self {
+        Self {
+            env_vars,
+            shell_vars,
+        }
+    }
+
+    pub fn execute(self, args: Vec<&str>) -> CommandRes {
+        if args.is_empty() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("unset: not enough arguments");
+        }
+
+        let mut stderr_parts = Vec::new();
+        for name in args {
+            if !is_valid_name(name) {
+                stderr_parts.push(format!("unset: {name}: invalid parameter name"));
+                continue;
+            }
+            self.env_vars.remove(name);
+            self.shell_vars.remove(name);
+        }
+
+        if stderr_parts.is_empty() {
+            CommandRes::new()
+        } else {
+            CommandRes::new()
+                .with_error()
+                .with_stderr(stderr_parts.join("\n"))
+        }
+    }
+}
+
+pub struct EnvCommand<'a> {
+    env_vars: &'a HashMap<String, String>,
+}
+
+impl<'a> EnvCommand<'a> {
+    pub fn new(env_vars: &'a HashMap<String, String>) -> Self {
+        Self { env_vars }
+    }
+}
+
+impl Command for EnvCommand<'_> {
+    fn execute(
+        &self,
+        _path: &str,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        if !args.is_empty() {
+            let error_msg = r#"env: too many arguments
+This version of env only prints the environment; use NAME=value cmd instead"#;
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        CommandRes::new().with_stdout_text(format_vars(self.env_vars.iter()))
+    }
+}
+
+pub struct PrintenvCommand<'a> {
+    env_vars: &'a HashMap<String, String>,
+}
+
+impl<'a> PrintenvCommand<'a> {
+    pub fn new(env_vars: &'a HashMap<String, String>) -> Self {
+        Self { env_vars }
+    }
+}
+
+impl Command for PrintenvCommand<'_> {
+    fn execute(
+        &self,
+        _path: &str,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        if args.is_empty() {
+            return CommandRes::new().with_stdout_text(format_vars(self.env_vars.iter()));
+        }
+
+        // Like the real printenv, missing variables only affect the exit status
+        let values = args
+            .iter()
+            .filter_map(|name| self.env_vars.get(*name).cloned())
+            .collect::<Vec<_>>();
+        let mut res = CommandRes::new();
+        if values.len() < args.len() {
+            res = res.with_error();
+        }
+        if !values.is_empty() {
+            res = res.with_stdout_text(values.join("\n"));
+        }
+        res
+    }
+}
+
+pub struct SetCommand<'a> {
+    env_vars: &'a HashMap<String, String>,
+    shell_vars: &'a HashMap<String, String>,
+    errexit: &'a mut bool,
+}
+
+impl<'a> SetCommand<'a> {
+    pub fn new(
+        env_vars: &'a HashMap<String, String>,
+        shell_vars: &'a HashMap<String, String>,
+        errexit: &'a mut bool,
+    ) -> Self {
+        Self {
+            env_vars,
+            shell_vars,
+            errexit,
+        }
+    }
+
+    pub fn execute(self, args: Vec<&str>) -> CommandRes {
+        match args.as_slice() {
+            [] => {
+                let all_vars = self.env_vars.iter().chain(self.shell_vars.iter());
+                CommandRes::new().with_stdout_text(format_vars(all_vars))
+            }
+            ["-e"] | ["-o", "errexit"] => {
+                *self.errexit = true;
+                CommandRes::new()
+            }
+            ["+e"] | ["+o", "errexit"] => {
+                *self.errexit = false;
+                CommandRes::new()
+            }
+            _ => {
+                let error_msg = r#"set: unsupported arguments
+This version of set only lists shell variables and toggles -e"#;
+                CommandRes::new().with_error().with_stderr(error_msg)
+            }
+        }
+    }
+}
+
+/// Format an alias the way zsh lists it, quoting the expansion when needed
+fn format_alias(name: &str, expansion: &str) -> String {
+    let is_plain = !expansion.is_empty()
+        && expansion
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./".contains(c));
+    if is_plain {
+        format!("{name}={expansion}")
+    } else {
+        format!("{name}='{}'", expansion.replace('\'', r"'\''"))
+    }
+}
+
+pub struct AliasCommand<'a> {
+    aliases: &'a mut HashMap<String, String>,
+}
+
+impl<'a> AliasCommand<'a> {
+    pub fn new(aliases: &'a mut HashMap<String, String>) -> Self {
+        Self { aliases }
+    }
+
+    pub fn execute(self, args: Vec<&str>) -> CommandRes {
+        if args.is_empty() {
+            let mut lines = self
+                .aliases
+                .iter()
+                .map(|(name, expansion)| format_alias(name, expansion))
+                .collect::<Vec<_>>();
+            lines.sort();
+            return CommandRes::new().with_stdout_text(lines.join("\n"));
+        }
+
+        let mut stdout_parts = Vec::new();
+        let mut stderr_parts = Vec::new();
+        let mut is_err = false;
+        for arg in args {
+            match arg.split_once('=') {
+                Some((name, _)) if name.is_empty() || name.contains('/') => {
+                    stderr_parts.push(format!("alias: invalid alias name: {name}"));
+                    is_err = true;
+                }
+                Some((name, expansion)) => {
+                    self.aliases.insert(name.to_string(), expansion.to_string());
+                }
+                // `alias name` shows a single alias, failing quietly if it doesn't exist
+                None => match self.aliases.get(arg) {
+                    Some(expansion) => stdout_parts.push(format_alias(arg, expansion)),
+                    None => is_err = true,
+                },
+            }
+        }
+
+        let mut res = CommandRes::new();
+        if is_err {
+            res = res.with_error();
+        }
+        if !stdout_parts.is_empty() {
+            res = res.with_stdout_text(stdout_parts.join("\n"));
+        }
+        if !stderr_parts.is_empty() {
+            res = res.with_stderr(stderr_parts.join("\n"));
+        }
+        res
+    }
+}
+
+pub struct UnaliasCommand<'a> {
+    aliases: &'a mut HashMap<String, String>,
+}
+
+impl<'a> UnaliasCommand<'a> {
+    pub fn new(aliases: &'a mut HashMap<String, String>) -> Self {
+        Self { aliases }
+    }
+
+    pub fn execute(self, args: Vec<&str>) -> CommandRes {
+        if args.is_empty() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("unalias: not enough arguments");
+        }
+        if args == ["-a"] {
+            self.aliases.clear();
+            return CommandRes::new();
+        }
+
+        let stderr_parts = args
+            .into_iter()
+            .filter(|name| self.aliases.remove(*name).is_none())
+            .map(|name| format!("unalias: no such hash table element: {name}"))
+            .collect::<Vec<_>>();
+
+        if stderr_parts.is_empty() {
+            CommandRes::new()
+        } else {
+            CommandRes::new()
+                .with_error()
+                .with_stderr(stderr_parts.join("\n"))
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone};
 use indextree::NodeId;
 use leptos::prelude::*;
 use leptos_router::components::*;
+use serde::Serialize;
 
-use super::command::{CommandRes, VfsCommand};
+use super::command::{Cmd, CommandRes, VfsCommand};
 use super::components::{ColumnarView, TextContent};
-use super::vfs::{FileContent, VfsError, VfsNode, VfsNodeType, VirtualFilesystem};
+use super::text_tools::parse_args;
+use super::vfs::{FileContent, VfsError, VfsNode, VfsNodeType, VirtualFilesystem, EXECUTE};
 
 // Parse arguments to extract options & path arguments
 pub fn parse_multitarget(args: Vec<&str>) -> (Vec<char>, Vec<&str>) {
@@ -36,6 +39,44 @@ struct VfsItem {
     path: String,         // Filesystem / URL path
 }
 
+impl VfsItem {
+    /// The ` -> target` that `ls -l` puts after a link's name
+    fn link_suffix(&self) -> String {
+        self.node
+            .link_target()
+            .map(|target| format!(" -> {target}"))
+            .unwrap_or_default()
+    }
+}
+
+/// What `ls` sorts by
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LsSort {
+    Name,
+    Time, // Newest first, by modification time
+    Size, // Largest first
+}
+
+/// Sort `items` as `ls` does, breaking ties by name, and reverse them for `-r`
+fn sort_items(items: &mut [VfsItem], sort: LsSort, reverse: bool) {
+    items.sort_by(|a, b| {
+        let by_name = a.display_name.cmp(&b.display_name);
+        match sort {
+            LsSort::Name => by_name,
+            LsSort::Time => b
+                .node
+                .metadata
+                .modified
+                .cmp(&a.node.metadata.modified)
+                .then(by_name),
+            LsSort::Size => b.node.size().cmp(&a.node.size()).then(by_name),
+        }
+    });
+    if reverse {
+        items.reverse();
+    }
+}
+
 impl TextContent for VfsItem {
     fn text_content(&self) -> &str {
         &self.display_name
@@ -61,16 +102,15 @@ impl VfsCommand for LsCommand {
     ) -> CommandRes {
         let mut all = false;
         let mut long_format = false;
+        let mut human = false;
+        let mut sort = LsSort::Name;
+        let mut reverse = false;
         let (options, mut target_paths) = parse_multitarget(args);
 
         // Validate options
-        let invalid = options.iter().find(|c| **c != 'a' && **c != 'l');
-        if let Some(c) = invalid {
-            let c = c.to_owned();
-            let error_msg = format!(
-                r#"ls: invalid option -- '{c}'
-This version of ls only supports options 'a' and 'l'"#
-            );
+        let usage = Cmd::Ls.usage().expect("ls has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
             return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
@@ -79,6 +119,11 @@ This version of ls only supports options 'a' and 'l'"#
             match option {
                 'a' => all = true,
                 'l' => long_format = true,
+                'h' => human = true,
+                // Whichever of -t and -S comes last wins
+                't' => sort = LsSort::Time,
+                'S' => sort = LsSort::Size,
+                'r' => reverse = true,
                 _ => unreachable!("Invalid options should be caught above"),
             }
         }
@@ -118,17 +163,35 @@ This version of ls only supports options 'a' and 'l'"#
             let resolved_path = if tp.is_empty() {
                 Ok(current_dir)
             } else {
-                vfs.resolve_path(current_dir, tp)
+                vfs.resolve_path_nofollow(current_dir, tp)
+                    .and_then(|node_id| {
+                        match vfs.resolve_path(current_dir, tp) {
+                            // Without -l a link stands for what it points at, unless it dangles
+                            Ok(followed) if !long_format => Ok(followed),
+                            Err(VfsError::TooManyLinks) if !long_format => {
+                                Err(VfsError::TooManyLinks)
+                            }
+                            _ => Ok(node_id),
+                        }
+                    })
             };
 
-            let node_id = if let Ok(node_id) = resolved_path {
-                node_id
-            } else {
-                has_error = true;
-                stderr_parts.push(format!(
-                    "ls: cannot access '{target_string}': No such file or directory"
-                ));
-                continue;
+            let node_id = match resolved_path {
+                Ok(node_id) => node_id,
+                Err(VfsError::TooManyLinks) => {
+                    has_error = true;
+                    stderr_parts.push(format!(
+                        "ls: cannot access '{target_string}': Too many levels of symbolic links"
+                    ));
+                    continue;
+                }
+                Err(_) => {
+                    has_error = true;
+                    stderr_parts.push(format!(
+                        "ls: cannot access '{target_string}': No such file or directory"
+                    ));
+                    continue;
+                }
             };
 
             let node = vfs
@@ -137,7 +200,7 @@ This version of ls only supports options 'a' and 'l'"#
             let node_path = vfs.get_node_path(node_id);
 
             match &node.node_type {
-                VfsNodeType::File { .. } => {
+                VfsNodeType::File { .. } | VfsNodeType::Link { .. } => {
                     file_items.push(VfsItem {
                         node: node.clone(),
                         link_count: 0,
@@ -175,7 +238,7 @@ This version of ls only supports options 'a' and 'l'"#
                             dir_items.push(get_vfs_item(entry.node_id, entry.name));
                         }
 
-                        dir_items.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+                        sort_items(&mut dir_items, sort, reverse);
                         dir_listings.push((tp.to_string(), dir_items));
                     } else {
                         has_error = true;
@@ -184,12 +247,6 @@ This version of ls only supports options 'a' and 'l'"#
                         ));
                     }
                 }
-                VfsNodeType::Link { .. } => {
-                    has_error = true;
-                    stderr_parts.push(format!(
-                        "ls: cannot access '{target_string}': No such file or directory"
-                    ));
-                }
             }
         }
 
@@ -200,13 +257,62 @@ This version of ls only supports options 'a' and 'l'"#
             result = result.with_error().with_stderr(stderr_text);
         }
 
-        file_items.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+        sort_items(&mut file_items, sort, reverse);
         dir_listings.sort_by(|a, b| a.0.cmp(&b.0));
 
-        if is_tty {
-            let is_multi =
-                dir_listings.len() > 1 || (!dir_listings.is_empty() && !file_items.is_empty());
+        // The plain listing goes along with the view too, so a long one can be paged
+        let mut text_output = Vec::new();
+        let is_multi =
+            dir_listings.len() > 1 || (!dir_listings.is_empty() && !file_items.is_empty());
+
+        // Handle file targets
+        if !file_items.is_empty() {
+            for item in &file_items {
+                if long_format {
+                    text_output.push(format!(
+                        "{} {}{}",
+                        item.node.long_meta_string(item.link_count, human),
+                        item.display_name,
+                        item.link_suffix()
+                    ));
+                } else {
+                    text_output.push(item.display_name.clone());
+                }
+            }
+
+            if is_multi {
+                text_output.push("".to_string()); // Empty line separator
+            }
+        }
+
+        // Handle directory targets
+        for (i, (display_name, items)) in dir_listings.iter().enumerate() {
+            if is_multi {
+                if i > 0 {
+                    text_output.push("".to_string()); // Empty line separator
+                }
+                text_output.push(format!("{display_name}:"));
+            }
+
+            for item in items {
+                if long_format {
+                    text_output.push(format!(
+                        "{} {}{}",
+                        item.node.long_meta_string(item.link_count, human),
+                        item.display_name,
+                        item.link_suffix()
+                    ));
+                } else {
+                    text_output.push(item.display_name.clone());
+                }
+            }
+        }
 
+        if !text_output.is_empty() {
+            result = result.with_stdout_text(text_output.join("\n"));
+        }
+
+        if is_tty {
             result = result.with_stdout_view(Arc::new(move || {
                 let mut all_views = Vec::new();
 
@@ -216,6 +322,7 @@ This version of ls only supports options 'a' and 'l'"#
                         VfsLsView(VfsLsViewProps {
                             items: file_items.clone(),
                             long_format,
+                            human,
                         })
                         .into_any(),
                     );
@@ -244,6 +351,7 @@ This version of ls only supports options 'a' and 'l'"#
                         VfsLsView(VfsLsViewProps {
                             items: items.clone(),
                             long_format,
+                            human,
                         })
                         .into_any(),
                     );
@@ -251,56 +359,6 @@ This version of ls only supports options 'a' and 'l'"#
 
                 view! { {all_views} }.into_any()
             }))
-        } else {
-            // For non-TTY output, return text format
-            let mut text_output = Vec::new();
-            let is_multi =
-                dir_listings.len() > 1 || (!dir_listings.is_empty() && !file_items.is_empty());
-
-            // Handle file targets
-            if !file_items.is_empty() {
-                for item in &file_items {
-                    if long_format {
-                        text_output.push(format!(
-                            "{} {}",
-                            item.node.long_meta_string(item.link_count),
-                            item.display_name
-                        ));
-                    } else {
-                        text_output.push(item.display_name.clone());
-                    }
-                }
-
-                if is_multi {
-                    text_output.push("".to_string()); // Empty line separator
-                }
-            }
-
-            // Handle directory targets
-            for (i, (display_name, items)) in dir_listings.iter().enumerate() {
-                if is_multi {
-                    if i > 0 {
-                        text_output.push("".to_string()); // Empty line separator
-                    }
-                    text_output.push(format!("{display_name}:"));
-                }
-
-                for item in items {
-                    if long_format {
-                        text_output.push(format!(
-                            "{} {}",
-                            item.node.long_meta_string(item.link_count),
-                            item.display_name
-                        ));
-                    } else {
-                        text_output.push(item.display_name.clone());
-                    }
-                }
-            }
-
-            if !text_output.is_empty() {
-                result = result.with_stdout_text(text_output.join("\n"));
-            }
         }
 
         result
@@ -309,19 +367,27 @@ This version of ls only supports options 'a' and 'l'"#
 
 /// VFS-based LsView component that works directly with VfsItem instead of DirContentItem
 #[component]
-fn VfsLsView(items: Vec<VfsItem>, #[prop(default = false)] long_format: bool) -> impl IntoView {
+fn VfsLsView(
+    items: Vec<VfsItem>,
+    #[prop(default = false)] long_format: bool,
+    #[prop(default = false)] human: bool,
+) -> impl IntoView {
     let dir_class = "text-blue";
     let ex_class = "text-green";
+    let link_class = "text-cyan";
 
     if long_format {
         let long_render_func = move |item: VfsItem| {
+            let link_suffix = item.link_suffix();
             let filename = item.display_name;
             let path = item.path;
             let is_directory = item.node.is_directory();
             let is_executable = item.node.is_executable();
 
             // Create the styled filename part
-            let styled_filename = if is_directory {
+            let styled_filename = if item.node.link_target().is_some() {
+                view! { <span class=link_class>{filename.clone()}</span> }.into_any()
+            } else if is_directory {
                 view! {
                     <A href=path attr:class=dir_class>
                         {filename.clone()}
@@ -336,7 +402,8 @@ fn VfsLsView(items: Vec<VfsItem>, #[prop(default = false)] long_format: bool) ->
 
             view! {
                 <div class="whitespace-pre font-mono">
-                    {item.node.long_meta_string(item.link_count)} {styled_filename}
+                    {item.node.long_meta_string(item.link_count, human)} {styled_filename}
+                    {link_suffix}
                 </div>
             }
             .into_any()
@@ -350,7 +417,9 @@ fn VfsLsView(items: Vec<VfsItem>, #[prop(default = false)] long_format: bool) ->
             let is_directory = item.node.is_directory();
             let is_executable = item.node.is_executable();
 
-            if is_directory {
+            if item.node.link_target().is_some() {
+                view! { <span class=link_class>{display_name}</span> }.into_any()
+            } else if is_directory {
                 view! {
                     <A href=path attr:class=dir_class>
                         {display_name}
@@ -373,68 +442,404 @@ fn VfsLsView(items: Vec<VfsItem>, #[prop(default = false)] long_format: bool) ->
     }
 }
 
-pub struct CdCommand;
+/// A node `tree` has visited, with its children if it's a directory it could open
+struct TreeEntry {
+    name: String,
+    path: String,
+    node_type: VfsNodeType,
+    is_executable: bool,
+    children: Vec<TreeEntry>,
+    unreadable: bool,
+}
+
+/// Box-drawing characters, or plain ASCII for `--charset ascii`
+struct TreeCharset {
+    branch: &'static str,
+    last: &'static str,
+    pipe: &'static str,
+}
+
+const UNICODE_CHARSET: TreeCharset = TreeCharset {
+    branch: "├── ",
+    last: "└── ",
+    pipe: "│   ",
+};
+
+const ASCII_CHARSET: TreeCharset = TreeCharset {
+    branch: "|-- ",
+    last: "`-- ",
+    pipe: "|   ",
+};
+
+/// One line of rendered tree output: the connectors drawn before an entry and the entry
+#[derive(Clone)]
+struct TreeLine {
+    prefix: String,
+    name: String,
+    path: String,
+    node_type: VfsNodeType,
+    is_executable: bool,
+    unreadable: bool,
+}
 
-impl CdCommand {
+impl TreeLine {
+    fn suffix(&self) -> String {
+        match &self.node_type {
+            VfsNodeType::Link { target } => format!(" -> {target}"),
+            _ if self.unreadable => " [error opening dir]".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn text(&self) -> String {
+        format!("{}{}{}", self.prefix, self.name, self.suffix())
+    }
+}
+
+/// `tree -J` output, in the same shape as tree's own JSON
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TreeJson {
+    Directory {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        contents: Vec<TreeJson>,
+    },
+    File {
+        name: String,
+    },
+    Link {
+        name: String,
+        target: String,
+    },
+    Report {
+        directories: usize,
+        files: usize,
+    },
+}
+
+/// Which entries `tree` visits
+struct TreeOptions {
+    all: bool,
+    dirs_only: bool,
+    max_depth: usize,
+}
+
+impl TreeOptions {
+    /// Visit `node_id` and what's below it, counting the directories and files found
+    fn walk(
+        &self,
+        vfs: &VirtualFilesystem,
+        node_id: NodeId,
+        name: String,
+        depth: usize,
+        counts: &mut (usize, usize),
+    ) -> TreeEntry {
+        let node = vfs.get_node(node_id).expect("walked nodes should exist");
+        let mut entry = TreeEntry {
+            name,
+            path: vfs.get_node_path(node_id),
+            node_type: node.node_type.clone(),
+            is_executable: node.is_executable(),
+            children: Vec::new(),
+            unreadable: false,
+        };
+        if !node.is_directory() || depth >= self.max_depth {
+            return entry;
+        }
+
+        match vfs.list_directory(node_id) {
+            Ok(children) => {
+                for child in children {
+                    let hidden = !self.all && child.name.starts_with('.');
+                    if hidden || (self.dirs_only && !child.is_directory) {
+                        continue;
+                    }
+                    if child.is_directory {
+                        counts.0 += 1;
+                    } else {
+                        counts.1 += 1;
+                    }
+                    entry.children.push(self.walk(
+                        vfs,
+                        child.node_id,
+                        child.name,
+                        depth + 1,
+                        counts,
+                    ));
+                }
+            }
+            Err(_) => entry.unreadable = true,
+        }
+        entry
+    }
+}
+
+pub struct TreeCommand;
+
+impl TreeCommand {
     pub fn new() -> Self {
         Self
     }
+
+    fn render(
+        entry: &TreeEntry,
+        charset: &TreeCharset,
+        prefix: &str,
+        connector: &str,
+        lines: &mut Vec<TreeLine>,
+    ) {
+        lines.push(TreeLine {
+            prefix: format!("{prefix}{connector}"),
+            name: entry.name.clone(),
+            path: entry.path.clone(),
+            node_type: entry.node_type.clone(),
+            is_executable: entry.is_executable,
+            unreadable: entry.unreadable,
+        });
+
+        // Children line up under their parent's name, continuing its branch if it has siblings
+        let child_prefix = match connector {
+            "" => prefix.to_string(),
+            c if c == charset.last => format!("{prefix}    "),
+            _ => format!("{prefix}{}", charset.pipe),
+        };
+        for (i, child) in entry.children.iter().enumerate() {
+            let connector = if i + 1 == entry.children.len() {
+                charset.last
+            } else {
+                charset.branch
+            };
+            Self::render(child, charset, &child_prefix, connector, lines);
+        }
+    }
+
+    fn to_json(entry: TreeEntry) -> TreeJson {
+        match entry.node_type {
+            VfsNodeType::Directory => TreeJson::Directory {
+                name: entry.name,
+                error: entry.unreadable.then(|| "error opening dir".to_string()),
+                contents: entry.children.into_iter().map(Self::to_json).collect(),
+            },
+            VfsNodeType::File { .. } => TreeJson::File { name: entry.name },
+            VfsNodeType::Link { target } => TreeJson::Link {
+                name: entry.name,
+                target,
+            },
+        }
+    }
 }
 
-impl VfsCommand for CdCommand {
+impl VfsCommand for TreeCommand {
     fn execute(
         &self,
         vfs: &mut VirtualFilesystem,
         current_dir: NodeId,
         args: Vec<&str>,
         _stdin: Option<&str>,
-        _is_tty: bool,
+        is_tty: bool,
     ) -> CommandRes {
-        // Validate arguments
-        if args.len() >= 2 {
-            let error_msg = "cd: too many arguments";
-            return CommandRes::new().with_error().with_stderr(error_msg);
+        let mut options = TreeOptions {
+            all: false,
+            dirs_only: false,
+            max_depth: usize::MAX,
+        };
+        let mut json = false;
+        let mut charset = &UNICODE_CHARSET;
+        let mut targets = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg {
+                "-a" => options.all = true,
+                "-d" => options.dirs_only = true,
+                "-J" => json = true,
+                "-L" => {
+                    let level = args.next().and_then(|level| level.parse::<usize>().ok());
+                    match level {
+                        Some(level) if level > 0 => options.max_depth = level,
+                        _ => {
+                            let error_msg = "tree: Invalid level, must be greater than 0.";
+                            return CommandRes::new().with_error().with_stderr(error_msg);
+                        }
+                    }
+                }
+                "--charset=ascii" => charset = &ASCII_CHARSET,
+                "--charset=utf-8" | "--charset=utf8" => charset = &UNICODE_CHARSET,
+                "--" => targets.extend(args.by_ref()),
+                arg if arg.starts_with('-') && arg != "-" => {
+                    let usage = Cmd::Tree.usage().expect("tree has a usage spec");
+                    let error_msg = format!(
+                        "tree: Invalid argument -`{}'.\n{}",
+                        arg.trim_start_matches('-'),
+                        usage.supported_options()
+                    );
+                    return CommandRes::new().with_error().with_stderr(error_msg);
+                }
+                target => targets.push(target),
+            }
+        }
+        if targets.is_empty() {
+            targets.push(".");
         }
 
-        let target_path = if args.is_empty() { "/" } else { args[0] };
-        let target_string = target_path.to_string();
+        let mut counts = (0, 0);
+        let mut has_error = false;
+        let mut entries = Vec::new();
+        for target in targets {
+            match vfs.resolve_path(current_dir, target) {
+                Ok(node_id) => {
+                    entries.push(options.walk(vfs, node_id, target.to_string(), 0, &mut counts))
+                }
+                Err(_) => {
+                    has_error = true;
+                    entries.push(TreeEntry {
+                        name: target.to_string(),
+                        path: String::new(),
+                        node_type: VfsNodeType::Directory,
+                        is_executable: false,
+                        children: Vec::new(),
+                        unreadable: true,
+                    });
+                }
+            }
+        }
+        let (directories, files) = counts;
+
+        let mut result = CommandRes::new();
+        if has_error {
+            result = result.with_error();
+        }
 
-        // Resolve path using VFS (~ expansion is now handled by resolve_path)
-        let resolved_path = if target_path.is_empty() {
-            Ok(vfs.get_root())
+        if json {
+            let mut report = entries.into_iter().map(Self::to_json).collect::<Vec<_>>();
+            report.push(TreeJson::Report { directories, files });
+            let text = serde_json::to_string(&report).unwrap_or_default();
+            return result.with_stdout_text(text);
+        }
+
+        let plural =
+            |n: usize, one: &str, many: &str| format!("{n} {}", if n == 1 { one } else { many });
+        let summary = if options.dirs_only {
+            plural(directories, "directory", "directories")
         } else {
-            vfs.resolve_path(current_dir, target_path)
+            format!(
+                "{}, {}",
+                plural(directories, "directory", "directories"),
+                plural(files, "file", "files")
+            )
         };
 
-        match resolved_path {
-            Ok(node_id) => {
-                if let Some(node) = vfs.get_node(node_id) {
-                    match &node.node_type {
-                        VfsNodeType::Directory => {
-                            // If it's the same directory, no change needed
-                            if node_id == current_dir {
-                                CommandRes::new()
-                            } else {
-                                // Return redirect with the new path
-                                let new_path = vfs.get_node_path(node_id);
-                                CommandRes::Redirect(new_path)
+        let mut lines = Vec::new();
+        for entry in &entries {
+            Self::render(entry, charset, "", "", &mut lines);
+        }
+
+        // Plain text goes along with the view, so a large tree can be paged
+        let mut text = lines.iter().map(TreeLine::text).collect::<Vec<_>>();
+        text.push(String::new());
+        text.push(summary.clone());
+        let result = result.with_stdout_text(text.join("\n"));
+        if is_tty {
+            result.with_stdout_view(Arc::new(move || {
+                let rendered = lines
+                    .clone()
+                    .into_iter()
+                    .map(|line| {
+                        let suffix = line.suffix();
+                        let href = line.path;
+                        let prefix = line.prefix;
+                        let name = match line.node_type {
+                            VfsNodeType::Directory if !href.is_empty() => view! {
+                                <A href=href attr:class="text-blue">
+                                    {line.name}
+                                </A>
                             }
+                            .into_any(),
+                            VfsNodeType::Link { .. } => view! {
+                                <span class="text-cyan">{line.name}</span>
+                            }
+                            .into_any(),
+                            _ if line.is_executable => view! {
+                                <span class="text-green">{line.name}</span>
+                            }
+                            .into_any(),
+                            _ => view! { <span>{line.name}</span> }.into_any(),
+                        };
+                        view! {
+                            <div class="whitespace-pre font-mono">
+                                <span class="text-muted">{prefix}</span>
+                                {name}
+                                {suffix}
+                            </div>
                         }
-                        VfsNodeType::File { .. } => {
-                            let error_msg = format!("cd: not a directory: {target_string}");
-                            CommandRes::new().with_error().with_stderr(error_msg)
-                        }
-                        VfsNodeType::Link { .. } => {
-                            let error_msg = format!("cd: cannot follow link: {target_string}");
-                            CommandRes::new().with_error().with_stderr(error_msg)
-                        }
-                    }
+                    })
+                    .collect_view();
+                view! {
+                    <div>
+                        {rendered}
+                        <br />
+                        <div>{summary.clone()}</div>
+                    </div>
+                }
+                .into_any()
+            }))
+        } else {
+            result
+        }
+    }
+}
+
+/// `cd` keeps the logical path it was given, so going through a link shows up in `pwd`,
+/// and `..` goes back the way it came rather than to the link target's parent
+pub struct CdCommand<'a> {
+    cwd: &'a str,
+}
+
+impl<'a> CdCommand<'a> {
+    pub fn new(cwd: &'a str) -> Self {
+        Self { cwd }
+    }
+
+    pub fn execute(&self, vfs: &VirtualFilesystem, args: Vec<&str>) -> CommandRes {
+        // Validate arguments
+        if args.len() >= 2 {
+            let error_msg = "cd: too many arguments";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+
+        let target_path = if args.is_empty() { "/" } else { args[0] };
+        let target_string = target_path.to_string();
+        let new_path = logical_path(self.cwd, target_path);
+
+        // The logical path is checked by resolving it, links and all
+        match vfs
+            .resolve_path(vfs.get_root(), &new_path)
+            .map(|node_id| vfs.get_node(node_id))
+        {
+            // Entering a directory needs search (execute) permission on it
+            Ok(Some(node)) if node.is_directory() && !node.allows(vfs.user(), EXECUTE) => {
+                let error_msg = format!("cd: permission denied: {target_string}");
+                CommandRes::new().with_error().with_stderr(error_msg)
+            }
+            Ok(Some(node)) if node.is_directory() => {
+                // If it's the same directory, no change needed
+                if new_path == self.cwd {
+                    CommandRes::new()
                 } else {
-                    let error_msg = format!("cd: no such file or directory: {target_string}");
-                    CommandRes::new().with_error().with_stderr(error_msg)
+                    CommandRes::Redirect(new_path)
                 }
             }
-            Err(_) => {
+            Ok(Some(_)) => {
+                let error_msg = format!("cd: not a directory: {target_string}");
+                CommandRes::new().with_error().with_stderr(error_msg)
+            }
+            Err(VfsError::TooManyLinks) => {
+                let error_msg = format!("cd: too many levels of symbolic links: {target_string}");
+                CommandRes::new().with_error().with_stderr(error_msg)
+            }
+            Ok(None) | Err(_) => {
                 let error_msg = format!("cd: no such file or directory: {target_string}");
                 CommandRes::new().with_error().with_stderr(error_msg)
             }
@@ -442,6 +847,31 @@ impl VfsCommand for CdCommand {
     }
 }
 
+/// Apply `target` to the path `cwd` without looking at the filesystem, as a shell's logical `cd` does
+fn logical_path(cwd: &str, target: &str) -> String {
+    let (mut parts, rest) = if target.is_empty() || target == "~" {
+        (Vec::new(), "")
+    } else if let Some(rest) = target.strip_prefix("~/") {
+        (Vec::new(), rest)
+    } else if let Some(rest) = target.strip_prefix('/') {
+        (Vec::new(), rest)
+    } else {
+        (cwd.split('/').filter(|s| !s.is_empty()).collect(), target)
+    };
+
+    for part in rest.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            name => parts.push(name),
+        }
+    }
+
+    format!("/{}", parts.join("/"))
+}
+
 pub struct CatCommand;
 
 impl CatCommand {
@@ -456,22 +886,23 @@ impl VfsCommand for CatCommand {
         vfs: &mut VirtualFilesystem,
         current_dir: NodeId,
         args: Vec<&str>,
-        _stdin: Option<&str>,
+        stdin: Option<&str>,
         _is_tty: bool,
     ) -> CommandRes {
         let (options, targets) = parse_multitarget(args);
 
         // Validate options
-        if !options.is_empty() {
-            let c = options[0].to_owned();
-            let error_msg = format!(
-                r#"cat: invalid option -- '{c}'
-This version of cat doesn't support any options"#
-            );
+        let usage = Cmd::Cat.usage().expect("cat has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
             return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
         if targets.is_empty() {
+            // With no file operands, cat copies stdin (e.g. the previous pipeline stage)
+            if let Some(input) = stdin {
+                return CommandRes::new().with_stdout_text(input);
+            }
             return CommandRes::new()
                 .with_error()
                 .with_stderr("cat: missing operand");
@@ -486,13 +917,20 @@ This version of cat doesn't support any options"#
             let target_string = tp.to_string();
 
             let resolved_path = if tp.is_empty() {
-                Err(())
+                Err(VfsError::NotFound)
             } else {
-                vfs.resolve_path(current_dir, tp).map_err(|_| ())
+                vfs.resolve_path(current_dir, tp)
             };
 
             let node_id = match resolved_path {
                 Ok(node_id) => node_id,
+                Err(VfsError::TooManyLinks) => {
+                    has_error = true;
+                    stderr_parts.push(format!(
+                        "cat: {target_string}: Too many levels of symbolic links"
+                    ));
+                    continue;
+                }
                 Err(_) => {
                     has_error = true;
                     stderr_parts.push(format!("cat: {target_string}: No such file or directory"));
@@ -514,6 +952,10 @@ This version of cat doesn't support any options"#
                     Ok(file_content) => {
                         stdout_parts.push(file_content);
                     }
+                    Err(VfsError::SystemError(msg)) => {
+                        has_error = true;
+                        stderr_parts.push(format!("cat: {target_string}: {msg}"));
+                    }
                     Err(_) => {
                         has_error = true;
                         stderr_parts.push(format!("cat: {target_string}: Permission denied"));
@@ -553,6 +995,64 @@ impl TouchCommand {
     pub fn new() -> Self {
         Self
     }
+
+    /// Parse a `-d` date: `now`, `@seconds`, RFC 3339, or a local `YYYY-MM-DD[ HH:MM[:SS]]`
+    fn parse_date(date: &str) -> Option<DateTime<Local>> {
+        let date = date.trim();
+        if date == "now" {
+            return Some(Local::now());
+        }
+        if let Some(seconds) = date.strip_prefix('@') {
+            let time = DateTime::from_timestamp(seconds.parse().ok()?, 0)?;
+            return Some(time.with_timezone(&Local));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(date) {
+            return Some(time.with_timezone(&Local));
+        }
+
+        let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)
+            })?;
+        Local.from_local_datetime(&naive).earliest()
+    }
+
+    /// Parse a `-t` stamp, `[[CC]YY]MMDDhhmm[.ss]`, in local time
+    fn parse_stamp(stamp: &str) -> Option<DateTime<Local>> {
+        let (digits, seconds) = stamp.split_once('.').unwrap_or((stamp, "00"));
+        let all_digits = digits
+            .chars()
+            .chain(seconds.chars())
+            .all(|c| c.is_ascii_digit());
+        if !all_digits || seconds.len() != 2 {
+            return None;
+        }
+
+        let (year, rest) = match digits.len() {
+            12 => (digits[..4].parse().ok()?, &digits[4..]),
+            // Two-digit years are 1969 to 2068, as POSIX says
+            10 => {
+                let year: i32 = digits[..2].parse().ok()?;
+                (
+                    if year < 69 { 2000 + year } else { 1900 + year },
+                    &digits[2..],
+                )
+            }
+            8 => (Local::now().year(), digits),
+            _ => return None,
+        };
+        let field = |i: usize| rest[i..i + 2].parse::<u32>().ok();
+        let naive = NaiveDate::from_ymd_opt(year, field(0)?, field(2)?)?.and_hms_opt(
+            field(4)?,
+            field(6)?,
+            seconds.parse().ok()?,
+        )?;
+        Local.from_local_datetime(&naive).earliest()
+    }
 }
 
 impl VfsCommand for TouchCommand {
@@ -564,7 +1064,29 @@ impl VfsCommand for TouchCommand {
         _stdin: Option<&str>,
         _is_tty: bool,
     ) -> CommandRes {
-        let (_, targets) = parse_multitarget(args);
+        let (options, targets) = match parse_args(Cmd::Touch, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+
+        // Files get the current time unless -d or -t give another
+        let mut time = None;
+        for (option, value) in options {
+            let value = value.expect("touch's options take values");
+            let parsed = if option == 'd' {
+                Self::parse_date(value)
+            } else {
+                Self::parse_stamp(value)
+            };
+            match parsed {
+                Some(parsed) => time = Some(parsed),
+                None => {
+                    return CommandRes::new()
+                        .with_error()
+                        .with_stderr(format!("touch: invalid date format '{value}'"))
+                }
+            }
+        }
 
         if targets.is_empty() {
             return CommandRes::new()
@@ -576,6 +1098,15 @@ impl VfsCommand for TouchCommand {
         let mut has_error = false;
 
         for target in targets {
+            // Existing files (or what links point at) just have their times updated
+            if let Ok(node_id) = vfs.resolve_path(current_dir, target) {
+                if vfs.set_times(node_id, time).is_err() {
+                    has_error = true;
+                    stderr_parts.push(format!("touch: cannot touch '{target}': Permission denied"));
+                }
+                continue;
+            }
+
             // Split the path to get parent directory and filename
             let (parent_path, filename) = if let Some(pos) = target.rfind('/') {
                 (&target[..pos], &target[pos + 1..])
@@ -610,37 +1141,31 @@ impl VfsCommand for TouchCommand {
                 }
             };
 
-            // Check if file already exists
-            let mut file_exists = false;
-            if let Ok(entries) = vfs.list_directory(parent_id) {
-                for entry in entries {
-                    if entry.name == filename {
-                        file_exists = true;
-                        break;
+            // The file doesn't exist, so create it
+            match vfs.create_file(parent_id, filename, FileContent::Dynamic(String::new())) {
+                Ok(file_id) => {
+                    if time.is_some() {
+                        vfs.set_times(file_id, time)
+                            .expect("new files belong to whoever made them");
                     }
                 }
-            }
-
-            // If file doesn't exist, create it
-            if !file_exists {
-                match vfs.create_file(parent_id, filename, FileContent::Dynamic(String::new())) {
-                    Ok(_) => {
-                        // File created successfully
-                    }
-                    Err(VfsError::PermissionDenied) => {
-                        has_error = true;
-                        stderr_parts
-                            .push(format!("touch: cannot touch '{target}': Permission denied"));
-                    }
-                    Err(_) => {
-                        has_error = true;
-                        stderr_parts.push(format!(
-                            "touch: cannot touch '{target}': No such file or directory"
-                        ));
-                    }
+                Err(VfsError::PermissionDenied) => {
+                    has_error = true;
+                    stderr_parts.push(format!("touch: cannot touch '{target}': Permission denied"));
+                }
+                Err(VfsError::QuotaExceeded) => {
+                    has_error = true;
+                    stderr_parts.push(format!(
+                        "touch: cannot touch '{target}': Disk quota exceeded"
+                    ));
+                }
+                Err(_) => {
+                    has_error = true;
+                    stderr_parts.push(format!(
+                        "touch: cannot touch '{target}': No such file or directory"
+                    ));
                 }
             }
-            // If file exists, touch would normally update timestamps, but we don't have that functionality yet
         }
 
         let mut result = CommandRes::new();
@@ -740,6 +1265,12 @@ impl VfsCommand for MkdirCommand {
                         "mkdir: cannot create directory '{target}': Not a directory"
                     ));
                 }
+                Err(VfsError::QuotaExceeded) => {
+                    has_error = true;
+                    stderr_parts.push(format!(
+                        "mkdir: cannot create directory '{target}': Disk quota exceeded"
+                    ));
+                }
                 Err(_) => {
                     has_error = true;
                     stderr_parts.push(format!(
@@ -783,13 +1314,9 @@ impl VfsCommand for RmCommand {
         let recursive = options.contains(&'r');
 
         // Validate options
-        let invalid = options.iter().find(|c| **c != 'r' && **c != 'f');
-        if let Some(c) = invalid {
-            let c = c.to_owned();
-            let error_msg = format!(
-                r#"rm: invalid option -- '{c}'
-This version of rm only supports options 'r' and 'f'"#
-            );
+        let usage = Cmd::Rm.usage().expect("rm has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
             return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
@@ -803,8 +1330,8 @@ This version of rm only supports options 'r' and 'f'"#
         let mut has_error = false;
 
         for target in targets {
-            // Resolve the target path
-            let node_id = match vfs.resolve_path(current_dir, target) {
+            // Resolve the target path, removing a link rather than what it points at
+            let node_id = match vfs.resolve_path_nofollow(current_dir, target) {
                 Ok(id) => id,
                 Err(_) => {
                     has_error = true;
@@ -886,13 +1413,9 @@ impl VfsCommand for CpCommand {
         let recursive = options.contains(&'r');
 
         // Validate options
-        let invalid = options.iter().find(|c| **c != 'r' && **c != 'f');
-        if let Some(c) = invalid {
-            let c = c.to_owned();
-            let error_msg = format!(
-                r#"cp: invalid option -- '{c}'
-This version of cp only supports options 'r' and 'f'"#
-            );
+        let usage = Cmd::Cp.usage().expect("cp has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
             return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
@@ -1028,6 +1551,11 @@ impl CpCommand {
         filename: &str,
         content: FileContent,
     ) -> Result<(), String> {
+        // A generated file is copied as it reads now
+        let content = match content {
+            FileContent::Generated(generator) => FileContent::Dynamic(vfs.generate(&generator)),
+            content => content,
+        };
         vfs.create_file(parent_id, filename, content)
             .map_err(|err| match err {
                 VfsError::AlreadyExists => format!("cp: cannot create '{filename}': File exists"),
@@ -1037,6 +1565,9 @@ impl CpCommand {
                 VfsError::NotADirectory => {
                     format!("cp: cannot create '{filename}': Not a directory")
                 }
+                VfsError::QuotaExceeded => {
+                    format!("cp: cannot create '{filename}': Disk quota exceeded")
+                }
                 _ => format!("cp: cannot create '{filename}': Unknown error"),
             })?;
         Ok(())
@@ -1062,6 +1593,9 @@ impl CpCommand {
                     VfsError::NotADirectory => {
                         format!("cp: cannot create directory '{dest_name}': Not a directory")
                     }
+                    VfsError::QuotaExceeded => {
+                        format!("cp: cannot create directory '{dest_name}': Disk quota exceeded")
+                    }
                     _ => format!("cp: cannot create directory '{dest_name}': Unknown error"),
                 })?;
 
@@ -1155,7 +1689,7 @@ impl MvCommand {
     ) -> Result<(), String> {
         // First check if source exists and is not immutable
         let source_id = vfs
-            .resolve_path(current_dir, source_path)
+            .resolve_path_nofollow(current_dir, source_path)
             .map_err(|_| format!("mv: cannot stat '{source_path}': No such file or directory"))?;
 
         let source_node = vfs
@@ -1171,68 +1705,232 @@ impl MvCommand {
 
         let is_directory = source_node.is_directory();
 
-        // As per Unix mv documentation:
-        // rm -f destination_path && cp -pRP source_file destination && rm -rf source_file
-
-        // Step 1: If destination exists and is a file, try to remove it first
-        // (We handle directory destinations differently in cp)
-        if let Ok(dest_id) = vfs.resolve_path(current_dir, dest_path) {
-            if let Some(dest_node) = vfs.get_node(dest_id) {
-                if !dest_node.is_directory() {
-                    // Try to remove the destination file (ignore errors for now)
-                    let _ = vfs.delete_node(dest_id);
+        // The node itself is moved, so it keeps its times and permissions and links stay links
+        let (parent_id, name) =
+            destination(vfs, current_dir, source_path, dest_path).ok_or_else(|| {
+                format!(
+                    "mv: cannot move '{source_path}' to '{dest_path}': No such file or directory"
+                )
+            })?;
+        // Name the node that's in the way, which is inside `dest_path` if that's a directory
+        let target = if vfs
+            .resolve_path(current_dir, dest_path)
+            .is_ok_and(|dest_id| dest_id == parent_id)
+        {
+            format!("{}/{name}", dest_path.trim_end_matches('/'))
+        } else {
+            dest_path.to_string()
+        };
+        vfs.rename(source_id, parent_id, &name)
+            .map_err(|err| match err {
+                VfsError::NotAFile => {
+                    format!("mv: cannot overwrite directory '{target}' with non-directory")
                 }
-            }
+                VfsError::NotADirectory if is_directory => format!(
+                    "mv: cannot overwrite non-directory '{target}' with directory '{source_path}'"
+                ),
+                VfsError::InvalidPath => format!(
+                    "mv: cannot move '{source_path}' to a subdirectory of itself, '{dest_path}'"
+                ),
+                VfsError::SystemError(msg) => {
+                    format!("mv: cannot move '{source_path}' to '{dest_path}': {msg}")
+                }
+                VfsError::QuotaExceeded => {
+                    format!("mv: cannot move '{source_path}' to '{dest_path}': Disk quota exceeded")
+                }
+                _ => format!("mv: cannot move '{source_path}': Permission denied"),
+            })
+    }
+}
+
+/// Where something named after `source` goes for the operand `dest`, as with `ln` and `mv`:
+/// inside it if it's a directory, otherwise at that path. Returns the parent directory and the
+/// name, if the parent exists.
+fn destination(
+    vfs: &VirtualFilesystem,
+    current_dir: NodeId,
+    source: &str,
+    dest: &str,
+) -> Option<(NodeId, String)> {
+    let source_name = source
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(source);
+    if let Ok(dest_id) = vfs.resolve_path(current_dir, dest) {
+        if vfs
+            .get_node(dest_id)
+            .is_some_and(|node| node.is_directory())
+        {
+            return Some((dest_id, source_name.to_string()));
         }
+    }
 
-        // Step 2: Copy source to destination (with -r if directory)
-        let cp_command = CpCommand::new();
-        let cp_args = if is_directory {
-            vec!["-r", source_path, dest_path]
-        } else {
-            vec![source_path, dest_path]
+    let (parent_path, name) = match dest.rfind('/') {
+        Some(0) => ("/", &dest[1..]),
+        Some(pos) => (&dest[..pos], &dest[pos + 1..]),
+        None => ("", dest),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let parent_id = if parent_path.is_empty() {
+        current_dir
+    } else {
+        vfs.resolve_path(current_dir, parent_path).ok()?
+    };
+    Some((parent_id, name.to_string()))
+}
+
+pub struct LnCommand;
+
+impl LnCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for LnCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, operands) = parse_multitarget(args);
+
+        // Validate options
+        let usage = Cmd::Ln.usage().expect("ln has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        if !options.contains(&'s') {
+            let error_msg = "ln: hard links are not supported, use ln -s";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+
+        // A lone target is linked into the working directory under its own name
+        let (targets, dest) = match operands.as_slice() {
+            [] => {
+                return CommandRes::new()
+                    .with_error()
+                    .with_stderr("ln: missing file operand");
+            }
+            [target] => (vec![*target], "."),
+            [targets @ .., dest] => (targets.to_vec(), *dest),
         };
 
-        // Execute the copy
-        let cp_result = cp_command.execute(vfs, current_dir, cp_args, None, false);
-        if cp_result.is_error() {
-            // Extract error message from cp command
-            return Err(format!(
-                "mv: copy failed: {}",
-                match cp_result {
-                    CommandRes::Output {
-                        stderr_text: Some(ref msg),
-                        ..
-                    } => msg,
-                    _ => "unknown error",
-                }
-            ));
+        let dest_is_dir = vfs
+            .resolve_path(current_dir, dest)
+            .is_ok_and(|id| vfs.get_node(id).is_some_and(|node| node.is_directory()));
+        if targets.len() > 1 && !dest_is_dir {
+            let error_msg = format!("ln: target '{dest}' is not a directory");
+            return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
-        // Step 3: Remove the source (with -r if directory)
-        let rm_command = RmCommand::new();
-        let rm_args = if is_directory {
-            vec!["-r", source_path]
+        let mut stderr_parts = Vec::new();
+        for target in targets {
+            let Some((parent_id, name)) = destination(vfs, current_dir, target, dest) else {
+                stderr_parts.push(format!(
+                    "ln: failed to create symbolic link '{dest}': No such file or directory"
+                ));
+                continue;
+            };
+
+            if let Err(e) = vfs.create_symlink(parent_id, &name, target) {
+                let link_path = if dest_is_dir {
+                    format!("{}/{name}", dest.trim_end_matches('/'))
+                } else {
+                    dest.to_string()
+                };
+                let reason = match e {
+                    VfsError::AlreadyExists => "File exists",
+                    VfsError::PermissionDenied => "Permission denied",
+                    VfsError::NotADirectory => "Not a directory",
+                    VfsError::QuotaExceeded => "Disk quota exceeded",
+                    _ => "No such file or directory",
+                };
+                stderr_parts.push(format!(
+                    "ln: failed to create symbolic link '{link_path}': {reason}"
+                ));
+            }
+        }
+
+        if stderr_parts.is_empty() {
+            CommandRes::new()
         } else {
-            vec![source_path]
-        };
+            CommandRes::new()
+                .with_error()
+                .with_stderr(stderr_parts.join("\n"))
+        }
+    }
+}
 
-        // Execute the removal
-        let rm_result = rm_command.execute(vfs, current_dir, rm_args, None, false);
-        if rm_result.is_error() {
-            // The copy succeeded but removal failed - this is still an error
-            return Err(format!(
-                "mv: cannot remove '{source_path}': Permission denied"
-            ));
+pub struct ReadlinkCommand;
+
+impl ReadlinkCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for ReadlinkCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, targets) = parse_multitarget(args);
+
+        // Validate options
+        let usage = Cmd::Readlink.usage().expect("readlink has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
-        Ok(())
+        if targets.is_empty() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("readlink: missing operand");
+        }
+
+        // Like GNU readlink, anything that isn't a link just fails quietly
+        let mut link_targets = Vec::new();
+        let mut has_error = false;
+        for target in targets {
+            let link_target = vfs
+                .resolve_path_nofollow(current_dir, target)
+                .ok()
+                .and_then(|id| vfs.get_node(id))
+                .and_then(|node| node.link_target());
+            match link_target {
+                Some(link_target) => link_targets.push(link_target.to_string()),
+                None => has_error = true,
+            }
+        }
+
+        let mut result = CommandRes::new();
+        if has_error {
+            result = result.with_error();
+        }
+        if !link_targets.is_empty() {
+            result = result.with_stdout_text(link_targets.join("\n"));
+        }
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::terminal::command::test_helpers::{output, run};
     use crate::app::terminal::vfs::VirtualFilesystem;
 
     #[test]
@@ -1392,4 +2090,224 @@ mod tests {
             "absolute and home should NOT exist in current directory"
         );
     }
+
+    fn tree(vfs: &mut VirtualFilesystem, dir: NodeId, args: Vec<&str>) -> String {
+        output(&run(&TreeCommand::new(), vfs, dir, &args, None)).to_string()
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut vfs = VirtualFilesystem::new(vec!["post".to_string()]);
+        let root = vfs.get_root();
+        let blog = vfs.resolve_path(root, "/blog").unwrap();
+
+        assert_eq!(
+            tree(&mut vfs, blog, vec![]),
+            "\
+.
+├── nav.rs
+└── post
+    ├── meta.yaml
+    ├── nav.rs
+    └── post.md
+
+1 directory, 4 files"
+        );
+        assert_eq!(
+            tree(&mut vfs, root, vec!["-L", "1", "-d", "--charset=ascii", "/blog"]),
+            "/blog\n`-- post\n\n1 directory"
+        );
+
+        // Hidden files only show with -a
+        let listing = tree(&mut vfs, root, vec!["-L", "1"]);
+        assert!(!listing.contains(".zshrc"));
+        assert!(listing.contains("├── cv\n"));
+        assert!(tree(&mut vfs, root, vec!["-a", "-L", "1"]).contains(".zshrc"));
+
+        assert_eq!(
+            tree(&mut vfs, blog, vec!["-J", "post"]),
+            r#"[{"type":"directory","name":"post","contents":[{"type":"file","name":"meta.yaml"},{"type":"file","name":"nav.rs"},{"type":"file","name":"post.md"}]},{"type":"report","directories":0,"files":3}]"#
+        );
+
+        assert_eq!(
+            tree(&mut vfs, root, vec!["missing"]),
+            "missing [error opening dir]\n\n0 directories, 0 files"
+        );
+        assert_eq!(
+            tree(&mut vfs, root, vec!["-L", "0"]),
+            "tree: Invalid level, must be greater than 0."
+        );
+    }
+
+    #[test]
+    fn test_tree_tty() {
+        let mut vfs = VirtualFilesystem::new(vec!["post".to_string()]);
+        let root = vfs.get_root();
+        vfs.create_symlink(root, "link", "blog").unwrap();
+
+        let text = tree(&mut vfs, root, vec!["-L", "2"]);
+
+        // On a terminal the tree is also rendered, with the same text for paging
+        match TreeCommand::new().execute(&mut vfs, root, vec!["-L", "2"], None, true) {
+            CommandRes::Output {
+                stdout_view,
+                stdout_text,
+                ..
+            } => {
+                assert!(stdout_view.is_some());
+                assert_eq!(stdout_text, Some(text));
+            }
+            CommandRes::Redirect(_) => panic!("tree should not redirect"),
+        }
+    }
+
+    #[test]
+    fn test_ls_sorting() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let dir = vfs.create_directory(root, "sorted").unwrap();
+        for (name, size, date) in [
+            ("a", 3000, "2020-01-01"),
+            ("b", 10, "2024-06-01"),
+            ("c", 200, "2022-03-01"),
+        ] {
+            let file = vfs
+                .create_file(dir, name, FileContent::Dynamic("x".repeat(size)))
+                .unwrap();
+            let time = TouchCommand::parse_date(date).unwrap();
+            vfs.set_times(file, Some(time)).unwrap();
+        }
+        let ls = LsCommand::new();
+        let names = |vfs: &mut VirtualFilesystem, args: Vec<&str>| {
+            output(&run(&ls, vfs, dir, &args, None))
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        assert_eq!(names(&mut vfs, vec![]), "a b c");
+        assert_eq!(names(&mut vfs, vec!["-t"]), "b c a");
+        assert_eq!(names(&mut vfs, vec!["-tr"]), "a c b");
+        assert_eq!(names(&mut vfs, vec!["-S"]), "a c b");
+        assert_eq!(names(&mut vfs, vec!["-t", "-S"]), "a c b");
+
+        // Long listings show the modification date, and sizes with -h
+        let long = run(&ls, &mut vfs, dir, &["-lh"], None);
+        let long = output(&long);
+        let line = long.lines().find(|line| line.ends_with(" a")).unwrap();
+        assert!(line.contains("3.0K"), "{line}");
+        assert!(line.contains("Jan  1  2020"), "{line}");
+    }
+
+    #[test]
+    fn test_touch_dates() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let touch = TouchCommand::new();
+
+        assert_eq!(
+            output(&run(
+                &touch,
+                &mut vfs,
+                root,
+                &["-d", "2021-05-04 12:30", "new"],
+                None
+            )),
+            ""
+        );
+        let file = vfs.resolve_path(root, "new").unwrap();
+        let modified = vfs.get_node(file).unwrap().metadata.modified;
+        assert_eq!(modified.format("%F %T").to_string(), "2021-05-04 12:30:00");
+
+        run(
+            &touch,
+            &mut vfs,
+            root,
+            &["-t", "199912312359.30", "new"],
+            None,
+        );
+        let metadata = &vfs.get_node(file).unwrap().metadata;
+        assert_eq!(
+            metadata.modified.format("%F %T").to_string(),
+            "1999-12-31 23:59:30"
+        );
+        assert_eq!(metadata.accessed, metadata.modified);
+        assert!(metadata.changed > metadata.modified);
+
+        run(&touch, &mut vfs, root, &["-t", "01020304", "new"], None);
+        let modified = vfs.get_node(file).unwrap().metadata.modified;
+        assert_eq!(modified.format("%m-%d %H:%M").to_string(), "01-02 03:04");
+
+        assert_eq!(
+            output(&run(
+                &touch,
+                &mut vfs,
+                root,
+                &["-d", "yesterday-ish", "new"],
+                None
+            )),
+            "touch: invalid date format 'yesterday-ish'"
+        );
+        assert_eq!(
+            output(&run(&touch, &mut vfs, root, &["-t", "2021", "new"], None)),
+            "touch: invalid date format '2021'"
+        );
+        assert_eq!(
+            output(&run(&touch, &mut vfs, root, &["/blog"], None)),
+            "touch: cannot touch '/blog': Permission denied"
+        );
+    }
+
+    #[test]
+    fn test_mv() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let mv = MvCommand::new();
+        vfs.create_directory(root, "dir").unwrap();
+        let file = vfs
+            .create_file(root, "file", FileContent::Dynamic("hi".to_string()))
+            .unwrap();
+        vfs.set_mode(file, 0o600).unwrap();
+        let time = TouchCommand::parse_date("2020-02-02").unwrap();
+        vfs.set_times(file, Some(time)).unwrap();
+
+        // Moving keeps the node itself, with its mode and times
+        assert_eq!(
+            output(&run(&mv, &mut vfs, root, &["file", "dir/renamed"], None)),
+            ""
+        );
+        assert_eq!(vfs.resolve_path(root, "dir/renamed").ok(), Some(file));
+        assert!(vfs.resolve_path(root, "file").is_err());
+        let node = vfs.get_node(file).unwrap();
+        assert_eq!(node.permissions.mode, 0o600);
+        assert_eq!(node.metadata.modified, time);
+
+        assert_eq!(
+            output(&run(&mv, &mut vfs, root, &["dir", "dir/inner"], None)),
+            "mv: cannot move 'dir' to a subdirectory of itself, 'dir/inner'"
+        );
+        vfs.create_directory(root, "other").unwrap();
+        assert_eq!(
+            output(&run(
+                &mv,
+                &mut vfs,
+                root,
+                &["dir/renamed", "other/dir"],
+                None
+            )),
+            ""
+        );
+        assert_eq!(
+            output(&run(&mv, &mut vfs, root, &["other/dir", "/"], None)),
+            "mv: cannot overwrite directory '/dir' with non-directory"
+        );
+        assert_eq!(
+            output(&run(&mv, &mut vfs, root, &["dir", "other/dir"], None)),
+            "mv: cannot overwrite non-directory 'other/dir' with directory 'dir'"
+        );
+        assert_eq!(
+            output(&run(&mv, &mut vfs, root, &["/blog", "/tmp"], None)),
+            "mv: cannot move '/blog': Permission denied"
+        );
+    }
 }
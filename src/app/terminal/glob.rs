@@ -0,0 +1,299 @@
+use indextree::NodeId;
+
+use super::vfs::VirtualFilesystem;
+
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+/// Backslash-escape glob characters so `text` only matches itself
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if GLOB_CHARS.contains(&c) || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn has_glob(component: &str) -> bool {
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if GLOB_CHARS.contains(&c) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else if prefix.ends_with('/') {
+        format!("{prefix}{name}")
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+/// Add the paths below `prefix`, or only the directories, without following links
+fn descendants(
+    vfs: &VirtualFilesystem,
+    current_dir: NodeId,
+    prefix: &str,
+    dirs_only: bool,
+    paths: &mut Vec<String>,
+) {
+    let dir_path = if prefix.is_empty() { "." } else { prefix };
+    let Ok(entries) = vfs
+        .resolve_path(current_dir, dir_path)
+        .and_then(|dir| vfs.list_directory(dir))
+    else {
+        return;
+    };
+    for entry in entries
+        .into_iter()
+        .filter(|entry| !entry.name.starts_with('.'))
+    {
+        let path = join(prefix, &entry.name);
+        // A link is never a directory entry, so links to directories aren't descended into
+        if entry.is_directory {
+            paths.push(path.clone());
+            descendants(vfs, current_dir, &path, dirs_only, paths);
+        } else if !dirs_only {
+            paths.push(path);
+        }
+    }
+}
+
+/// Expand `pattern` into the sorted paths it matches, relative to `current_dir` unless the
+/// pattern is absolute. Hidden entries only match components that start with a dot.
+/// A `**` component matches any number of directories, or everything below when it's last.
+/// Returns nothing when there are no matches, leaving the caller to keep the literal word.
+pub fn expand(vfs: &VirtualFilesystem, current_dir: NodeId, pattern: &str) -> Vec<String> {
+    let (mut paths, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (vec!["/".to_string()], rest),
+        None => (vec![String::new()], pattern),
+    };
+
+    let components = rest.split('/').collect::<Vec<_>>();
+    for (i, component) in components.iter().enumerate() {
+        let is_last = i == components.len() - 1;
+        let mut next = Vec::new();
+        for prefix in paths {
+            if *component == "**" {
+                if !is_last {
+                    next.push(prefix.clone());
+                }
+                descendants(vfs, current_dir, &prefix, !is_last, &mut next);
+                continue;
+            }
+            if !has_glob(component) {
+                next.push(join(&prefix, &unescape(component)));
+                continue;
+            }
+
+            let dir_path = if prefix.is_empty() { "." } else { &prefix };
+            let Ok(entries) = vfs
+                .resolve_path(current_dir, dir_path)
+                .and_then(|dir| vfs.list_directory(dir))
+            else {
+                continue;
+            };
+            next.extend(
+                entries
+                    .into_iter()
+                    .filter(|entry| !entry.name.starts_with('.') || component.starts_with('.'))
+                    .filter(|entry| matches(component, &entry.name))
+                    .map(|entry| join(&prefix, &entry.name)),
+            );
+        }
+        paths = next;
+    }
+
+    // Literal components and trailing slashes still have to exist
+    paths.retain(|path| match vfs.resolve_path(current_dir, path) {
+        Ok(node) => {
+            !path.ends_with('/') || vfs.get_node(node).is_some_and(|node| node.is_directory())
+        }
+        Err(_) => false,
+    });
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Match a single path component against a pattern using `*`, `?`, `[...]` and `\` escapes
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| matches_from(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => match (parse_class(&pattern[1..]), name.first()) {
+            (Some((class, len)), Some(c)) => {
+                class.contains(*c) && matches_from(&pattern[len + 1..], &name[1..])
+            }
+            (Some(_), None) => false,
+            // An unclosed bracket is just a bracket
+            (None, _) => name.first() == Some(&'[') && matches_from(&pattern[1..], &name[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            name.first() == Some(&pattern[1]) && matches_from(&pattern[2..], &name[1..])
+        }
+        Some(c) => name.first() == Some(c) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
+
+/// A `[...]` character class such as `[abc]`, `[a-z]` or `[!0-9]`
+struct CharClass {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    fn contains(&self, c: char) -> bool {
+        let in_ranges = self
+            .ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&c));
+        in_ranges != self.negated
+    }
+}
+
+/// Parse the class following a `[`, returning it and how many chars it used (including `]`)
+fn parse_class(pattern: &[char]) -> Option<(CharClass, usize)> {
+    let mut i = 0;
+    let negated = matches!(pattern.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let start = i;
+    while i < pattern.len() {
+        // A `]` straight after the opening bracket is a literal member
+        if pattern[i] == ']' && i > start {
+            return Some((CharClass { negated, ranges }, i + 1));
+        }
+        let c = pattern[i];
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|end| *end != ']') {
+            ranges.push((c, pattern[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::vfs::FileContent;
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*.txt", "notes.txt"));
+        assert!(matches("*", ""));
+        assert!(!matches("*.txt", "notes.md"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(matches("[abc]*", "blog"));
+        assert!(matches("[a-c]*", "cv"));
+        assert!(!matches("[!a-c]*", "cv"));
+        assert!(matches("[]x]", "]"));
+        assert!(matches("[x", "[x"));
+        assert!(matches(r"\*", "*"));
+        assert!(!matches(r"\*", "a"));
+        assert!(matches(&format!("{}*", escape("a[1]")), "a[1].txt"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut vfs = VirtualFilesystem::new(vec!["first".to_string(), "second".to_string()]);
+        let root = vfs.get_root();
+        vfs.create_file(root, "a.txt", FileContent::Dynamic(String::new()))
+            .unwrap();
+        vfs.create_file(root, "b.txt", FileContent::Dynamic(String::new()))
+            .unwrap();
+
+        assert_eq!(
+            expand(&vfs, root, "*.txt"),
+            vec!["a.txt", "b.txt", "thanks.txt"]
+        );
+        assert_eq!(
+            expand(&vfs, root, "blog/*"),
+            vec!["blog/first", "blog/nav.rs", "blog/second"]
+        );
+        assert_eq!(expand(&vfs, root, "/blog/s*"), vec!["/blog/second"]);
+        assert_eq!(expand(&vfs, root, "?.txt"), vec!["a.txt", "b.txt"]);
+
+        // Hidden files need an explicit dot
+        assert!(!expand(&vfs, root, "*").contains(&".zshrc".to_string()));
+        assert_eq!(expand(&vfs, root, ".z*"), vec![".zshrc"]);
+
+        // Trailing slashes only match directories
+        assert!(expand(&vfs, root, "*/")
+            .iter()
+            .all(|path| path.ends_with('/')));
+        assert!(expand(&vfs, root, "*/").contains(&"blog/".to_string()));
+        assert!(!expand(&vfs, root, "*/").contains(&"a.txt/".to_string()));
+
+        // Relative to the current directory
+        let blog = vfs.resolve_path(root, "/blog").unwrap();
+        assert_eq!(expand(&vfs, blog, "../?.txt"), vec!["../a.txt", "../b.txt"]);
+
+        assert!(expand(&vfs, root, "*.md").is_empty());
+        assert!(expand(&vfs, root, "missing/*").is_empty());
+    }
+
+    #[test]
+    fn test_expand_recursive() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let g = vfs.create_directory(root, "g").unwrap();
+        let a = vfs.create_directory(g, "a").unwrap();
+        let b = vfs.create_directory(a, "b").unwrap();
+        vfs.create_file(b, "x.txt", FileContent::Dynamic(String::new()))
+            .unwrap();
+        vfs.create_file(g, "y.txt", FileContent::Dynamic(String::new()))
+            .unwrap();
+        vfs.create_symlink(g, "link", "a").unwrap();
+
+        // Zero or more directories, without going through links
+        assert_eq!(
+            expand(&vfs, root, "/g/**/*.txt"),
+            vec!["/g/a/b/x.txt", "/g/y.txt"]
+        );
+        assert_eq!(expand(&vfs, g, "**/b"), vec!["a/b"]);
+        assert_eq!(
+            expand(&vfs, root, "g/**"),
+            vec!["g/a", "g/a/b", "g/a/b/x.txt", "g/link", "g/y.txt"]
+        );
+        assert!(expand(&vfs, root, "g/**/*.md").is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    EventNotFound(String),
+    SubstitutionFailed,
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::EventNotFound(event) => write!(f, "event not found: {event}"),
+            HistoryError::SubstitutionFailed => write!(f, "substitution failed"),
+        }
+    }
+}
+
+/// Expand `!!`, `!n`, `!-n`, `!prefix` and a leading `^old^new` against `history`
+/// (oldest first, numbered from 1 as `history` prints it).
+/// Returns `None` if the input has nothing to expand.
+pub fn expand_history(
+    input: &str,
+    history: &VecDeque<String>,
+) -> Result<Option<String>, HistoryError> {
+    if let Some(rest) = input.strip_prefix('^') {
+        return quick_substitution(rest, history).map(Some);
+    }
+    if !input.contains('!') {
+        return Ok(None);
+    }
+
+    let mut output = String::new();
+    let mut chars = input.chars().peekable();
+    let mut in_single_quotes = false;
+    let mut expanded = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_single_quotes = !in_single_quotes;
+                output.push(c);
+            }
+            // Leave escapes for the lexer, but don't expand an escaped `!`
+            '\\' if !in_single_quotes => {
+                output.push(c);
+                output.extend(chars.next());
+            }
+            '!' if !in_single_quotes => {
+                let event = match chars.peek() {
+                    Some('!') => {
+                        chars.next();
+                        "!".to_string()
+                    }
+                    Some(c) if c.is_ascii_digit() || *c == '-' => {
+                        let mut event = chars.next().map(String::from).unwrap_or_default();
+                        while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                            event.push(c);
+                        }
+                        event
+                    }
+                    Some(c) if !ends_event(*c) => {
+                        let mut event = String::new();
+                        while let Some(c) = chars.next_if(|c| !ends_event(*c)) {
+                            event.push(c);
+                        }
+                        event
+                    }
+                    // `!` before a space, `=` or the end of the line is just a `!`
+                    _ => {
+                        output.push(c);
+                        continue;
+                    }
+                };
+                output.push_str(find_event(&event, history)?);
+                expanded = true;
+            }
+            c => output.push(c),
+        }
+    }
+
+    Ok(expanded.then_some(output))
+}
+
+fn ends_event(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '=' | '(' | ')' | ';' | '|' | '&' | '<' | '>' | '"' | '\''
+        )
+}
+
+fn find_event<'a>(event: &str, history: &'a VecDeque<String>) -> Result<&'a str, HistoryError> {
+    let not_found = || HistoryError::EventNotFound(format!("!{event}"));
+    let found = if event == "!" {
+        history.back()
+    } else if let Some(offset) = event.strip_prefix('-') {
+        let offset = offset.parse::<usize>().map_err(|_| not_found())?;
+        history
+            .len()
+            .checked_sub(offset)
+            .filter(|_| offset > 0)
+            .and_then(|i| history.get(i))
+    } else if let Ok(number) = event.parse::<usize>() {
+        number.checked_sub(1).and_then(|i| history.get(i))
+    } else {
+        history.iter().rev().find(|cmd| cmd.starts_with(event))
+    };
+    found.map(String::as_str).ok_or_else(not_found)
+}
+
+/// `^old^new^` re-runs the previous command with the first `old` replaced by `new`
+fn quick_substitution(rest: &str, history: &VecDeque<String>) -> Result<String, HistoryError> {
+    let mut parts = rest.splitn(3, '^');
+    let old = parts.next().unwrap_or_default();
+    let new = parts.next().unwrap_or_default();
+    let suffix = parts.next().unwrap_or_default();
+
+    let last = history
+        .back()
+        .ok_or_else(|| HistoryError::EventNotFound("^".to_string()))?;
+    if old.is_empty() || !last.contains(old) {
+        return Err(HistoryError::SubstitutionFailed);
+    }
+    Ok(format!("{}{suffix}", last.replacen(old, new, 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> VecDeque<String> {
+        ["ls -la", "cd blog", "cat thanks.txt", "echo hello"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn expand(input: &str) -> Result<Option<String>, HistoryError> {
+        expand_history(input, &history())
+    }
+
+    #[test]
+    fn test_event_designators() {
+        assert_eq!(expand("!!"), Ok(Some("echo hello".to_string())));
+        assert_eq!(expand("!! | wc"), Ok(Some("echo hello | wc".to_string())));
+        assert_eq!(expand("!1"), Ok(Some("ls -la".to_string())));
+        assert_eq!(expand("!-2"), Ok(Some("cat thanks.txt".to_string())));
+        assert_eq!(expand("!c"), Ok(Some("cat thanks.txt".to_string())));
+        assert_eq!(
+            expand("!cd && !!"),
+            Ok(Some("cd blog && echo hello".to_string()))
+        );
+        assert_eq!(
+            expand("echo \"!!\""),
+            Ok(Some("echo \"echo hello\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_literal_bangs() {
+        assert_eq!(expand("ls"), Ok(None));
+        assert_eq!(expand("echo hi!"), Ok(None));
+        assert_eq!(expand("echo ! x"), Ok(None));
+        assert_eq!(expand("echo '!!'"), Ok(None));
+        assert_eq!(expand(r"echo \!!"), Ok(None));
+    }
+
+    #[test]
+    fn test_event_not_found() {
+        let not_found = |event: &str| Err(HistoryError::EventNotFound(event.to_string()));
+        assert_eq!(expand("!99"), not_found("!99"));
+        assert_eq!(expand("!0"), not_found("!0"));
+        assert_eq!(expand("!-9"), not_found("!-9"));
+        assert_eq!(expand("!-"), not_found("!-"));
+        assert_eq!(expand("!vim"), not_found("!vim"));
+        assert_eq!(expand_history("!!", &VecDeque::new()), not_found("!!"));
+        assert_eq!(
+            HistoryError::EventNotFound("!vim".to_string()).to_string(),
+            "event not found: !vim"
+        );
+    }
+
+    #[test]
+    fn test_quick_substitution() {
+        assert_eq!(expand("^hello^world"), Ok(Some("echo world".to_string())));
+        assert_eq!(
+            expand("^hello^world^!"),
+            Ok(Some("echo world!".to_string()))
+        );
+        assert_eq!(expand("^hello^"), Ok(Some("echo ".to_string())));
+        assert_eq!(expand("^nope^x"), Err(HistoryError::SubstitutionFailed));
+    }
+}
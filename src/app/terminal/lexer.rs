@@ -0,0 +1,470 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::env_tools::is_valid_name;
+use super::glob;
+
+/// One piece of a shell word, keeping track of how it was quoted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart {
+    Bare(String),   // Unquoted text
+    Quoted(String), // Text inside quotes or escaped with a backslash
+    Var(String),    // `$VAR` or `${VAR}`, expanded when the word is evaluated
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Word(pub Vec<WordPart>);
+
+impl Word {
+    /// Substitute variables using `lookup`, treating unknown variables as empty
+    pub fn expand(&self, lookup: impl Fn(&str) -> Option<String>) -> String {
+        self.0
+            .iter()
+            .map(|part| match part {
+                WordPart::Bare(s) | WordPart::Quoted(s) => s.clone(),
+                WordPart::Var(name) => lookup(name).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// True when the word is nothing but variable references (so may expand to nothing at all)
+    pub fn is_only_vars(&self) -> bool {
+        self.0.iter().all(|part| matches!(part, WordPart::Var(_)))
+    }
+
+    /// The word as a glob pattern, if it has an unquoted `*`, `?` or `[`.
+    /// Quoted text and variable values are escaped so they only match literally.
+    pub fn glob_pattern(&self, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+        let is_glob = self
+            .0
+            .iter()
+            .any(|part| matches!(part, WordPart::Bare(s) if s.contains(['*', '?', '['])));
+        if !is_glob {
+            return None;
+        }
+
+        let pattern = self
+            .0
+            .iter()
+            .map(|part| match part {
+                WordPart::Bare(s) => s.clone(),
+                WordPart::Quoted(s) => glob::escape(s),
+                WordPart::Var(name) => glob::escape(&lookup(name).unwrap_or_default()),
+            })
+            .collect();
+        Some(pattern)
+    }
+
+    /// The word's text without expanding variables (used for tab completion)
+    pub fn literal(&self) -> String {
+        self.0
+            .iter()
+            .map(|part| match part {
+                WordPart::Bare(s) | WordPart::Quoted(s) => s.clone(),
+                WordPart::Var(name) => format!("${name}"),
+            })
+            .collect()
+    }
+
+    /// Split a `NAME=value` word into the name and the (unexpanded) value
+    pub fn assignment(&self) -> Option<(String, Word)> {
+        let Some(WordPart::Bare(first)) = self.0.first() else {
+            return None;
+        };
+        let (name, rest) = first.split_once('=')?;
+        if !is_valid_name(name) {
+            return None;
+        }
+
+        let mut value = Vec::new();
+        if !rest.is_empty() {
+            value.push(WordPart::Bare(rest.to_string()));
+        }
+        value.extend(self.0[1..].iter().cloned());
+        Some((name.to_string(), Word(value)))
+    }
+
+    fn push_char(&mut self, c: char, quoted: bool) {
+        match (self.0.last_mut(), quoted) {
+            (Some(WordPart::Bare(s)), false) | (Some(WordPart::Quoted(s)), true) => s.push(c),
+            (_, false) => self.0.push(WordPart::Bare(c.to_string())),
+            (_, true) => self.0.push(WordPart::Quoted(c.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Word(Word),
+    Pipe,                              // |
+    Semi,                              // ;
+    Newline,                           // A newline, which may also end a blank line or comment
+    And,                               // &&
+    Or,                                // ||
+    Redirect { fd: u8, append: bool }, // >, >>, 2>, 2>>
+    StderrToStdout,                    // 2>&1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexError {
+    UnmatchedQuote(char),
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::UnmatchedQuote(q) => write!(f, "unmatched {q}"),
+        }
+    }
+}
+
+/// Split a command line into tokens following POSIX shell quoting rules:
+/// single quotes are literal, double quotes allow `$VAR` and a few escapes,
+/// and an unquoted backslash escapes the next character.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, LexError> {
+    let mut lexer = Lexer {
+        chars: input.chars().peekable(),
+        tokens: Vec::new(),
+        word: None,
+    };
+    lexer.run()?;
+    Ok(lexer.tokens)
+}
+
+/// Like `tokenize`, but an unterminated quote just ends the final word.
+/// Used for tab completion, where the line is still being typed.
+pub fn tokenize_partial(input: &str) -> Vec<Token> {
+    let mut lexer = Lexer {
+        chars: input.chars().peekable(),
+        tokens: Vec::new(),
+        word: None,
+    };
+    let _ = lexer.run();
+    lexer.finish_word();
+    lexer.tokens
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    tokens: Vec<Token>,
+    word: Option<Word>, // The word currently being built, if any
+}
+
+impl Lexer<'_> {
+    fn run(&mut self) -> Result<(), LexError> {
+        while let Some(c) = self.chars.next() {
+            match c {
+                ';' => {
+                    self.finish_word();
+                    self.tokens.push(Token::Semi);
+                }
+                '\n' => {
+                    self.finish_word();
+                    self.tokens.push(Token::Newline);
+                }
+                c if c.is_whitespace() => self.finish_word(),
+                '#' if self.word.is_none() => {
+                    // Comment runs to the end of the line
+                    while self.chars.next_if(|c| *c != '\n').is_some() {}
+                }
+                '\'' => {
+                    let word = self.word.get_or_insert_with(Word::default);
+                    // Make sure '' still produces an (empty) word
+                    word.0.push(WordPart::Quoted(String::new()));
+                    loop {
+                        match self.chars.next() {
+                            Some('\'') => break,
+                            Some(c) => word.push_char(c, true),
+                            None => return Err(LexError::UnmatchedQuote('\'')),
+                        }
+                    }
+                }
+                '"' => self.double_quoted()?,
+                '\\' => match self.chars.next() {
+                    // Line continuation
+                    Some('\n') => {}
+                    Some(c) => self.push_char(c, true),
+                    None => self.push_char('\\', false),
+                },
+                '$' => self.variable(false),
+                '|' => {
+                    self.finish_word();
+                    if self.chars.next_if_eq(&'|').is_some() {
+                        self.tokens.push(Token::Or);
+                    } else {
+                        self.tokens.push(Token::Pipe);
+                    }
+                }
+                '&' if self.chars.next_if_eq(&'&').is_some() => {
+                    self.finish_word();
+                    self.tokens.push(Token::And);
+                }
+                '>' => self.redirect(),
+                c => self.push_char(c, false),
+            }
+        }
+        self.finish_word();
+        Ok(())
+    }
+
+    fn double_quoted(&mut self) -> Result<(), LexError> {
+        self.word
+            .get_or_insert_with(Word::default)
+            .0
+            .push(WordPart::Quoted(String::new()));
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(()),
+                Some('\\') => match self.chars.next_if(|c| matches!(c, '$' | '`' | '"' | '\\')) {
+                    Some(c) => self.push_char(c, true),
+                    None if self.chars.next_if_eq(&'\n').is_some() => {}
+                    None => self.push_char('\\', true),
+                },
+                Some('$') => self.variable(true),
+                Some(c) => self.push_char(c, true),
+                None => return Err(LexError::UnmatchedQuote('"')),
+            }
+        }
+    }
+
+    /// Called just after a `$`: read `NAME`, `{NAME}` or a special parameter
+    fn variable(&mut self, quoted: bool) {
+        let name = match self.chars.peek() {
+            Some('{') => {
+                let mut lookahead = self.chars.clone();
+                lookahead.next();
+                let name = lookahead
+                    .by_ref()
+                    .take_while(|c| *c != '}')
+                    .collect::<String>();
+                self.chars = lookahead;
+                name
+            }
+            Some(c) if matches!(c, '?' | '#' | '@' | '$') || c.is_ascii_digit() => {
+                self.chars.next().map(String::from).unwrap_or_default()
+            }
+            Some(c) if c.is_alphabetic() || *c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                name
+            }
+            // A lone `$` is just a dollar sign
+            _ => {
+                self.push_char('$', quoted);
+                return;
+            }
+        };
+        self.word
+            .get_or_insert_with(Word::default)
+            .0
+            .push(WordPart::Var(name));
+    }
+
+    /// Called just after an unquoted `>`
+    fn redirect(&mut self) {
+        // A lone 1 or 2 directly in front of `>` names the file descriptor
+        let fd = match self.word.as_ref().map(|w| w.0.as_slice()) {
+            Some([WordPart::Bare(s)]) if s == "1" || s == "2" => {
+                let fd = if s == "1" { 1 } else { 2 };
+                self.word = None;
+                fd
+            }
+            _ => {
+                self.finish_word();
+                1
+            }
+        };
+
+        if fd == 2 && self.chars.peek() == Some(&'&') {
+            let mut lookahead = self.chars.clone();
+            lookahead.next();
+            if lookahead.next() == Some('1') {
+                self.chars = lookahead;
+                self.tokens.push(Token::StderrToStdout);
+                return;
+            }
+        }
+
+        let append = self.chars.next_if_eq(&'>').is_some();
+        self.tokens.push(Token::Redirect { fd, append });
+    }
+
+    fn push_char(&mut self, c: char, quoted: bool) {
+        self.word
+            .get_or_insert_with(Word::default)
+            .push_char(c, quoted);
+    }
+
+    fn finish_word(&mut self) {
+        if let Some(word) = self.word.take() {
+            self.tokens.push(Token::Word(word));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tokenize and expand every word with a fixed environment
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word.expand(|name| match name {
+                    "HOME" => Some("/".to_string()),
+                    "USER" => Some("user".to_string()),
+                    _ => None,
+                })),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_whitespace_and_quotes() {
+        assert_eq!(words("echo  hello   world"), vec!["echo", "hello", "world"]);
+        assert_eq!(
+            words(r#"echo "hello   world""#),
+            vec!["echo", "hello   world"]
+        );
+        assert_eq!(words("touch 'my file.txt'"), vec!["touch", "my file.txt"]);
+        assert_eq!(
+            words(r#"echo "it's" 'say "hi"'"#),
+            vec!["echo", "it's", r#"say "hi""#]
+        );
+        assert_eq!(words(r#"echo a"b"'c'd"#), vec!["echo", "abcd"]);
+        assert_eq!(words(r#"echo "" ''"#), vec!["echo", "", ""]);
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(words(r"touch my\ file.txt"), vec!["touch", "my file.txt"]);
+        assert_eq!(
+            words(r"echo \$HOME \\ \'"),
+            vec!["echo", "$HOME", "\\", "'"]
+        );
+        assert_eq!(
+            words(r#"echo "a\"b" "\$x" "\n""#),
+            vec!["echo", "a\"b", "$x", "\\n"]
+        );
+        assert_eq!(words(r"echo 'a\b'"), vec!["echo", r"a\b"]);
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(words("echo $HOME"), vec!["echo", "/"]);
+        assert_eq!(words("echo '$HOME'"), vec!["echo", "$HOME"]);
+        assert_eq!(words(r#"echo "$USER@$HOME""#), vec!["echo", "user@/"]);
+        assert_eq!(
+            words("echo ${USER}name $USERname"),
+            vec!["echo", "username", ""]
+        );
+        assert_eq!(words("echo $ cost$"), vec!["echo", "$", "cost$"]);
+
+        let tokens = tokenize("echo $? $1 $#").unwrap();
+        assert_eq!(
+            tokens[1..],
+            ["?", "1", "#"].map(|name| Token::Word(Word(vec![WordPart::Var(name.to_string())])))
+        );
+    }
+
+    #[test]
+    fn test_assignments() {
+        let assignment = |input: &str| match &tokenize(input).unwrap()[0] {
+            Token::Word(word) => word
+                .assignment()
+                .map(|(name, value)| (name, value.expand(|_| Some("x".to_string())))),
+            _ => None,
+        };
+        assert_eq!(assignment("FOO=bar"), Some(("FOO".into(), "bar".into())));
+        assert_eq!(assignment("FOO="), Some(("FOO".into(), "".into())));
+        assert_eq!(
+            assignment("A_1=\"a b\"$V"),
+            Some(("A_1".into(), "a bx".into()))
+        );
+        assert_eq!(assignment("1A=b"), None);
+        assert_eq!(assignment("'FOO'=bar"), None);
+        assert_eq!(assignment("echo"), None);
+    }
+
+    #[test]
+    fn test_operators() {
+        let tokens = tokenize("cat a|grep 'x|y' > out 2>&1").unwrap();
+        assert_eq!(tokens.len(), 8);
+        assert_eq!(tokens[2], Token::Pipe);
+        assert_eq!(
+            tokens[5],
+            Token::Redirect {
+                fd: 1,
+                append: false
+            }
+        );
+        assert_eq!(tokens[7], Token::StderrToStdout);
+
+        let tokens = tokenize("ls 2>>err x2>y").unwrap();
+        assert_eq!(
+            tokens[1],
+            Token::Redirect {
+                fd: 2,
+                append: true
+            }
+        );
+        assert_eq!(
+            tokens[3],
+            Token::Word(Word(vec![WordPart::Bare("x2".to_string())]))
+        );
+        assert_eq!(
+            tokens[4],
+            Token::Redirect {
+                fd: 1,
+                append: false
+            }
+        );
+
+        let tokens = tokenize("mkdir a&&cd a || echo x; ls\npwd").unwrap();
+        let operators = tokens
+            .into_iter()
+            .filter(|token| !matches!(token, Token::Word(_)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            operators,
+            vec![Token::And, Token::Or, Token::Semi, Token::Newline]
+        );
+        assert_eq!(words("echo a & b"), vec!["echo", "a", "&", "b"]);
+
+        // Quoted operators are plain text
+        assert_eq!(
+            words(r#"echo ">" \| '2>&1' "a;b" '&&'"#),
+            vec!["echo", ">", "|", "2>&1", "a;b", "&&"]
+        );
+    }
+
+    #[test]
+    fn test_comments_and_errors() {
+        assert_eq!(words("echo hi # a comment"), vec!["echo", "hi"]);
+        assert_eq!(
+            tokenize("# comment\nls").unwrap(),
+            vec![
+                Token::Newline,
+                Token::Word(Word(vec![WordPart::Bare("ls".to_string())]))
+            ]
+        );
+        assert_eq!(words("echo a#b"), vec!["echo", "a#b"]);
+        assert_eq!(tokenize("echo 'oops"), Err(LexError::UnmatchedQuote('\'')));
+        assert_eq!(tokenize("echo \"oops"), Err(LexError::UnmatchedQuote('"')));
+
+        // Partial input keeps the unfinished word for tab completion
+        let tokens = tokenize_partial("cat 'my fi");
+        assert_eq!(tokens.len(), 2);
+        if let Token::Word(word) = &tokens[1] {
+            assert_eq!(word.literal(), "my fi");
+        } else {
+            panic!("expected a word");
+        }
+    }
+}
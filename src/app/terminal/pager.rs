@@ -0,0 +1,422 @@
+use std::sync::Arc;
+
+use indextree::NodeId;
+use regex::Regex;
+
+use super::command::{Cmd, CommandRes, VfsCommand};
+use super::fs_tools::parse_multitarget;
+use super::vfs::{VfsError, VfsNodeType, VirtualFilesystem};
+
+/// Lines on one screen of the pager, about what fits in the header's output panel.
+/// TTY output taller than this is paged automatically.
+pub const PAGE_LINES: usize = 24;
+
+/// A less(1) command bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagerAction {
+    LineDown,  // j, Enter, ArrowDown
+    LineUp,    // k, ArrowUp
+    PageDown,  // Space, f, PageDown
+    PageUp,    // b, PageUp
+    Top,       // g, Home
+    Bottom,    // G, End
+    Search,    // /: start typing a pattern
+    NextMatch, // n
+    PrevMatch, // N
+    Quit,      // q, Escape
+}
+
+impl PagerAction {
+    /// The action for a key press, going by `KeyboardEvent.key`
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "j" | "Enter" | "ArrowDown" => Some(Self::LineDown),
+            "k" | "ArrowUp" => Some(Self::LineUp),
+            " " | "f" | "PageDown" => Some(Self::PageDown),
+            "b" | "PageUp" => Some(Self::PageUp),
+            "g" | "Home" => Some(Self::Top),
+            "G" | "End" => Some(Self::Bottom),
+            "/" => Some(Self::Search),
+            "n" => Some(Self::NextMatch),
+            "N" => Some(Self::PrevMatch),
+            "q" | "Q" | "Escape" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Part of a paged line, `matched` if it's a match for the current search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagerSegment {
+    pub text: String,
+    pub matched: bool,
+}
+
+/// The state of `less`: which lines are on screen and the search, independent of the DOM
+#[derive(Debug, Clone)]
+pub struct Pager {
+    lines: Arc<Vec<String>>,
+    height: usize,
+    top: usize,
+    pattern: Option<Regex>,
+    input: Option<String>, // The `/pattern` being typed, if any
+    message: Option<String>,
+}
+
+impl Pager {
+    pub fn new(text: &str, height: usize) -> Self {
+        Self {
+            lines: Arc::new(text.lines().map(str::to_string).collect()),
+            height: height.max(1),
+            top: 0,
+            pattern: None,
+            input: None,
+            message: None,
+        }
+    }
+
+    /// Handle a key press, returning false once the pager should close
+    pub fn handle_key(&mut self, key: &str) -> bool {
+        if let Some(input) = &mut self.input {
+            match key {
+                "Enter" => {
+                    let input = self.input.take().unwrap_or_default();
+                    self.start_search(&input);
+                }
+                "Escape" => self.input = None,
+                "Backspace" => {
+                    // Deleting past the `/` cancels the search, as in less
+                    if input.pop().is_none() {
+                        self.input = None;
+                    }
+                }
+                _ if key.chars().count() == 1 => input.push_str(key),
+                _ => {}
+            }
+            return true;
+        }
+
+        self.message = None;
+        match PagerAction::from_key(key) {
+            Some(PagerAction::Quit) => return false,
+            Some(action) => self.apply(action),
+            None => {}
+        }
+        true
+    }
+
+    pub fn apply(&mut self, action: PagerAction) {
+        // A search can leave the last match at the top of the screen, past the last
+        // full page, so scrolling only clamps to the end when it gets there
+        let last_top = self.last_top().max(self.top);
+        match action {
+            PagerAction::LineDown => self.top = (self.top + 1).min(last_top),
+            PagerAction::LineUp => self.top = self.top.saturating_sub(1),
+            PagerAction::PageDown => self.top = (self.top + self.height).min(last_top),
+            PagerAction::PageUp => self.top = self.top.saturating_sub(self.height),
+            PagerAction::Top => self.top = 0,
+            PagerAction::Bottom => self.top = self.last_top(),
+            PagerAction::Search => self.input = Some(String::new()),
+            PagerAction::NextMatch => self.find_match(true),
+            PagerAction::PrevMatch => self.find_match(false),
+            PagerAction::Quit => {}
+        }
+    }
+
+    fn last_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    fn start_search(&mut self, input: &str) {
+        // An empty pattern repeats the last search
+        if !input.is_empty() {
+            match Regex::new(input) {
+                Ok(regex) => self.pattern = Some(regex),
+                Err(_) => {
+                    self.message = Some("Invalid pattern".to_string());
+                    return;
+                }
+            }
+        }
+        self.find_match(true);
+    }
+
+    /// Move the next (or previous) line with a match to the top of the screen
+    fn find_match(&mut self, forward: bool) {
+        let Some(pattern) = &self.pattern else {
+            self.message = Some("No previous regular expression".to_string());
+            return;
+        };
+        let is_match = |i: &usize| pattern.is_match(&self.lines[*i]);
+        let found = if forward {
+            (self.top + 1..self.lines.len()).find(is_match)
+        } else {
+            (0..self.top).rev().find(is_match)
+        };
+        match found {
+            Some(line) => self.top = line,
+            None => self.message = Some("Pattern not found".to_string()),
+        }
+    }
+
+    /// The lines on screen, split into segments so search matches can be highlighted
+    pub fn visible_lines(&self) -> Vec<Vec<PagerSegment>> {
+        let end = (self.top + self.height).min(self.lines.len());
+        self.lines[self.top..end]
+            .iter()
+            .map(|line| self.segments(line))
+            .collect()
+    }
+
+    fn segments(&self, line: &str) -> Vec<PagerSegment> {
+        let segment = |text: &str, matched| PagerSegment {
+            text: text.to_string(),
+            matched,
+        };
+        let Some(pattern) = &self.pattern else {
+            return vec![segment(line, false)];
+        };
+        let mut segments = Vec::new();
+        let mut last = 0;
+        for found in pattern.find_iter(line).filter(|found| !found.is_empty()) {
+            if found.start() > last {
+                segments.push(segment(&line[last..found.start()], false));
+            }
+            segments.push(segment(found.as_str(), true));
+            last = found.end();
+        }
+        if last < line.len() || segments.is_empty() {
+            segments.push(segment(&line[last..], false));
+        }
+        segments
+    }
+
+    /// The bottom line of the screen: the search being typed, a message, or the prompt
+    pub fn status(&self) -> String {
+        if let Some(input) = &self.input {
+            return format!("/{input}");
+        }
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        if self.top + self.height >= self.lines.len() {
+            "(END)".to_string()
+        } else {
+            ":".to_string()
+        }
+    }
+}
+
+/// `less`, or `more` which pages the same way
+pub struct LessCommand {
+    cmd: Cmd,
+}
+
+impl LessCommand {
+    pub fn new(cmd: Cmd) -> Self {
+        Self { cmd }
+    }
+}
+
+impl VfsCommand for LessCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        is_tty: bool,
+    ) -> CommandRes {
+        let (options, targets) = parse_multitarget(args);
+
+        let usage = self.cmd.usage().expect("pagers have a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+
+        let mut stdout_parts = Vec::new();
+        let mut stderr_parts = Vec::new();
+        if targets.is_empty() {
+            let Some(input) = stdin else {
+                let error_msg = format!(
+                    "Missing filename (\"{} --help\" for help)",
+                    self.cmd.as_str()
+                );
+                return CommandRes::new().with_error().with_stderr(error_msg);
+            };
+            stdout_parts.push(input.to_string());
+        }
+
+        for target in targets {
+            let node_id = match vfs.resolve_path(current_dir, target) {
+                Ok(node_id) => node_id,
+                Err(_) => {
+                    stderr_parts.push(format!("{target}: No such file or directory"));
+                    continue;
+                }
+            };
+            let is_dir = vfs
+                .get_node(node_id)
+                .is_some_and(|node| matches!(node.node_type, VfsNodeType::Directory));
+            if is_dir {
+                stderr_parts.push(format!("{target} is a directory"));
+                continue;
+            }
+            match vfs.read_file(node_id) {
+                Ok(content) => stdout_parts.push(content),
+                Err(VfsError::SystemError(msg)) => stderr_parts.push(format!("{target}: {msg}")),
+                Err(_) => stderr_parts.push(format!("{target}: Permission denied")),
+            }
+        }
+
+        let mut result = CommandRes::new();
+        if !stderr_parts.is_empty() {
+            result = result.with_error().with_stderr(stderr_parts.join("\n"));
+        }
+        if stdout_parts.is_empty() {
+            return result;
+        }
+        // Like less, just copy the input when it isn't writing to a terminal
+        let result = result.with_stdout_text(stdout_parts.concat());
+        if is_tty {
+            result.with_pager()
+        } else {
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::vfs::FileContent;
+    use super::*;
+
+    fn numbered(count: usize) -> String {
+        (1..=count)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn first_line(pager: &Pager) -> String {
+        pager.visible_lines()[0]
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_pager_scrolling() {
+        let mut pager = Pager::new(&numbered(50), 10);
+        assert_eq!(pager.visible_lines().len(), 10);
+        assert_eq!(pager.status(), ":");
+
+        for (key, expected) in [
+            ("j", "line 2"),
+            ("Enter", "line 3"),
+            ("k", "line 2"),
+            (" ", "line 12"),
+            ("b", "line 2"),
+            ("b", "line 1"),
+            ("G", "line 41"),
+            ("j", "line 41"),
+            (" ", "line 41"),
+            ("g", "line 1"),
+        ] {
+            assert!(pager.handle_key(key));
+            assert_eq!(first_line(&pager), expected, "after {key:?}");
+        }
+        pager.handle_key("G");
+        assert_eq!(pager.status(), "(END)");
+
+        // Keys that aren't bound are ignored, and q closes the pager
+        assert!(pager.handle_key("x"));
+        assert!(!pager.handle_key("q"));
+
+        // Short input fits on one screen
+        let pager = Pager::new("one\ntwo", 10);
+        assert_eq!(pager.visible_lines().len(), 2);
+        assert_eq!(pager.status(), "(END)");
+    }
+
+    #[test]
+    fn test_pager_search() {
+        let mut pager = Pager::new(&numbered(50), 10);
+        for key in ["/", "4", "x"] {
+            pager.handle_key(key);
+        }
+        assert_eq!(pager.status(), "/4x");
+        pager.handle_key("Backspace");
+        pager.handle_key("Enter");
+        assert_eq!(first_line(&pager), "line 4");
+        assert_eq!(
+            pager.visible_lines()[0],
+            vec![
+                PagerSegment {
+                    text: "line ".to_string(),
+                    matched: false
+                },
+                PagerSegment {
+                    text: "4".to_string(),
+                    matched: true
+                },
+            ]
+        );
+
+        // n and N move between matches, and a search can go past the last full page
+        pager.handle_key("n");
+        assert_eq!(first_line(&pager), "line 14");
+        for _ in 0..4 {
+            pager.handle_key("n");
+        }
+        assert_eq!(first_line(&pager), "line 41");
+        pager.handle_key("n");
+        assert_eq!(first_line(&pager), "line 42");
+        pager.handle_key("N");
+        assert_eq!(first_line(&pager), "line 41");
+
+        pager.handle_key("g");
+        for key in ["/", "n", "o", "p", "e", "Enter"] {
+            pager.handle_key(key);
+        }
+        assert_eq!(pager.status(), "Pattern not found");
+        assert_eq!(first_line(&pager), "line 1");
+        pager.handle_key("j");
+        assert_eq!(pager.status(), ":");
+
+        // Escape abandons a search without closing the pager
+        pager.handle_key("/");
+        assert!(pager.handle_key("Escape"));
+        assert_eq!(pager.status(), ":");
+    }
+
+    #[test]
+    fn test_less() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        vfs.create_file(
+            root,
+            "notes.txt",
+            FileContent::Dynamic("one\ntwo\n".to_string()),
+        )
+        .unwrap();
+        let less = LessCommand::new(Cmd::Less);
+
+        let res = less.execute(&mut vfs, root, vec!["notes.txt"], None, true);
+        assert_eq!(res.pager_text(PAGE_LINES), Some("one\ntwo\n"));
+
+        // Piped on, less behaves like cat
+        let res = less.execute(&mut vfs, root, vec!["notes.txt"], None, false);
+        assert_eq!(res.pager_text(PAGE_LINES), None);
+
+        let res = less.execute(&mut vfs, root, vec![], Some("piped"), true);
+        assert_eq!(res.pager_text(PAGE_LINES), Some("piped"));
+
+        let res = less.execute(&mut vfs, root, vec!["missing"], None, true);
+        assert!(res.is_error());
+        assert_eq!(res.pager_text(PAGE_LINES), None);
+        let res = less.execute(&mut vfs, root, vec![], None, true);
+        assert!(res.is_error());
+    }
+}
@@ -0,0 +1,696 @@
+use indextree::NodeId;
+
+use super::command::{Cmd, CommandRes, VfsCommand};
+use super::fs_tools::parse_multitarget;
+use super::vfs::{VfsNode, VfsNodeType, VirtualFilesystem, USERS};
+
+/// Apply a mode to `mode`, as chmod does: either octal like `755`, or comma-separated symbolic
+/// clauses like `u+x,go-w` or `a=r`. Clauses without a `ugoa` part leave out the `umask` bits.
+/// Returns `None` if the mode can't be parsed.
+pub fn apply_mode(spec: &str, mode: u16, is_dir: bool, umask: u16) -> Option<u16> {
+    if !spec.is_empty() && spec.chars().all(|c| c.is_digit(8)) {
+        return u16::from_str_radix(spec, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777)
+            .map(|mode| mode & 0o777);
+    }
+
+    let mut mode = mode;
+    for clause in spec.split(',') {
+        let mut chars = clause.chars().peekable();
+        let mut who = 0;
+        while let Some(c) = chars.peek() {
+            who |= match c {
+                'u' => 0o700,
+                'g' => 0o070,
+                'o' => 0o007,
+                'a' => 0o777,
+                _ => break,
+            };
+            chars.next();
+        }
+        let mask = if who == 0 { 0o777 & !umask } else { who };
+        let who = if who == 0 { 0o777 } else { who };
+
+        // Each clause needs at least one operator, and each operator may be followed by permissions
+        chars.peek()?;
+        while let Some(op) = chars.next() {
+            if !matches!(op, '+' | '-' | '=') {
+                return None;
+            }
+            let mut bits = 0;
+            while let Some(c) = chars.peek() {
+                bits |= match c {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    // Execute only for directories and files that are already executable
+                    'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                    'X' => 0,
+                    _ => break,
+                };
+                chars.next();
+            }
+            let bits = bits & mask;
+            mode = match op {
+                '+' => mode | bits,
+                '-' => mode & !bits,
+                _ => (mode & !who) | bits,
+            };
+        }
+    }
+    Some(mode)
+}
+
+/// The mode as `stat` and `umask -S` spell each class, like `u=rwx,g=rx,o=rx`
+fn symbolic_mode(mode: u16) -> String {
+    [('u', 6), ('g', 3), ('o', 0)]
+        .iter()
+        .map(|(who, shift)| {
+            let bits = mode >> shift;
+            let perms = [(0o4, 'r'), (0o2, 'w'), (0o1, 'x')]
+                .iter()
+                .filter(|(bit, _)| bits & bit != 0)
+                .map(|(_, c)| c)
+                .collect::<String>();
+            format!("{who}={perms}")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Apply `change` to `node_id`, and with -R to everything below it. As with `chmod -R`,
+/// symbolic links found along the way are skipped rather than followed.
+fn change_tree(
+    cmd: Cmd,
+    vfs: &mut VirtualFilesystem,
+    node_id: NodeId,
+    path: &str,
+    recursive: bool,
+    errors: &mut Vec<String>,
+    change: &mut dyn FnMut(&mut VirtualFilesystem, NodeId, &str) -> Result<(), String>,
+) {
+    if let Err(e) = change(vfs, node_id, path) {
+        errors.push(e);
+    }
+
+    let is_dir = vfs.get_node(node_id).is_some_and(VfsNode::is_directory);
+    if !recursive || !is_dir {
+        return;
+    }
+    let Ok(entries) = vfs.list_directory(node_id) else {
+        let name = cmd.as_str();
+        errors.push(format!(
+            "{name}: cannot read directory '{path}': Permission denied"
+        ));
+        return;
+    };
+    for entry in entries {
+        let is_link = vfs
+            .get_node(entry.node_id)
+            .is_some_and(|node| node.link_target().is_some());
+        if is_link {
+            continue;
+        }
+        let child_path = format!("{}/{}", path.trim_end_matches('/'), entry.name);
+        change_tree(
+            cmd,
+            vfs,
+            entry.node_id,
+            &child_path,
+            recursive,
+            errors,
+            change,
+        );
+    }
+}
+
+/// Split arguments into `-R` and the rest, keeping modes like `-w` as operands for chmod
+fn split_recursive(cmd: Cmd, args: Vec<&str>) -> Result<(bool, Vec<&str>), CommandRes> {
+    let usage = cmd.usage().expect("permission commands have usage specs");
+    let mut recursive = false;
+    let mut operands = Vec::new();
+    for arg in args {
+        let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+            operands.push(arg);
+            continue;
+        };
+        if flags.chars().all(|c| c == 'R') {
+            recursive = true;
+        } else if cmd == Cmd::Chmod && flags.chars().all(|c| "rwxX".contains(c)) {
+            operands.push(arg);
+        } else {
+            let c = flags.chars().find(|c| !usage.supports(*c)).unwrap_or('-');
+            return Err(CommandRes::new()
+                .with_error()
+                .with_stderr(usage.invalid_option(c)));
+        }
+    }
+    Ok((recursive, operands))
+}
+
+/// Combine the errors from changing several files into one result
+fn errors_result(errors: Vec<String>) -> CommandRes {
+    if errors.is_empty() {
+        CommandRes::new()
+    } else {
+        CommandRes::new()
+            .with_error()
+            .with_stderr(errors.join("\n"))
+    }
+}
+
+pub struct ChmodCommand;
+
+impl ChmodCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for ChmodCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (recursive, operands) = match split_recursive(Cmd::Chmod, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let Some((spec, targets)) = operands.split_first() else {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("chmod: missing operand");
+        };
+        if targets.is_empty() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr(format!("chmod: missing operand after '{spec}'"));
+        }
+        // Catch a bad mode before changing anything
+        if apply_mode(spec, 0, false, 0).is_none() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr(format!("chmod: invalid mode: '{spec}'"));
+        }
+
+        let umask = vfs.umask();
+        let mut errors = Vec::new();
+        for target in targets {
+            // Like chmod itself, this changes what a link points at, not the link
+            let Ok(node_id) = vfs.resolve_path(current_dir, target) else {
+                errors.push(format!(
+                    "chmod: cannot access '{target}': No such file or directory"
+                ));
+                continue;
+            };
+            change_tree(
+                Cmd::Chmod,
+                vfs,
+                node_id,
+                target,
+                recursive,
+                &mut errors,
+                &mut |vfs, node_id, path| {
+                    let node = vfs.get_node(node_id).expect("changed nodes exist");
+                    let mode = node.permissions.mode;
+                    let mode = apply_mode(spec, mode, node.is_directory(), umask)
+                        .expect("mode was checked");
+                    vfs.set_mode(node_id, mode).map_err(|_| {
+                        format!("chmod: changing permissions of '{path}': Operation not permitted")
+                    })
+                },
+            );
+        }
+
+        errors_result(errors)
+    }
+}
+
+pub struct ChownCommand;
+
+impl ChownCommand {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Split `owner[:group]` into its parts. `owner:` means the owner's own group.
+    fn parse_spec(spec: &str) -> Result<(Option<&str>, Option<&str>), String> {
+        let (owner, group) = match spec.split_once(':') {
+            Some((owner, "")) => (owner, owner),
+            Some((owner, group)) => (owner, group),
+            None => (spec, ""),
+        };
+        let owner = (!owner.is_empty()).then_some(owner);
+        let group = (!group.is_empty()).then_some(group);
+
+        if owner.is_some_and(|owner| !USERS.iter().any(|(name, _)| *name == owner)) {
+            return Err(format!("chown: invalid user: '{spec}'"));
+        }
+        let known_group = |group: &str| {
+            USERS
+                .iter()
+                .any(|(name, groups)| *name == group || groups.contains(&group))
+        };
+        if group.is_some_and(|group| !known_group(group)) {
+            return Err(format!("chown: invalid group: '{spec}'"));
+        }
+        Ok((owner, group))
+    }
+}
+
+impl VfsCommand for ChownCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (recursive, operands) = match split_recursive(Cmd::Chown, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let Some((spec, targets)) = operands.split_first() else {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("chown: missing operand");
+        };
+        if targets.is_empty() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr(format!("chown: missing operand after '{spec}'"));
+        }
+        let (owner, group) = match Self::parse_spec(spec) {
+            Ok(parsed) => parsed,
+            Err(e) => return CommandRes::new().with_error().with_stderr(e),
+        };
+
+        let mut errors = Vec::new();
+        for target in targets {
+            let Ok(node_id) = vfs.resolve_path(current_dir, target) else {
+                errors.push(format!(
+                    "chown: cannot access '{target}': No such file or directory"
+                ));
+                continue;
+            };
+            change_tree(
+                Cmd::Chown,
+                vfs,
+                node_id,
+                target,
+                recursive,
+                &mut errors,
+                &mut |vfs, node_id, path| {
+                    vfs.set_owner(node_id, owner, group).map_err(|_| {
+                        format!("chown: changing ownership of '{path}': Operation not permitted")
+                    })
+                },
+            );
+        }
+
+        errors_result(errors)
+    }
+}
+
+pub struct UmaskCommand;
+
+impl UmaskCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for UmaskCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        _current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, operands) = parse_multitarget(args);
+
+        // Validate options
+        let usage = Cmd::Umask.usage().expect("umask has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        let symbolic = options.contains(&'S');
+
+        match operands.as_slice() {
+            [] if symbolic => {
+                CommandRes::new().with_stdout_text(symbolic_mode(!vfs.umask() & 0o777))
+            }
+            [] => CommandRes::new().with_stdout_text(format!("{:04o}", vfs.umask())),
+            [mask] => {
+                // An octal mask gives the bits to leave out, but a symbolic one the bits to allow
+                let is_octal = mask.chars().all(|c| c.is_digit(8));
+                match apply_mode(mask, !vfs.umask() & 0o777, true, 0) {
+                    Some(bits) if is_octal => vfs.set_umask(bits),
+                    Some(allowed) => vfs.set_umask(!allowed & 0o777),
+                    None => {
+                        return CommandRes::new()
+                            .with_error()
+                            .with_stderr(format!("umask: {mask}: invalid mode"))
+                    }
+                }
+                CommandRes::new()
+            }
+            _ => CommandRes::new()
+                .with_error()
+                .with_stderr("umask: too many arguments"),
+        }
+    }
+}
+
+pub struct StatCommand;
+
+impl StatCommand {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The lines `stat` prints for one file
+    fn describe(node: &VfsNode, name: &str) -> String {
+        let (file, kind, type_char) = match &node.node_type {
+            VfsNodeType::Directory => (name.to_string(), "directory", 'd'),
+            VfsNodeType::Link { target } => (format!("{name} -> {target}"), "symbolic link", 'l'),
+            VfsNodeType::File { .. } if node.size() == 0 => {
+                (name.to_string(), "regular empty file", '-')
+            }
+            VfsNodeType::File { .. } => (name.to_string(), "regular file", '-'),
+        };
+        let time_format = "%Y-%m-%d %H:%M:%S%.9f %z";
+        [
+            format!("  File: {file}"),
+            format!("  Size: {:<15} {kind}", node.size()),
+            format!(
+                "Access: ({:04o}/{type_char}{})  Uid: {}  Gid: {}",
+                node.permissions.mode,
+                node.permissions.mode_string(),
+                node.metadata.owner,
+                node.metadata.group
+            ),
+            format!("Access: {}", node.metadata.accessed.format(time_format)),
+            format!("Modify: {}", node.metadata.modified.format(time_format)),
+            format!("Change: {}", node.metadata.changed.format(time_format)),
+            format!(" Birth: {}", node.metadata.created.format(time_format)),
+        ]
+        .join("\n")
+    }
+}
+
+impl VfsCommand for StatCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, targets) = parse_multitarget(args);
+
+        // Validate options
+        let usage = Cmd::Stat.usage().expect("stat has a usage spec");
+        if let Some(c) = options.iter().find(|c| !usage.supports(**c)) {
+            let error_msg = usage.invalid_option(*c);
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        let follow = options.contains(&'L');
+
+        if targets.is_empty() {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("stat: missing operand");
+        }
+
+        let mut stdout_parts = Vec::new();
+        let mut stderr_parts = Vec::new();
+        for target in targets {
+            // Links are described themselves unless -L asks for what they point at
+            let resolved = if follow {
+                vfs.resolve_path(current_dir, target)
+            } else {
+                vfs.resolve_path_nofollow(current_dir, target)
+            };
+            match resolved.ok().and_then(|node_id| vfs.get_node(node_id)) {
+                Some(node) => stdout_parts.push(Self::describe(node, target)),
+                None => stderr_parts.push(format!(
+                    "stat: cannot stat '{target}': No such file or directory"
+                )),
+            }
+        }
+
+        let mut result = CommandRes::new();
+        if !stdout_parts.is_empty() {
+            result = result.with_stdout_text(stdout_parts.join("\n"));
+        }
+        if !stderr_parts.is_empty() {
+            result = result.with_error().with_stderr(stderr_parts.join("\n"));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::terminal::command::test_helpers::{run, stdout, vfs_with_files};
+
+    const FILES: &[(&str, &str)] = &[("notes/todo.txt", "hi")];
+
+    fn mode(vfs: &VirtualFilesystem, root: NodeId, path: &str) -> u16 {
+        let node = vfs.resolve_path(root, path).unwrap();
+        vfs.get_node(node).unwrap().permissions.mode
+    }
+
+    #[test]
+    fn test_apply_mode() {
+        assert_eq!(apply_mode("755", 0, false, 0), Some(0o755));
+        assert_eq!(apply_mode("0640", 0o777, false, 0), Some(0o640));
+        assert_eq!(apply_mode("u+x", 0o644, false, 0o022), Some(0o744));
+        assert_eq!(apply_mode("go-r,u=rw", 0o755, false, 0), Some(0o611));
+        assert_eq!(apply_mode("a=r", 0o777, false, 0), Some(0o444));
+        assert_eq!(apply_mode("+w", 0o444, false, 0o022), Some(0o644));
+        assert_eq!(apply_mode("a+X", 0o644, true, 0), Some(0o755));
+        assert_eq!(apply_mode("a+X", 0o644, false, 0), Some(0o644));
+        assert_eq!(apply_mode("u+x-w", 0o644, false, 0), Some(0o544));
+        for bad in ["", "8", "77777", "u", "u+q", "z+x", "u+x,"] {
+            assert_eq!(apply_mode(bad, 0o644, false, 0), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_chmod() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+
+        assert!(!run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["600", "notes/todo.txt"],
+            None
+        )
+        .is_error());
+        assert_eq!(mode(&vfs, root, "notes/todo.txt"), 0o600);
+        assert!(!run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["-w", "notes/todo.txt"],
+            None
+        )
+        .is_error());
+        assert_eq!(mode(&vfs, root, "notes/todo.txt"), 0o400);
+        assert!(!run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["-R", "go=", "notes"],
+            None
+        )
+        .is_error());
+        assert_eq!(mode(&vfs, root, "notes"), 0o700);
+        assert_eq!(mode(&vfs, root, "notes/todo.txt"), 0o400);
+
+        let res = run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["u+q", "notes"],
+            None,
+        );
+        assert!(res.is_error());
+        let res = run(&ChmodCommand::new(), &mut vfs, root, &["755"], None);
+        assert!(res.is_error());
+        let res = run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["755", "missing"],
+            None,
+        );
+        assert!(res.is_error());
+        let res = run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["-v", "755", "notes"],
+            None,
+        );
+        assert!(res.is_error());
+
+        // The site's files, and other people's, can't be changed
+        let res = run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["777", "/blog"],
+            None,
+        );
+        assert!(res.is_error());
+        vfs.set_user("hans");
+        let res = run(
+            &ChmodCommand::new(),
+            &mut vfs,
+            root,
+            &["777", "notes"],
+            None,
+        );
+        assert!(res.is_error());
+        assert_eq!(mode(&vfs, root, "notes"), 0o700);
+    }
+
+    #[test]
+    fn test_chown() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        let owner = |vfs: &VirtualFilesystem, path: &str| {
+            let node = vfs.resolve_path(root, path).unwrap();
+            let metadata = &vfs.get_node(node).unwrap().metadata;
+            format!("{}:{}", metadata.owner, metadata.group)
+        };
+
+        // Only root can give files away
+        let res = run(
+            &ChownCommand::new(),
+            &mut vfs,
+            root,
+            &["hans", "notes"],
+            None,
+        );
+        assert!(res.is_error());
+        vfs.set_user("root");
+        let res = run(
+            &ChownCommand::new(),
+            &mut vfs,
+            root,
+            &["-R", "hans:", "notes"],
+            None,
+        );
+        assert!(!res.is_error());
+        assert_eq!(owner(&vfs, "notes"), "hans:hans");
+        assert_eq!(owner(&vfs, "notes/todo.txt"), "hans:hans");
+
+        // Owners can change the group to one they're in
+        vfs.set_user("hans");
+        let res = run(
+            &ChownCommand::new(),
+            &mut vfs,
+            root,
+            &[":staff", "notes"],
+            None,
+        );
+        assert!(!res.is_error());
+        assert_eq!(owner(&vfs, "notes"), "hans:staff");
+        let res = run(
+            &ChownCommand::new(),
+            &mut vfs,
+            root,
+            &[":user", "notes"],
+            None,
+        );
+        assert!(res.is_error());
+
+        let res = run(
+            &ChownCommand::new(),
+            &mut vfs,
+            root,
+            &["bob", "notes"],
+            None,
+        );
+        assert!(matches!(
+            res,
+            CommandRes::Output { stderr_text: Some(text), .. } if text == "chown: invalid user: 'bob'"
+        ));
+        let res = run(
+            &ChownCommand::new(),
+            &mut vfs,
+            root,
+            &["hans:nobody", "notes"],
+            None,
+        );
+        assert!(res.is_error());
+    }
+
+    #[test]
+    fn test_umask() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+
+        let res = run(&UmaskCommand::new(), &mut vfs, root, &[], None);
+        assert_eq!(stdout(&res), "0022");
+        let res = run(&UmaskCommand::new(), &mut vfs, root, &["-S"], None);
+        assert_eq!(stdout(&res), "u=rwx,g=rx,o=rx");
+
+        run(&UmaskCommand::new(), &mut vfs, root, &["077"], None);
+        assert_eq!(vfs.umask(), 0o077);
+        run(&UmaskCommand::new(), &mut vfs, root, &["g+rx"], None);
+        assert_eq!(vfs.umask(), 0o027);
+        let res = run(&UmaskCommand::new(), &mut vfs, root, &["-S"], None);
+        assert_eq!(stdout(&res), "u=rwx,g=rx,o=");
+
+        assert!(run(&UmaskCommand::new(), &mut vfs, root, &["999"], None).is_error());
+        assert!(run(&UmaskCommand::new(), &mut vfs, root, &["-x"], None).is_error());
+    }
+
+    #[test]
+    fn test_stat() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        vfs.create_symlink(root, "todo", "notes/todo.txt").unwrap();
+
+        let res = run(
+            &StatCommand::new(),
+            &mut vfs,
+            root,
+            &["notes/todo.txt"],
+            None,
+        );
+        let lines = stdout(&res).lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "  File: notes/todo.txt");
+        assert_eq!(lines[1], "  Size: 2               regular file");
+        assert_eq!(lines[2], "Access: (0644/-rw-r--r--)  Uid: user  Gid: user");
+        assert!(lines[3].starts_with("Access: "));
+        assert!(lines[4].starts_with("Modify: "));
+        assert!(lines[5].starts_with("Change: "));
+        assert!(lines[6].starts_with(" Birth: "));
+
+        let res = run(&StatCommand::new(), &mut vfs, root, &["todo"], None);
+        assert!(stdout(&res).starts_with("  File: todo -> notes/todo.txt\n"));
+        assert!(stdout(&res).contains("symbolic link"));
+        let res = run(&StatCommand::new(), &mut vfs, root, &["-L", "todo"], None);
+        assert!(stdout(&res).contains("regular file"));
+        let res = run(&StatCommand::new(), &mut vfs, root, &["/blog"], None);
+        assert!(stdout(&res).contains("Access: (0777/drwxrwxrwx)  Uid: hans  Gid: staff"));
+
+        assert!(run(&StatCommand::new(), &mut vfs, root, &["missing"], None).is_error());
+        assert!(run(&StatCommand::new(), &mut vfs, root, &[], None).is_error());
+    }
+}
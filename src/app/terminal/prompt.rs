@@ -0,0 +1,111 @@
+/// Expand the parts of a zsh `PROMPT` that make sense for this site.
+///
+/// Directory escapes (`%c`, `%1~`, `%~`, `%d`) all show `dir`, since the header only knows the
+/// current directory name. Colour and style escapes (`%{...%}`, `%F{...}`, `%B`, ...) are
+/// dropped, and `%(?:ok:err)` picks a branch based on the last exit status.
+pub fn render_prompt(template: &str, dir: &str, is_err: bool) -> String {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('c' | 'C' | '~' | 'd' | '/') => output.push_str(dir),
+            Some('1') => {
+                // %1~ and %1d are the trailing directory
+                chars.next_if(|c| *c == '~' || *c == 'd');
+                output.push_str(dir);
+            }
+            Some('n') => output.push_str("user"),
+            Some('m' | 'M') => output.push_str("hansbaker.com"),
+            Some('?') => output.push(if is_err { '1' } else { '0' }),
+            Some('#') | Some('%') => output.push('%'),
+            Some('{') => {
+                // Raw escape sequences up to the matching %}
+                let mut prev = None;
+                for c in chars.by_ref() {
+                    if prev == Some('%') && c == '}' {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+            }
+            Some('F' | 'K') => {
+                if chars.next_if_eq(&'{').is_some() {
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            Some('(') => {
+                // %(?:true-text:false-text)
+                let condition = chars.next();
+                let separator = chars.next().unwrap_or(':');
+                let body = take_until_close(&mut chars);
+                let (ok, err) = body.split_once(separator).unwrap_or((&body, ""));
+                let branch = match (condition, is_err) {
+                    (Some('?'), false) => ok,
+                    (Some('?'), true) => err,
+                    _ => "",
+                };
+                output.push_str(&render_prompt(branch, dir, is_err));
+            }
+            // Styles (%B, %b, %f, %k, %U, %u, %S, %s) and unknown escapes render as nothing
+            Some(_) | None => {}
+        }
+    }
+
+    output
+}
+
+// Read a conditional's body up to its closing parenthesis, allowing nested %(...)
+fn take_until_close(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut body = String::new();
+    let mut depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if chars.peek() == Some(&'(') => depth += 1,
+            ')' if depth == 0 => break,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        body.push(c);
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::vfs::DEFAULT_PROMPT;
+    use super::*;
+
+    #[test]
+    fn test_render_prompt() {
+        assert_eq!(
+            render_prompt("%n@%m %c %# ", "blog", false),
+            "user@hansbaker.com blog % "
+        );
+        assert_eq!(render_prompt("%F{green}%1~%f $ ", "cv", false), "cv $ ");
+        assert_eq!(render_prompt("[%?] %B>%b", "cv", true), "[1] >");
+        assert_eq!(render_prompt("100%% ", "cv", false), "100% ");
+    }
+
+    #[test]
+    fn test_render_default_prompt() {
+        assert_eq!(
+            render_prompt(DEFAULT_PROMPT, "blog", false),
+            "➜ blog $(git_prompt_info)"
+        );
+        assert_eq!(
+            render_prompt(DEFAULT_PROMPT, "blog", true),
+            "➜ blog $(git_prompt_info)"
+        );
+        assert_eq!(render_prompt("%(?:ok:%(?:a:b))", "", true), "b");
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::VecDeque;
+
+const KILL_RING_SIZE: usize = 16;
+
+/// A readline editing command bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditAction {
+    BeginningOfLine,  // Ctrl+A
+    EndOfLine,        // Ctrl+E
+    BackwardWord,     // Alt+B
+    ForwardWord,      // Alt+F
+    KillToStart,      // Ctrl+U
+    KillToEnd,        // Ctrl+K
+    UnixWordRubout,   // Ctrl+W: kill back to whitespace
+    BackwardKillWord, // Alt+Backspace: kill back to a word boundary
+    KillWord,         // Alt+D
+    Yank,             // Ctrl+Y
+    YankPop,          // Alt+Y: swap the text just yanked for an older kill
+}
+
+impl EditAction {
+    /// The action for a key press. `code` is the physical key, since Alt+letter produces
+    /// other characters on some layouts (e.g. Alt+B is `∫` on a Mac).
+    pub fn from_key(key: &str, code: &str, ctrl: bool, alt: bool) -> Option<Self> {
+        match (ctrl, alt) {
+            (true, false) => match key {
+                "a" => Some(Self::BeginningOfLine),
+                "e" => Some(Self::EndOfLine),
+                "u" => Some(Self::KillToStart),
+                "k" => Some(Self::KillToEnd),
+                "w" => Some(Self::UnixWordRubout),
+                "y" => Some(Self::Yank),
+                _ => None,
+            },
+            (false, true) => match code {
+                "KeyB" => Some(Self::BackwardWord),
+                "KeyF" => Some(Self::ForwardWord),
+                "KeyD" => Some(Self::KillWord),
+                "KeyY" => Some(Self::YankPop),
+                "Backspace" => Some(Self::BackwardKillWord),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn is_kill(self) -> bool {
+        matches!(
+            self,
+            Self::KillToStart
+                | Self::KillToEnd
+                | Self::UnixWordRubout
+                | Self::BackwardKillWord
+                | Self::KillWord
+        )
+    }
+}
+
+/// The input line and cursor (a byte offset) with a kill ring, independent of the DOM
+/// so both header inputs can share it.
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    line: String,
+    cursor: usize,
+    kill_ring: VecDeque<String>, // Most recent kill first
+    yank_index: usize,
+    last_yank: Option<(usize, usize)>, // Where the last yank was inserted, for yank-pop
+    last_action: Option<EditAction>,
+}
+
+impl LineEditor {
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Bring the editor up to date with the input. Typing or moving the cursor in between
+    /// breaks a run of kills (which otherwise build up one kill ring entry) or yanks.
+    pub fn sync(&mut self, line: &str, cursor: usize) {
+        if line != self.line || cursor != self.cursor {
+            self.line = line.to_string();
+            self.cursor = floor_char_boundary(line, cursor);
+            self.last_action = None;
+        }
+    }
+
+    pub fn apply(&mut self, action: EditAction) {
+        match action {
+            EditAction::BeginningOfLine => self.cursor = 0,
+            EditAction::EndOfLine => self.cursor = self.line.len(),
+            EditAction::BackwardWord => self.cursor = self.word_start(),
+            EditAction::ForwardWord => self.cursor = self.word_end(),
+            EditAction::KillToStart => self.kill(0, self.cursor, true),
+            EditAction::KillToEnd => self.kill(self.cursor, self.line.len(), false),
+            EditAction::UnixWordRubout => {
+                let before = &self.line[..self.cursor];
+                let start = before
+                    .trim_end()
+                    .rfind(char::is_whitespace)
+                    .map(|i| i + 1)
+                    .unwrap_or(0);
+                self.kill(start, self.cursor, true);
+            }
+            EditAction::BackwardKillWord => self.kill(self.word_start(), self.cursor, true),
+            EditAction::KillWord => self.kill(self.cursor, self.word_end(), false),
+            EditAction::Yank => {
+                self.yank_index = 0;
+                self.yank();
+            }
+            EditAction::YankPop => {
+                let Some((start, end)) = self.last_yank.filter(|_| {
+                    matches!(
+                        self.last_action,
+                        Some(EditAction::Yank | EditAction::YankPop)
+                    )
+                }) else {
+                    return;
+                };
+                self.line.replace_range(start..end, "");
+                self.cursor = start;
+                self.yank_index = (self.yank_index + 1) % self.kill_ring.len();
+                self.yank();
+            }
+        }
+        self.last_action = Some(action);
+    }
+
+    fn kill(&mut self, start: usize, end: usize, backward: bool) {
+        if start == end {
+            return;
+        }
+        let killed = self.line[start..end].to_string();
+        self.line.replace_range(start..end, "");
+        self.cursor = start;
+
+        // Consecutive kills build up a single entry, so Ctrl+W Ctrl+W yanks back both words
+        match self.kill_ring.front_mut() {
+            Some(last) if self.last_action.is_some_and(EditAction::is_kill) => {
+                if backward {
+                    last.insert_str(0, &killed);
+                } else {
+                    last.push_str(&killed);
+                }
+            }
+            _ => {
+                self.kill_ring.push_front(killed);
+                self.kill_ring.truncate(KILL_RING_SIZE);
+            }
+        }
+    }
+
+    fn yank(&mut self) {
+        let Some(text) = self.kill_ring.get(self.yank_index) else {
+            return;
+        };
+        let start = self.cursor;
+        self.line.insert_str(start, text);
+        self.cursor = start + text.len();
+        self.last_yank = Some((start, self.cursor));
+    }
+
+    /// The start of the word before the cursor, skipping any punctuation in between
+    fn word_start(&self) -> usize {
+        let before = &self.line[..self.cursor];
+        let word_end = before
+            .trim_end_matches(|c: char| !c.is_alphanumeric())
+            .len();
+        before[..word_end]
+            .rfind(|c: char| !c.is_alphanumeric())
+            .map(|i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0)
+    }
+
+    /// The end of the word after the cursor, skipping any punctuation in between
+    fn word_end(&self) -> usize {
+        let after = &self.line[self.cursor..];
+        let word_start = after.len()
+            - after
+                .trim_start_matches(|c: char| !c.is_alphanumeric())
+                .len();
+        let word_len = after[word_start..]
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(after.len() - word_start);
+        self.cursor + word_start + word_len
+    }
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(line: &str, cursor: usize) -> LineEditor {
+        let mut editor = LineEditor::default();
+        editor.sync(line, cursor);
+        editor
+    }
+
+    #[test]
+    fn test_key_bindings() {
+        assert_eq!(
+            EditAction::from_key("a", "KeyA", true, false),
+            Some(EditAction::BeginningOfLine)
+        );
+        assert_eq!(
+            EditAction::from_key("∫", "KeyB", false, true),
+            Some(EditAction::BackwardWord)
+        );
+        assert_eq!(
+            EditAction::from_key("Backspace", "Backspace", false, true),
+            Some(EditAction::BackwardKillWord)
+        );
+        assert_eq!(EditAction::from_key("c", "KeyC", true, false), None);
+        assert_eq!(EditAction::from_key("a", "KeyA", false, false), None);
+        assert_eq!(EditAction::from_key("a", "KeyA", true, true), None);
+    }
+
+    #[test]
+    fn test_movement() {
+        let mut ed = editor("cat blog/my-post.md", 10);
+        ed.apply(EditAction::BeginningOfLine);
+        assert_eq!(ed.cursor(), 0);
+        ed.apply(EditAction::EndOfLine);
+        assert_eq!(ed.cursor(), 19);
+
+        ed.apply(EditAction::BackwardWord);
+        assert_eq!(ed.cursor(), 17); // md
+        ed.apply(EditAction::BackwardWord);
+        assert_eq!(ed.cursor(), 12); // post
+        ed.apply(EditAction::BackwardWord);
+        assert_eq!(ed.cursor(), 9); // my
+        ed.apply(EditAction::ForwardWord);
+        assert_eq!(ed.cursor(), 11);
+        ed.apply(EditAction::ForwardWord);
+        assert_eq!(ed.cursor(), 16);
+
+        ed.apply(EditAction::BeginningOfLine);
+        ed.apply(EditAction::BackwardWord);
+        assert_eq!(ed.cursor(), 0);
+        ed.apply(EditAction::EndOfLine);
+        ed.apply(EditAction::ForwardWord);
+        assert_eq!(ed.cursor(), 19);
+    }
+
+    #[test]
+    fn test_kills() {
+        let mut ed = editor("echo hello world", 10);
+        ed.apply(EditAction::KillToEnd);
+        assert_eq!((ed.line(), ed.cursor()), ("echo hello", 10));
+        ed.apply(EditAction::KillToStart);
+        assert_eq!((ed.line(), ed.cursor()), ("", 0));
+
+        let mut ed = editor("ls -la  ~/blog/", 15);
+        ed.apply(EditAction::UnixWordRubout);
+        assert_eq!(ed.line(), "ls -la  ");
+        let mut ed = editor("ls -la  ~/blog/", 15);
+        ed.apply(EditAction::BackwardKillWord);
+        assert_eq!(ed.line(), "ls -la  ~/");
+
+        let mut ed = editor("cd /blog/post", 3);
+        ed.apply(EditAction::KillWord);
+        assert_eq!((ed.line(), ed.cursor()), ("cd /post", 3));
+    }
+
+    #[test]
+    fn test_yank_and_kill_ring() {
+        // Consecutive kills are yanked back together
+        let mut ed = editor("git commit -m msg", 17);
+        ed.apply(EditAction::UnixWordRubout);
+        ed.apply(EditAction::UnixWordRubout);
+        assert_eq!(ed.line(), "git commit ");
+        ed.apply(EditAction::Yank);
+        assert_eq!((ed.line(), ed.cursor()), ("git commit -m msg", 17));
+
+        // Moving in between starts a new entry, and Alt+Y cycles back to older ones
+        ed.sync("git commit -m msg", 0);
+        ed.apply(EditAction::KillWord);
+        assert_eq!(ed.line(), " commit -m msg");
+        ed.apply(EditAction::EndOfLine);
+        ed.apply(EditAction::Yank);
+        assert_eq!(ed.line(), " commit -m msggit");
+        ed.apply(EditAction::YankPop);
+        assert_eq!((ed.line(), ed.cursor()), (" commit -m msg-m msg", 20));
+        ed.apply(EditAction::YankPop);
+        assert_eq!(ed.line(), " commit -m msggit");
+
+        // Yank-pop does nothing unless it follows a yank
+        ed.apply(EditAction::BeginningOfLine);
+        ed.apply(EditAction::YankPop);
+        assert_eq!(ed.line(), " commit -m msggit");
+
+        // Yanking with an empty kill ring is harmless
+        let mut ed = editor("ls", 2);
+        ed.apply(EditAction::Yank);
+        ed.apply(EditAction::YankPop);
+        assert_eq!(ed.line(), "ls");
+    }
+
+    #[test]
+    fn test_multibyte() {
+        let mut ed = editor("echo ➜ héllo", 100);
+        assert_eq!(ed.cursor(), "echo ➜ héllo".len());
+        ed.apply(EditAction::BackwardWord);
+        assert_eq!(&ed.line()[ed.cursor()..], "héllo");
+        ed.apply(EditAction::BackwardKillWord);
+        assert_eq!(ed.line(), "héllo");
+    }
+}
@@ -0,0 +1,274 @@
+use indextree::NodeId;
+
+use super::command::CommandRes;
+use super::lexer::{Token, Word};
+use super::vfs::{FileContent, VfsError, VirtualFilesystem};
+
+/// A single output redirection parsed from a command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redirection {
+    /// `>`, `>>`, `2>` and `2>>`: send stdout (fd 1) or stderr (fd 2) to a file
+    File {
+        fd: u8,
+        target: String,
+        append: bool,
+    },
+    /// `2>&1`: send stderr wherever stdout currently goes
+    StderrToStdout,
+}
+
+/// Where a command's output stream ends up once redirections are applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sink {
+    Stdout, // The command's own stdout (next pipeline stage or the TTY)
+    Stderr, // The TTY's stderr
+    File(NodeId),
+}
+
+/// Split a command's tokens into its words and its redirections,
+/// expanding each redirection target with `expand`.
+pub fn parse_redirections(
+    tokens: Vec<Token>,
+    expand: impl Fn(&Word) -> String,
+) -> Result<(Vec<Word>, Vec<Redirection>), String> {
+    let mut words = Vec::new();
+    let mut redirections = Vec::new();
+    let mut tokens = tokens.into_iter();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => words.push(word),
+            Token::StderrToStdout => redirections.push(Redirection::StderrToStdout),
+            Token::Redirect { fd, append } => {
+                let target = match tokens.next() {
+                    Some(Token::Word(target)) => expand(&target),
+                    Some(_) => return Err("zsh: parse error near `>'".to_string()),
+                    None => return Err("zsh: parse error near `\\n'".to_string()),
+                };
+                redirections.push(Redirection::File { fd, target, append });
+            }
+            Token::Pipe | Token::Semi | Token::Newline | Token::And | Token::Or => {
+                unreachable!("Lists and pipelines are split before redirections are parsed")
+            }
+        }
+    }
+
+    Ok((words, redirections))
+}
+
+/// Open every redirection target left to right (creating or truncating files as the shell would)
+/// and work out where stdout and stderr should go.
+pub fn open_redirections(
+    vfs: &mut VirtualFilesystem,
+    current_dir: NodeId,
+    redirections: &[Redirection],
+) -> Result<(Sink, Sink), String> {
+    let mut stdout = Sink::Stdout;
+    let mut stderr = Sink::Stderr;
+
+    for redirection in redirections {
+        match redirection {
+            Redirection::File { fd, target, append } => {
+                let file = open_file(vfs, current_dir, target, *append)
+                    .map_err(|e| format!("zsh: {e}: {target}"))?;
+                if *fd == 1 {
+                    stdout = Sink::File(file);
+                } else {
+                    stderr = Sink::File(file);
+                }
+            }
+            Redirection::StderrToStdout => stderr = stdout,
+        }
+    }
+
+    Ok((stdout, stderr))
+}
+
+fn open_file(
+    vfs: &mut VirtualFilesystem,
+    current_dir: NodeId,
+    target: &str,
+    append: bool,
+) -> Result<NodeId, VfsError> {
+    match vfs.resolve_path(current_dir, target) {
+        Ok(node) => {
+            // Check writability up front, truncating unless appending
+            vfs.write_file(node, "", append)?;
+            Ok(node)
+        }
+        Err(VfsError::NotFound) => {
+            // A dangling link creates what it points at, relative to the link's directory
+            if let Ok(link) = vfs.resolve_path_nofollow(current_dir, target) {
+                let link_target = vfs
+                    .get_node(link)
+                    .and_then(|node| node.link_target())
+                    .map(str::to_string);
+                if let Some(link_target) = link_target {
+                    let link_dir = vfs.get_parent(link).unwrap_or(current_dir);
+                    return open_file(vfs, link_dir, &link_target, append);
+                }
+            }
+
+            let (parent_path, name) = match target.rsplit_once('/') {
+                Some(("", name)) => ("/", name),
+                Some((parent, name)) => (parent, name),
+                None => ("", target),
+            };
+            if name.is_empty() {
+                return Err(VfsError::NotAFile);
+            }
+            let parent = vfs.resolve_path(current_dir, parent_path)?;
+            vfs.create_file(parent, name, FileContent::Dynamic(String::new()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Route a command's output to the sinks chosen by `open_redirections`
+pub fn apply_redirections(
+    vfs: &mut VirtualFilesystem,
+    res: CommandRes,
+    stdout: Sink,
+    stderr: Sink,
+) -> CommandRes {
+    let CommandRes::Output {
+        is_err,
+        stdout_view,
+        stdout_text,
+        stderr_text,
+        paged,
+    } = res
+    else {
+        return res;
+    };
+
+    let mut stdout_parts = Vec::new();
+    let mut stderr_parts = Vec::new();
+    let mut is_err = is_err;
+
+    for (text, sink, is_stdout) in [(stdout_text, stdout, true), (stderr_text, stderr, false)] {
+        let Some(text) = text else {
+            continue;
+        };
+        match sink {
+            // A stdout view already shows stdout, so merged stderr stays on stderr
+            Sink::Stdout if is_stdout || stdout_view.is_none() => stdout_parts.push(text),
+            Sink::Stdout | Sink::Stderr => stderr_parts.push(text),
+            Sink::File(file) => {
+                let mut text = text;
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                if let Err(e) = vfs.write_file(file, &text, true) {
+                    is_err = true;
+                    stderr_parts.push(format!("zsh: {e}: {}", vfs.get_node_path(file)));
+                }
+            }
+        }
+    }
+
+    let (stdout_view, paged) = if stdout == Sink::Stdout {
+        (stdout_view, paged)
+    } else {
+        (None, false)
+    };
+    let join = |parts: Vec<String>| (!parts.is_empty()).then(|| parts.join("\n"));
+
+    CommandRes::Output {
+        is_err,
+        stdout_view,
+        stdout_text: join(stdout_parts),
+        stderr_text: join(stderr_parts),
+        paged,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::lexer::tokenize;
+    use super::*;
+
+    fn parse(input: &str) -> (Vec<String>, Vec<Redirection>) {
+        let tokens = tokenize(input).unwrap();
+        let (words, redirections) = parse_redirections(tokens, |w| w.expand(|_| None)).unwrap();
+        let words = words.iter().map(|w| w.expand(|_| None)).collect();
+        (words, redirections)
+    }
+
+    #[test]
+    fn test_parse_redirections() {
+        let (args, redirections) = parse("echo hi > out.txt");
+        assert_eq!(args, vec!["echo", "hi"]);
+        assert_eq!(
+            redirections,
+            vec![Redirection::File {
+                fd: 1,
+                target: "out.txt".to_string(),
+                append: false
+            }]
+        );
+
+        let (args, redirections) = parse("ls missing >>log 2>&1 2> 'my err'");
+        assert_eq!(args, vec!["ls", "missing"]);
+        assert_eq!(
+            redirections,
+            vec![
+                Redirection::File {
+                    fd: 1,
+                    target: "log".to_string(),
+                    append: true
+                },
+                Redirection::StderrToStdout,
+                Redirection::File {
+                    fd: 2,
+                    target: "my err".to_string(),
+                    append: false
+                },
+            ]
+        );
+
+        let expand = |w: &Word| w.expand(|_| None);
+        assert!(parse_redirections(tokenize("echo >").unwrap(), expand).is_err());
+        assert!(parse_redirections(tokenize("echo > >> x").unwrap(), expand).is_err());
+    }
+
+    #[test]
+    fn test_open_redirections_order() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+
+        // `> f 2>&1` sends both streams to the file
+        let redirections = parse("> f 2>&1").1;
+        let (stdout, stderr) = open_redirections(&mut vfs, root, &redirections).unwrap();
+        let file = vfs.resolve_path(root, "f").unwrap();
+        assert_eq!(stdout, Sink::File(file));
+        assert_eq!(stderr, Sink::File(file));
+
+        // `2>&1 > f` leaves stderr on the original stdout
+        let redirections = parse("2>&1 > f").1;
+        let (stdout, stderr) = open_redirections(&mut vfs, root, &redirections).unwrap();
+        assert_eq!(stdout, Sink::File(file));
+        assert_eq!(stderr, Sink::Stdout);
+
+        // Targets in missing directories fail to open
+        let redirections = parse("> nope/f").1;
+        let err = open_redirections(&mut vfs, root, &redirections).unwrap_err();
+        assert_eq!(err, "zsh: no such file or directory: nope/f");
+    }
+
+    #[test]
+    fn test_open_dangling_link() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let g = vfs.create_directory(root, "g").unwrap();
+        vfs.create_symlink(g, "dangle", "nonexist").unwrap();
+
+        // Writing through a dangling link creates its target next to the link
+        let redirections = parse("> /g/dangle").1;
+        let (stdout, _) = open_redirections(&mut vfs, root, &redirections).unwrap();
+        let file = vfs.resolve_path(root, "/g/nonexist").unwrap();
+        assert_eq!(stdout, Sink::File(file));
+        assert_eq!(vfs.resolve_path(root, "/g/dangle").unwrap(), file);
+        assert!(vfs.resolve_path(root, "/nonexist").is_err());
+    }
+}
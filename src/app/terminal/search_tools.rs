@@ -0,0 +1,801 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Local};
+use indextree::NodeId;
+use leptos::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+use super::command::{Cmd, CommandRes, VfsCommand};
+use super::glob;
+use super::vfs::{VfsError, VfsNode, VfsNodeType, VirtualFilesystem};
+
+fn grep_usage() -> String {
+    Cmd::Grep
+        .usage()
+        .expect("grep has a usage spec")
+        .usage_line()
+}
+
+/// How a piece of grep output is coloured on a TTY, following GNU grep's defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GrepStyle {
+    Plain,
+    FileName,
+    LineNumber,
+    Separator,
+    Match,
+}
+
+impl GrepStyle {
+    fn class(self) -> &'static str {
+        match self {
+            Self::Plain => "",
+            Self::FileName => "text-purple",
+            Self::LineNumber => "text-green",
+            Self::Separator => "text-cyan",
+            Self::Match => "text-red font-bold",
+        }
+    }
+}
+
+type GrepLine = Vec<(GrepStyle, String)>;
+
+#[derive(Debug, Default)]
+struct GrepOptions {
+    ignore_case: bool,
+    invert: bool,
+    line_numbers: bool,
+    count: bool,
+    files_with_matches: bool,
+    recursive: bool,
+    extended: bool,
+    only_matching: bool,
+}
+
+/// Translate a basic regular expression into Rust's (extended) syntax: in a BRE `+`, `?`, `|`,
+/// braces and parentheses are literal unless escaped, the reverse of an ERE.
+fn bre_to_ere(pattern: &str) -> String {
+    let mut ere = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if "+?|(){}".contains(c) => ere.push(c),
+                Some(c) => {
+                    ere.push('\\');
+                    ere.push(c);
+                }
+                None => ere.push_str(r"\\"),
+            },
+            c if "+?|(){}".contains(c) => {
+                ere.push('\\');
+                ere.push(c);
+            }
+            c => ere.push(c),
+        }
+    }
+    ere
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{dir}{name}")
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// Every file below `dir`, depth first and in name order, with paths starting from `display`
+fn collect_files(
+    vfs: &VirtualFilesystem,
+    dir: NodeId,
+    display: &str,
+    files: &mut Vec<(String, NodeId)>,
+) {
+    let Ok(entries) = vfs.list_directory(dir) else {
+        return;
+    };
+    for entry in entries {
+        let path = join_path(display, &entry.name);
+        if entry.is_directory {
+            collect_files(vfs, entry.node_id, &path, files);
+        } else {
+            files.push((path, entry.node_id));
+        }
+    }
+}
+
+pub struct GrepCommand;
+
+impl GrepCommand {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn search(
+        regex: &Regex,
+        options: &GrepOptions,
+        name: Option<&str>,
+        content: &str,
+        output: &mut Vec<GrepLine>,
+    ) -> bool {
+        let file_prefix = || {
+            name.map(|name| {
+                vec![
+                    (GrepStyle::FileName, name.to_string()),
+                    (GrepStyle::Separator, ":".to_string()),
+                ]
+            })
+            .unwrap_or_default()
+        };
+
+        let mut count = 0;
+        for (i, line) in content.lines().enumerate() {
+            if regex.is_match(line) == options.invert {
+                continue;
+            }
+            count += 1;
+            if options.count || options.files_with_matches {
+                continue;
+            }
+
+            let mut prefix = file_prefix();
+            if options.line_numbers {
+                prefix.push((GrepStyle::LineNumber, (i + 1).to_string()));
+                prefix.push((GrepStyle::Separator, ":".to_string()));
+            }
+
+            if options.only_matching {
+                // Inverted matches have no matching parts to print
+                if options.invert {
+                    continue;
+                }
+                for m in regex.find_iter(line).filter(|m| !m.is_empty()) {
+                    let mut out = prefix.clone();
+                    out.push((GrepStyle::Match, m.as_str().to_string()));
+                    output.push(out);
+                }
+                continue;
+            }
+
+            let mut out = prefix;
+            let mut last = 0;
+            if !options.invert {
+                for m in regex.find_iter(line).filter(|m| !m.is_empty()) {
+                    out.push((GrepStyle::Plain, line[last..m.start()].to_string()));
+                    out.push((GrepStyle::Match, m.as_str().to_string()));
+                    last = m.end();
+                }
+            }
+            out.push((GrepStyle::Plain, line[last..].to_string()));
+            output.push(out);
+        }
+
+        if options.files_with_matches && count > 0 {
+            let name = name.unwrap_or("(standard input)");
+            output.push(vec![(GrepStyle::FileName, name.to_string())]);
+        } else if options.count && !options.files_with_matches {
+            let mut out = file_prefix();
+            out.push((GrepStyle::Plain, count.to_string()));
+            output.push(out);
+        }
+
+        count > 0
+    }
+}
+
+impl VfsCommand for GrepCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        is_tty: bool,
+    ) -> CommandRes {
+        // Options can be grouped (`-Eil`) and come anywhere before a `--`
+        let mut options = GrepOptions::default();
+        let mut operands = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                operands.extend(args.by_ref());
+                break;
+            }
+            let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+                operands.push(arg);
+                continue;
+            };
+            for c in flags.chars() {
+                match c {
+                    'i' => options.ignore_case = true,
+                    'v' => options.invert = true,
+                    'n' => options.line_numbers = true,
+                    'c' => options.count = true,
+                    'l' => options.files_with_matches = true,
+                    'r' => options.recursive = true,
+                    'E' => options.extended = true,
+                    'o' => options.only_matching = true,
+                    _ => {
+                        let usage = Cmd::Grep.usage().expect("grep has a usage spec");
+                        let error_msg = format!("{}\n{}", usage.invalid_option(c), grep_usage());
+                        return CommandRes::new().with_error().with_stderr(error_msg);
+                    }
+                }
+            }
+        }
+
+        let Some((pattern, targets)) = operands.split_first() else {
+            return CommandRes::new().with_error().with_stderr(grep_usage());
+        };
+        let pattern = if options.extended {
+            pattern.to_string()
+        } else {
+            bre_to_ere(pattern)
+        };
+        let regex = match RegexBuilder::new(&pattern)
+            .case_insensitive(options.ignore_case)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(_) => {
+                let error_msg = format!("grep: invalid regular expression: {}", operands[0]);
+                return CommandRes::new().with_error().with_stderr(error_msg);
+            }
+        };
+
+        // `grep -r pattern` with no files searches the current directory
+        let targets = if targets.is_empty() && options.recursive {
+            vec!["."]
+        } else {
+            targets.to_vec()
+        };
+
+        let mut output = Vec::new();
+        let mut stderr_parts = Vec::new();
+        let mut has_error = false;
+        let mut any_match = false;
+
+        if targets.is_empty() {
+            let Some(input) = stdin else {
+                return CommandRes::new().with_error().with_stderr(grep_usage());
+            };
+            any_match = Self::search(&regex, &options, None, input, &mut output);
+        } else {
+            let mut files = Vec::new();
+            let mut searched_dir = false;
+            for target in &targets {
+                let node_id = match vfs.resolve_path(current_dir, target) {
+                    Ok(node_id) => node_id,
+                    Err(_) => {
+                        has_error = true;
+                        stderr_parts.push(format!("grep: {target}: No such file or directory"));
+                        continue;
+                    }
+                };
+                let is_dir = vfs
+                    .get_node(node_id)
+                    .is_some_and(|node| node.is_directory());
+                match (is_dir, options.recursive) {
+                    (false, _) => files.push((target.to_string(), node_id)),
+                    (true, true) => {
+                        searched_dir = true;
+                        collect_files(vfs, node_id, target, &mut files);
+                    }
+                    (true, false) => {
+                        has_error = true;
+                        stderr_parts.push(format!("grep: {target}: Is a directory"));
+                    }
+                }
+            }
+
+            let with_names = targets.len() > 1 || searched_dir;
+            for (name, node_id) in files {
+                let content = match vfs.read_file(node_id) {
+                    Ok(content) => content,
+                    Err(VfsError::PermissionDenied) => {
+                        has_error = true;
+                        stderr_parts.push(format!("grep: {name}: Permission denied"));
+                        continue;
+                    }
+                    Err(VfsError::SystemError(msg)) => {
+                        has_error = true;
+                        stderr_parts.push(format!("grep: {name}: {msg}"));
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                let name = with_names.then_some(name.as_str());
+                any_match |= Self::search(&regex, &options, name, &content, &mut output);
+            }
+        }
+
+        // Like grep, exit non-zero when nothing matched even if there's nothing to report
+        let mut result = CommandRes::new();
+        if has_error || !any_match {
+            result = result.with_error();
+        }
+        if !stderr_parts.is_empty() {
+            result = result.with_stderr(stderr_parts.join("\n"));
+        }
+        if output.is_empty() {
+            return result;
+        }
+
+        // Plain text goes along with the highlighted view, so long output can be paged
+        let text = output
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|(_, text)| text.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let result = result.with_stdout_text(text);
+        if is_tty {
+            result.with_stdout_view(Arc::new(move || {
+                output
+                    .iter()
+                    .map(|line| {
+                        let parts = line
+                            .iter()
+                            .map(|(style, text)| {
+                                view! { <span class=style.class()>{text.clone()}</span> }
+                            })
+                            .collect_view();
+                        view! { <div class="whitespace-pre-wrap">{parts}</div> }
+                    })
+                    .collect_view()
+                    .into_any()
+            }))
+        } else {
+            result
+        }
+    }
+}
+
+/// A test `find` applies to each node it visits
+enum FindPredicate {
+    Name(String),
+    Type(char),
+    Newer(DateTime<Local>),
+}
+
+/// The command given to `-exec`, run once per path (`;`) or once with every path (`+`)
+pub struct FindExec {
+    args: Vec<String>,
+    batch: bool,
+}
+
+impl FindExec {
+    /// The command lines to run, with `{}` replaced by the found paths
+    pub fn command_lines(&self, paths: &[String]) -> Vec<Vec<String>> {
+        if self.batch {
+            if paths.is_empty() {
+                return Vec::new();
+            }
+            let line = self
+                .args
+                .iter()
+                .flat_map(|arg| match arg.as_str() {
+                    "{}" => paths.to_vec(),
+                    _ => vec![arg.clone()],
+                })
+                .collect();
+            return vec![line];
+        }
+        paths
+            .iter()
+            .map(|path| {
+                self.args
+                    .iter()
+                    .map(|arg| arg.replace("{}", path))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// What a `find` search turned up, before any `-exec` command is run
+pub struct FindResults {
+    pub paths: Vec<String>,
+    pub errors: Vec<String>,
+    pub exec: Option<FindExec>,
+}
+
+impl FindResults {
+    /// The result of a search without `-exec`: the paths, one per line
+    pub fn into_command_res(self) -> CommandRes {
+        let mut result = CommandRes::new();
+        if !self.errors.is_empty() {
+            result = result.with_error().with_stderr(self.errors.join("\n"));
+        }
+        if !self.paths.is_empty() {
+            result = result.with_stdout_text(self.paths.join("\n"));
+        }
+        result
+    }
+}
+
+/// `find` walks the filesystem itself, but `-exec` needs the terminal to run commands,
+/// so the terminal runs the search and then each command line.
+pub struct FindCommand {
+    min_depth: usize,
+    max_depth: usize,
+    predicates: Vec<FindPredicate>,
+}
+
+impl FindCommand {
+    pub fn new() -> Self {
+        Self {
+            min_depth: 0,
+            max_depth: usize::MAX,
+            predicates: Vec::new(),
+        }
+    }
+
+    pub fn search(
+        mut self,
+        vfs: &VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+    ) -> Result<FindResults, CommandRes> {
+        let usage_error = |msg: String| CommandRes::new().with_error().with_stderr(msg);
+
+        // Starting points come before the first expression
+        let split = args
+            .iter()
+            .position(|arg| arg.starts_with('-') && *arg != "-")
+            .unwrap_or(args.len());
+        let (starts, expression) = args.split_at(split);
+        let starts = if starts.is_empty() {
+            &["."][..]
+        } else {
+            starts
+        };
+
+        let mut exec = None;
+        let mut expression = expression.iter();
+        while let Some(&arg) = expression.next() {
+            let mut value = || {
+                expression
+                    .next()
+                    .copied()
+                    .ok_or_else(|| usage_error(format!("find: missing argument to `{arg}'")))
+            };
+            match arg {
+                "-name" => self
+                    .predicates
+                    .push(FindPredicate::Name(value()?.to_string())),
+                "-type" => match value()? {
+                    kind @ ("f" | "d" | "l") => self
+                        .predicates
+                        .push(FindPredicate::Type(kind.chars().next().unwrap())),
+                    kind => {
+                        return Err(usage_error(format!(
+                            "find: Unknown argument to -type: {kind}"
+                        )))
+                    }
+                },
+                "-maxdepth" | "-mindepth" => {
+                    let depth = value()?;
+                    let Ok(depth) = depth.parse::<usize>() else {
+                        return Err(usage_error(format!(
+                            "find: Expected a positive decimal integer argument to {arg}, but got `{depth}'"
+                        )));
+                    };
+                    if arg == "-maxdepth" {
+                        self.max_depth = depth;
+                    } else {
+                        self.min_depth = depth;
+                    }
+                }
+                "-newer" => {
+                    let file = value()?;
+                    let modified = vfs
+                        .resolve_path(current_dir, file)
+                        .ok()
+                        .and_then(|node_id| vfs.get_node(node_id))
+                        .map(|node| node.metadata.modified)
+                        .ok_or_else(|| {
+                            usage_error(format!("find: '{file}': No such file or directory"))
+                        })?;
+                    self.predicates.push(FindPredicate::Newer(modified));
+                }
+                "-exec" => {
+                    let mut args = Vec::new();
+                    let batch = loop {
+                        match expression.next().copied() {
+                            Some(";") => break false,
+                            Some("+") if args.last().is_some_and(|arg| arg == "{}") => break true,
+                            Some(arg) => args.push(arg.to_string()),
+                            None => {
+                                return Err(usage_error(
+                                    "find: missing argument to `-exec'".to_string(),
+                                ))
+                            }
+                        }
+                    };
+                    if args.is_empty() {
+                        return Err(usage_error("find: missing argument to `-exec'".to_string()));
+                    }
+                    exec = Some(FindExec { args, batch });
+                }
+                _ => return Err(usage_error(format!("find: unknown predicate `{arg}'"))),
+            }
+        }
+
+        let mut results = FindResults {
+            paths: Vec::new(),
+            errors: Vec::new(),
+            exec,
+        };
+        for start in starts {
+            match vfs.resolve_path(current_dir, start) {
+                Ok(node_id) => self.walk(vfs, node_id, 0, &mut results),
+                Err(_) => results
+                    .errors
+                    .push(format!("find: '{start}': No such file or directory")),
+            }
+        }
+        Ok(results)
+    }
+
+    fn walk(
+        &self,
+        vfs: &VirtualFilesystem,
+        node_id: NodeId,
+        depth: usize,
+        results: &mut FindResults,
+    ) {
+        let Some(node) = vfs.get_node(node_id) else {
+            return;
+        };
+        let path = vfs.get_node_path(node_id);
+        if depth >= self.min_depth && self.matches(node) {
+            results.paths.push(path.clone());
+        }
+        if !node.is_directory() || depth >= self.max_depth {
+            return;
+        }
+
+        match vfs.list_directory(node_id) {
+            Ok(entries) => {
+                for entry in entries {
+                    self.walk(vfs, entry.node_id, depth + 1, results);
+                }
+            }
+            Err(_) => results
+                .errors
+                .push(format!("find: '{path}': Permission denied")),
+        }
+    }
+
+    fn matches(&self, node: &VfsNode) -> bool {
+        self.predicates.iter().all(|predicate| match predicate {
+            FindPredicate::Name(pattern) => glob::matches(pattern, &node.name),
+            FindPredicate::Type(kind) => match &node.node_type {
+                VfsNodeType::Directory => *kind == 'd',
+                VfsNodeType::File { .. } => *kind == 'f',
+                VfsNodeType::Link { .. } => *kind == 'l',
+            },
+            FindPredicate::Newer(time) => node.metadata.modified > *time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::command::test_helpers::{output, run, stdout, vfs_with_files};
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        (
+            "notes/langs.txt",
+            "Rust is fun\nrust is fast\nGo is simple\n",
+        ),
+        ("notes/nums.txt", "one (1)\ntwo (2)\n"),
+    ];
+
+    fn grep(vfs: &mut VirtualFilesystem, dir: NodeId, args: &str, stdin: Option<&str>) -> String {
+        let args = args.split_whitespace().collect::<Vec<_>>();
+        stdout(&run(&GrepCommand::new(), vfs, dir, &args, stdin)).to_string()
+    }
+
+    #[test]
+    fn test_grep_options() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        let vfs = &mut vfs;
+
+        assert_eq!(
+            grep(vfs, root, "rust notes/langs.txt", None),
+            "rust is fast"
+        );
+        assert_eq!(
+            grep(vfs, root, "-i rust notes/langs.txt", None),
+            "Rust is fun\nrust is fast"
+        );
+        assert_eq!(grep(vfs, root, "-v is notes/langs.txt", None), "");
+        assert_eq!(
+            grep(vfs, root, "-vi rust notes/langs.txt", None),
+            "Go is simple"
+        );
+        assert_eq!(
+            grep(vfs, root, "-n fast notes/langs.txt", None),
+            "2:rust is fast"
+        );
+        assert_eq!(grep(vfs, root, "-ci rust notes/langs.txt", None), "2");
+        assert_eq!(
+            grep(vfs, root, "-o [a-z]*st notes/langs.txt", None),
+            "ust\nrust\nfast"
+        );
+        assert_eq!(
+            grep(vfs, root, "-l is notes/langs.txt notes/nums.txt", None),
+            "notes/langs.txt"
+        );
+        assert_eq!(
+            grep(vfs, root, "-c o notes/langs.txt notes/nums.txt", None),
+            "notes/langs.txt:1\nnotes/nums.txt:2"
+        );
+    }
+
+    #[test]
+    fn test_grep_regex_flavours() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        let vfs = &mut vfs;
+
+        // Parentheses and `|` are literal in a basic regex and operators with -E
+        assert_eq!(grep(vfs, root, "(1) notes/nums.txt", None), "one (1)");
+        assert_eq!(
+            grep(vfs, root, "-E fun|fast notes/langs.txt", None),
+            "Rust is fun\nrust is fast"
+        );
+        assert_eq!(
+            grep(vfs, root, r"-o t\(wo\|hree\) notes/nums.txt", None),
+            "two"
+        );
+        assert_eq!(bre_to_ere(r"a+\+(b)\{2\}"), r"a\++\(b\){2}");
+    }
+
+    #[test]
+    fn test_grep_inputs() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        let vfs = &mut vfs;
+
+        assert_eq!(grep(vfs, root, "b", Some("abc\nxyz")), "abc");
+        assert_eq!(grep(vfs, root, "-l b", Some("abc")), "(standard input)");
+        assert_eq!(
+            grep(vfs, root, "-ri RUST notes", None),
+            "notes/langs.txt:Rust is fun\nnotes/langs.txt:rust is fast"
+        );
+        let notes = vfs.resolve_path(root, "notes").unwrap();
+        assert_eq!(grep(vfs, notes, "-rl two", None), "./nums.txt");
+
+        let res = run(
+            &GrepCommand::new(),
+            vfs,
+            root,
+            &["x", "notes", "missing"],
+            None,
+        );
+        assert!(res.is_error());
+        assert_eq!(
+            output(&res),
+            "grep: notes: Is a directory\ngrep: missing: No such file or directory"
+        );
+
+        // No match is an error without any output
+        let res = run(&GrepCommand::new(), vfs, root, &["zzz"], Some("abc"));
+        assert!(res.is_error());
+        let res = run(&GrepCommand::new(), vfs, root, &["-x", "a"], Some("abc"));
+        assert!(res.is_error());
+    }
+
+    fn find(vfs: &VirtualFilesystem, dir: NodeId, args: &str) -> Result<Vec<String>, String> {
+        let args = args.split_whitespace().collect();
+        match FindCommand::new().search(vfs, dir, args) {
+            Ok(found) => Ok(found.paths),
+            Err(res) => Err(output(&res).to_string()),
+        }
+    }
+
+    #[test]
+    fn test_find_predicates() {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        let notes = vfs.resolve_path(root, "notes").unwrap();
+
+        assert_eq!(
+            find(&vfs, root, "notes").unwrap(),
+            vec!["/notes", "/notes/langs.txt", "/notes/nums.txt"]
+        );
+        assert_eq!(
+            find(&vfs, notes, "-name *s.txt").unwrap(),
+            vec!["/notes/langs.txt", "/notes/nums.txt"]
+        );
+        assert_eq!(
+            find(&vfs, root, "/ -name nums*").unwrap(),
+            vec!["/notes/nums.txt"]
+        );
+        assert_eq!(find(&vfs, root, "/blog -type d").unwrap(), vec!["/blog"]);
+        assert!(find(&vfs, root, "-type f -name nav.rs")
+            .unwrap()
+            .contains(&"/blog/nav.rs".to_string()));
+
+        assert_eq!(find(&vfs, root, "-maxdepth 0").unwrap(), vec!["/"]);
+        assert_eq!(
+            find(&vfs, notes, "-mindepth 1 -maxdepth 1").unwrap(),
+            vec!["/notes/langs.txt", "/notes/nums.txt"]
+        );
+
+        let nums = vfs.resolve_path(notes, "nums.txt").unwrap();
+        vfs.write_file(nums, "three (3)\n", true).unwrap();
+        assert_eq!(
+            find(&vfs, notes, ". -newer langs.txt").unwrap(),
+            vec!["/notes", "/notes/nums.txt"]
+        );
+    }
+
+    #[test]
+    fn test_find_errors_and_exec() {
+        let (vfs, root) = vfs_with_files(FILES);
+
+        assert_eq!(
+            find(&vfs, root, "-size 1"),
+            Err("find: unknown predicate `-size'".to_string())
+        );
+        assert_eq!(
+            find(&vfs, root, "-name"),
+            Err("find: missing argument to `-name'".to_string())
+        );
+        assert_eq!(
+            find(&vfs, root, "-type x"),
+            Err("find: Unknown argument to -type: x".to_string())
+        );
+        assert_eq!(
+            find(&vfs, root, "-newer missing"),
+            Err("find: 'missing': No such file or directory".to_string())
+        );
+        assert!(find(&vfs, root, "-exec cat {}").is_err());
+
+        // Missing starting points are reported and the rest are still searched
+        let found = FindCommand::new()
+            .search(&vfs, root, vec!["missing", "notes", "-name", "nums.txt"])
+            .ok()
+            .expect("find should succeed");
+        assert_eq!(found.paths, vec!["/notes/nums.txt"]);
+        assert_eq!(
+            found.errors,
+            vec!["find: 'missing': No such file or directory"]
+        );
+
+        let found = FindCommand::new()
+            .search(
+                &vfs,
+                root,
+                vec!["notes", "-type", "f", "-exec", "wc", "-l", "{}", ";"],
+            )
+            .ok()
+            .expect("find should succeed");
+        let exec = found.exec.unwrap();
+        assert_eq!(
+            exec.command_lines(&found.paths),
+            vec![
+                vec!["wc", "-l", "/notes/langs.txt"],
+                vec!["wc", "-l", "/notes/nums.txt"]
+            ]
+        );
+        let found = FindCommand::new()
+            .search(
+                &vfs,
+                root,
+                vec!["notes", "-type", "f", "-exec", "cat", "{}", "+"],
+            )
+            .ok()
+            .expect("find should succeed");
+        assert_eq!(
+            found.exec.unwrap().command_lines(&found.paths),
+            vec![vec!["cat", "/notes/langs.txt", "/notes/nums.txt"]]
+        );
+    }
+}
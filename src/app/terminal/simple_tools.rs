@@ -1,17 +1,12 @@
-use super::command::{Command, CommandRes};
+use std::sync::Arc;
+
+use super::command::{Cmd, Command, CommandRes};
+use super::usage::help_list;
 use crate::app::ascii::{AVATAR_BLOCK, INFO_BLOCK};
 use chrono::prelude::*;
+use leptos::prelude::*;
 
 const MINES_URL: &str = "https://mines.hansbaker.com";
-const HELP_TEXT: &str = r#"This is Hans Baker's personal website.  Use this terminal to navigate the site.
-The commands should feel familiar:
-    cat     concatenate files and print to the standard output
-    cd      change directory (navigate site)
-    clear   clear the terminal screen
-    ls      list directory contents (sitemap)
-    mines   minesweeper app
-    pwd     print name of the current/working directory (current URL path)
-"#;
 
 pub struct HelpCommand;
 
@@ -23,11 +18,54 @@ impl Command for HelpCommand {
         _stdin: Option<&str>,
         _is_tty: bool,
     ) -> CommandRes {
-        CommandRes::new().with_stdout_text(HELP_TEXT)
+        CommandRes::new().with_stdout_text(help_list())
     }
 }
 
-pub struct PwdCommand;
+pub struct ManCommand;
+
+impl Command for ManCommand {
+    fn execute(
+        &self,
+        _path: &str,
+        args: Vec<&str>,
+        _stdin: Option<&str>,
+        is_tty: bool,
+    ) -> CommandRes {
+        let Some(name) = args.first() else {
+            let error_msg = "What manual page do you want?\nFor example, try 'man man'.";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        };
+        if args.len() > 1 {
+            return CommandRes::new()
+                .with_error()
+                .with_stderr("man: too many arguments");
+        }
+
+        match Cmd::from(*name).usage() {
+            // Pages open in the pager, like man(1) does on a terminal
+            Some(usage) if is_tty => CommandRes::new()
+                .with_stdout_text(usage.man_page())
+                .with_pager(),
+            Some(usage) => CommandRes::new().with_stdout_text(usage.man_page()),
+            None => {
+                let error_msg = format!("No manual entry for {name}");
+                CommandRes::new().with_error().with_stderr(error_msg)
+            }
+        }
+    }
+}
+
+/// `pwd` prints the logical path the terminal is at, and `pwd -P` the same path with links resolved
+pub struct PwdCommand {
+    physical: String,
+}
+
+impl PwdCommand {
+    pub fn new(physical: String) -> Self {
+        Self { physical }
+    }
+}
 
 impl Command for PwdCommand {
     fn execute(
@@ -37,12 +75,30 @@ impl Command for PwdCommand {
         _stdin: Option<&str>,
         _is_tty: bool,
     ) -> CommandRes {
-        if !args.is_empty() {
-            let error_msg = "pwd: too many arguments";
-            return CommandRes::new().with_error().with_stderr(error_msg);
+        let mut physical = false;
+        for arg in args {
+            let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+                let error_msg = "pwd: too many arguments";
+                return CommandRes::new().with_error().with_stderr(error_msg);
+            };
+            for c in flags.chars() {
+                match c {
+                    'P' => physical = true,
+                    'L' => physical = false,
+                    _ => {
+                        let usage = Cmd::Pwd.usage().expect("pwd has a usage spec");
+                        let error_msg = usage.invalid_option(c);
+                        return CommandRes::new().with_error().with_stderr(error_msg);
+                    }
+                }
+            }
         }
 
-        CommandRes::new().with_stdout_text(path)
+        if physical {
+            CommandRes::new().with_stdout_text(&self.physical)
+        } else {
+            CommandRes::new().with_stdout_text(path)
+        }
     }
 }
 
@@ -82,7 +138,7 @@ impl Command for ClearCommand {
 pub struct NeofetchCommand;
 
 impl NeofetchCommand {
-    fn as_text(&self) -> String {
+    fn as_html(&self) -> String {
         AVATAR_BLOCK
             .iter()
             .zip(INFO_BLOCK.iter())
@@ -97,16 +153,41 @@ impl NeofetchCommand {
     }
 }
 
+/// The text of the ascii art's markup, without its tags
+fn strip_markup(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
 impl Command for NeofetchCommand {
     fn execute(
         &self,
         _path: &str,
         _args: Vec<&str>,
         _stdin: Option<&str>,
-        _is_tty: bool,
+        is_tty: bool,
     ) -> CommandRes {
-        let text = self.as_text();
-        CommandRes::new().with_stdout_text(text)
+        let html = self.as_html();
+        let res = CommandRes::new().with_stdout_text(strip_markup(&html));
+        if !is_tty {
+            return res;
+        }
+        // Only the terminal gets the colours
+        res.with_stdout_view(Arc::new(move || {
+            view! { <div class="whitespace-pre-wrap" inner_html=html.clone()></div> }.into_any()
+        }))
     }
 }
 
@@ -149,11 +230,7 @@ impl Command for EchoCommand {
         _stdin: Option<&str>,
         _is_tty: bool,
     ) -> CommandRes {
-        let message = args
-            .iter()
-            .map(|s| s.replace("\"", ""))
-            .collect::<Vec<_>>()
-            .join(" ");
+        let message = args.join(" ");
 
         // Check for unsupported command substitution
         if message.contains("$(") {
@@ -227,6 +304,20 @@ impl Command for DateCommand {
     }
 }
 
+/// When the "system" came up, which is when the site was built
+pub fn boot_time() -> DateTime<Local> {
+    match DateTime::parse_from_rfc3339(env!("BUILD_TIME")) {
+        Ok(dt) => dt.with_timezone(&Local),
+        Err(_) => {
+            // Fallback to a reasonable default if parsing fails
+            Local::now()
+                - chrono::Duration::days(42)
+                - chrono::Duration::hours(13)
+                - chrono::Duration::minutes(37)
+        }
+    }
+}
+
 pub struct UptimeCommand;
 
 impl UptimeCommand {
@@ -235,18 +326,7 @@ impl UptimeCommand {
         let current_time = now.format("%H:%M:%S").to_string();
 
         // Use the actual build time as the start point for uptime
-        let build_time_str = env!("BUILD_TIME");
-        let build_time = match DateTime::parse_from_rfc3339(build_time_str) {
-            Ok(dt) => dt.with_timezone(&Local),
-            Err(_) => {
-                // Fallback to a reasonable default if parsing fails
-                now - chrono::Duration::days(42)
-                    - chrono::Duration::hours(13)
-                    - chrono::Duration::minutes(37)
-            }
-        };
-
-        let uptime_duration = now.signed_duration_since(build_time);
+        let uptime_duration = now.signed_duration_since(boot_time());
         let uptime_days = uptime_duration.num_days();
         let uptime_hours = uptime_duration.num_hours() % 24;
         let uptime_minutes = uptime_duration.num_minutes() % 60;
@@ -1,13 +1,16 @@
-use super::command::{Cmd, CmdAlias, Command, CommandRes, VfsCommand};
-use super::simple_tools::MinesCommand;
+use std::collections::HashMap;
+
+use super::command::{Cmd, CommandRes, VfsCommand};
 use super::vfs::{FileContent, VfsNodeType, VirtualFilesystem};
 use indextree::NodeId;
 
-pub struct WhichCommand;
+pub struct WhichCommand<'a> {
+    aliases: &'a HashMap<String, String>,
+}
 
-impl WhichCommand {
-    pub fn new() -> Self {
-        Self
+impl<'a> WhichCommand<'a> {
+    pub fn new(aliases: &'a HashMap<String, String>) -> Self {
+        Self { aliases }
     }
 
     fn get_which_result(
@@ -28,9 +31,8 @@ impl WhichCommand {
                 Some(node) if node.is_executable() => (command.to_string(), true),
                 _ => (format!("{command} not found"), false),
             }
-        } else if let Some(alias) = CmdAlias::from_str(command) {
+        } else if let Some(expansion) = self.aliases.get(command) {
             // Check if it's an alias first
-            let expansion = alias.expand("");
             (format!("{command}: aliased to {expansion}"), true)
         } else if let Some(cmd) = Cmd::from_str(command) {
             // Known command - get its simulated path or mark as builtin
@@ -48,7 +50,7 @@ impl WhichCommand {
     }
 }
 
-impl VfsCommand for WhichCommand {
+impl VfsCommand for WhichCommand<'_> {
     fn execute(
         &self,
         vfs: &mut VirtualFilesystem,
@@ -101,7 +103,7 @@ impl VfsCommand for UnknownCommand {
         current_dir: NodeId,
         args: Vec<&str>,
         _stdin: Option<&str>,
-        is_tty: bool,
+        _is_tty: bool,
     ) -> CommandRes {
         let target_string = &self.command_name;
 
@@ -130,11 +132,6 @@ impl VfsCommand for UnknownCommand {
             return CommandRes::new().with_error().with_stderr(error_msg);
         }
 
-        if node.name == "mines.sh" && is_executable {
-            let path = vfs.get_node_path(current_dir);
-            return MinesCommand.execute(&path, args, None, is_tty);
-        }
-
         match &node.node_type {
             VfsNodeType::Directory => {
                 let target_path = vfs.get_node_path(node_id);
@@ -149,7 +146,11 @@ impl VfsCommand for UnknownCommand {
 
                 match content {
                     FileContent::NavFile(s) => CommandRes::redirect(s.clone()),
-                    FileContent::Static(_) | FileContent::Dynamic(_) => {
+                    FileContent::Static(_)
+                    | FileContent::Dynamic(_)
+                    | FileContent::BlogPost(_)
+                    | FileContent::BlogMeta(_)
+                    | FileContent::Generated(_) => {
                         let error_msg = if target_string.contains("/") {
                             format!("permission denied: {target_string}")
                         } else {
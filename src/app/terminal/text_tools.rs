@@ -0,0 +1,822 @@
+use std::cmp::Ordering;
+
+use indextree::NodeId;
+
+use super::command::{Cmd, Command, CommandRes, VfsCommand};
+use super::vfs::{VfsError, VfsNodeType, VirtualFilesystem};
+
+/// Options parsed from a command line, e.g. `-rn` or `-n 5` or `-d,`
+pub type ParsedOptions<'a> = Vec<(char, Option<&'a str>)>;
+
+/// Split `args` into options and operands, as described by the command's usage spec.
+/// Options that take a value accept it attached (`-n5`) or as the next argument.
+pub fn parse_args<'a>(
+    cmd: Cmd,
+    args: Vec<&'a str>,
+) -> Result<(ParsedOptions<'a>, Vec<&'a str>), CommandRes> {
+    let usage = cmd.usage().expect("text tools have usage specs");
+    let mut options = Vec::new();
+    let mut operands = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            operands.extend(args.by_ref());
+            break;
+        }
+        let Some(chars) = arg.strip_prefix('-').filter(|chars| !chars.is_empty()) else {
+            operands.push(arg);
+            continue;
+        };
+        for (i, c) in chars.char_indices() {
+            if !usage.supports(c) {
+                return Err(CommandRes::new()
+                    .with_error()
+                    .with_stderr(usage.invalid_option(c)));
+            } else if !usage.takes_value(c) {
+                options.push((c, None));
+            } else {
+                let attached = &chars[i + c.len_utf8()..];
+                let value = if attached.is_empty() {
+                    args.next().ok_or_else(|| {
+                        let error_msg =
+                            format!("{}: option requires an argument -- '{c}'", usage.name);
+                        CommandRes::new().with_error().with_stderr(error_msg)
+                    })?
+                } else {
+                    attached
+                };
+                options.push((c, Some(value)));
+                break;
+            }
+        }
+    }
+
+    Ok((options, operands))
+}
+
+/// One input to a filter: a named file, or stdin when `name` is `None`
+struct Input {
+    name: Option<String>,
+    text: String,
+}
+
+/// Read each file operand (`-` is stdin), or stdin alone when there are none.
+/// Unreadable files are reported as `cmd: file: reason` and skipped.
+fn read_inputs(
+    cmd: &str,
+    vfs: &mut VirtualFilesystem,
+    current_dir: NodeId,
+    files: &[&str],
+    stdin: Option<&str>,
+) -> Result<(Vec<Input>, Vec<String>), CommandRes> {
+    if files.is_empty() {
+        let Some(text) = stdin else {
+            let error_msg = format!("{cmd}: missing operand");
+            return Err(CommandRes::new().with_error().with_stderr(error_msg));
+        };
+        let input = Input {
+            name: None,
+            text: text.to_string(),
+        };
+        return Ok((vec![input], Vec::new()));
+    }
+
+    let mut inputs = Vec::new();
+    let mut errors = Vec::new();
+    for file in files {
+        if *file == "-" {
+            inputs.push(Input {
+                name: None,
+                text: stdin.unwrap_or_default().to_string(),
+            });
+            continue;
+        }
+
+        let node_id = match vfs.resolve_path(current_dir, file) {
+            Ok(node_id) => node_id,
+            Err(_) => {
+                errors.push(format!("{cmd}: {file}: No such file or directory"));
+                continue;
+            }
+        };
+        if let Some(VfsNodeType::Directory) = vfs.get_node(node_id).map(|node| &node.node_type) {
+            errors.push(format!("{cmd}: {file}: Is a directory"));
+            continue;
+        }
+        match vfs.read_file(node_id) {
+            Ok(text) => inputs.push(Input {
+                name: Some(file.to_string()),
+                text,
+            }),
+            Err(VfsError::SystemError(msg)) => errors.push(format!("{cmd}: {file}: {msg}")),
+            Err(_) => errors.push(format!("{cmd}: {file}: Permission denied")),
+        }
+    }
+
+    Ok((inputs, errors))
+}
+
+/// The result of a filter: its output lines, plus any errors reading inputs
+fn filter_result(lines: Vec<String>, errors: Vec<String>) -> CommandRes {
+    let mut result = CommandRes::new();
+    if !errors.is_empty() {
+        result = result.with_error().with_stderr(errors.join("\n"));
+    }
+    if !lines.is_empty() {
+        result = result.with_stdout_text(lines.join("\n"));
+    }
+    result
+}
+
+fn parse_count(cmd: &str, value: &str) -> Result<usize, CommandRes> {
+    value.parse().map_err(|_| {
+        let error_msg = format!("{cmd}: invalid number of lines: '{value}'");
+        CommandRes::new().with_error().with_stderr(error_msg)
+    })
+}
+
+/// `head -5` and `tail -5` are shorthand for `-n 5`
+fn expand_count_shorthand(args: Vec<&str>) -> Vec<&str> {
+    args.into_iter()
+        .flat_map(|arg| match arg.strip_prefix('-') {
+            Some(count) if !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()) => {
+                vec!["-n", count]
+            }
+            _ => vec![arg],
+        })
+        .collect()
+}
+
+/// Parse `head`/`tail` arguments into the `-n` value, if any, and the files
+fn parse_line_count(cmd: Cmd, args: Vec<&str>) -> Result<(Option<&str>, Vec<&str>), CommandRes> {
+    let (options, files) = parse_args(cmd, expand_count_shorthand(args))?;
+    let count = options.last().and_then(|(_, value)| *value);
+    Ok((count, files))
+}
+
+/// Take some lines from each input, with `==> name <==` headers when there are several
+fn select_lines(
+    cmd: &str,
+    vfs: &mut VirtualFilesystem,
+    current_dir: NodeId,
+    files: &[&str],
+    stdin: Option<&str>,
+    select: impl Fn(Vec<&str>) -> Vec<&str>,
+) -> CommandRes {
+    let (inputs, errors) = match read_inputs(cmd, vfs, current_dir, files, stdin) {
+        Ok(read) => read,
+        Err(res) => return res,
+    };
+
+    let mut lines = Vec::new();
+    let with_headers = files.len() > 1;
+    for (i, input) in inputs.iter().enumerate() {
+        if with_headers {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            let name = input.name.as_deref().unwrap_or("standard input");
+            lines.push(format!("==> {name} <=="));
+        }
+        lines.extend(
+            select(input.text.lines().collect())
+                .into_iter()
+                .map(String::from),
+        );
+    }
+
+    filter_result(lines, errors)
+}
+
+pub struct HeadCommand;
+
+impl HeadCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for HeadCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (count, files) = match parse_line_count(Cmd::Head, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let count = match count.map(|count| parse_count("head", count)) {
+            Some(Ok(count)) => count,
+            Some(Err(res)) => return res,
+            None => 10,
+        };
+
+        select_lines("head", vfs, current_dir, &files, stdin, |lines| {
+            lines.into_iter().take(count).collect()
+        })
+    }
+}
+
+pub struct TailCommand;
+
+impl TailCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for TailCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (count, files) = match parse_line_count(Cmd::Tail, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        // `-n +N` starts from line N rather than taking the last N lines
+        let (count, from_start) = match count {
+            Some(count) => {
+                let from_start = count.starts_with('+');
+                match parse_count("tail", count.trim_start_matches('+')) {
+                    Ok(count) => (count, from_start),
+                    Err(res) => return res,
+                }
+            }
+            None => (10, false),
+        };
+
+        select_lines("tail", vfs, current_dir, &files, stdin, |lines| {
+            let skip = if from_start {
+                count.saturating_sub(1)
+            } else {
+                lines.len().saturating_sub(count)
+            };
+            lines.into_iter().skip(skip).collect()
+        })
+    }
+}
+
+pub struct WcCommand;
+
+impl WcCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for WcCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, files) = match parse_args(Cmd::Wc, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let (inputs, errors) = match read_inputs("wc", vfs, current_dir, &files, stdin) {
+            Ok(read) => read,
+            Err(res) => return res,
+        };
+
+        // With no options wc shows lines, words and bytes, always in that order
+        let columns = ['l', 'w', 'c']
+            .into_iter()
+            .filter(|c| options.is_empty() || options.iter().any(|(o, _)| o == c))
+            .collect::<Vec<_>>();
+        let count = |text: &str, column: char| match column {
+            'l' => text.lines().count(),
+            'w' => text.split_whitespace().count(),
+            _ => text.len(),
+        };
+
+        let mut rows = inputs
+            .iter()
+            .map(|input| {
+                // Output here doesn't end in a newline as it would in a shell, so count one
+                let mut text = input.text.clone();
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                let counts = columns.iter().map(|c| count(&text, *c)).collect::<Vec<_>>();
+                (counts, input.name.clone())
+            })
+            .collect::<Vec<_>>();
+        if rows.len() > 1 {
+            let totals = (0..columns.len())
+                .map(|i| rows.iter().map(|(counts, _)| counts[i]).sum())
+                .collect();
+            rows.push((totals, Some("total".to_string())));
+        }
+
+        let width = rows
+            .iter()
+            .flat_map(|(counts, _)| counts.iter().map(|n| n.to_string().len()))
+            .max()
+            .unwrap_or_default();
+        let lines = rows
+            .into_iter()
+            .map(|(counts, name)| {
+                let mut line = counts
+                    .iter()
+                    .map(|n| format!("{n:>width$}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if let Some(name) = name {
+                    line.push(' ');
+                    line.push_str(&name);
+                }
+                line
+            })
+            .collect();
+
+        filter_result(lines, errors)
+    }
+}
+
+/// The number at the start of a line, as `sort -n` compares it. Lines without one count as 0.
+fn numeric_key(line: &str) -> f64 {
+    let line = line.trim_start();
+    let end = line
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-')))
+        .map(|(i, _)| i)
+        .unwrap_or(line.len());
+    line[..end].parse().unwrap_or_default()
+}
+
+pub struct SortCommand;
+
+impl SortCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for SortCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, files) = match parse_args(Cmd::Sort, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let (inputs, errors) = match read_inputs("sort", vfs, current_dir, &files, stdin) {
+            Ok(read) => read,
+            Err(res) => return res,
+        };
+        let has = |flag: char| options.iter().any(|(c, _)| *c == flag);
+        let (reverse, numeric, unique) = (has('r'), has('n'), has('u'));
+
+        let compare = |a: &&str, b: &&str| {
+            if numeric {
+                numeric_key(a)
+                    .partial_cmp(&numeric_key(b))
+                    .unwrap_or(Ordering::Equal)
+            } else {
+                a.cmp(b)
+            }
+        };
+
+        let mut lines = inputs
+            .iter()
+            .flat_map(|input| input.text.lines())
+            .collect::<Vec<_>>();
+        // Equal numbers fall back to comparing the whole line
+        lines.sort_by(|a, b| compare(a, b).then_with(|| a.cmp(b)));
+        if unique {
+            lines.dedup_by(|a, b| compare(&&**a, &&**b) == Ordering::Equal);
+        }
+        if reverse {
+            lines.reverse();
+        }
+
+        filter_result(lines.into_iter().map(String::from).collect(), errors)
+    }
+}
+
+pub struct UniqCommand;
+
+impl UniqCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for UniqCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, files) = match parse_args(Cmd::Uniq, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        if files.len() > 1 {
+            let error_msg = "uniq: extra operand\nThis version of uniq can't write to a file";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+        let (inputs, errors) = match read_inputs("uniq", vfs, current_dir, &files, stdin) {
+            Ok(read) => read,
+            Err(res) => return res,
+        };
+        let with_counts = !options.is_empty();
+
+        // Only adjacent duplicates are merged, which is why uniq usually follows sort
+        let mut runs: Vec<(usize, &str)> = Vec::new();
+        for line in inputs.iter().flat_map(|input| input.text.lines()) {
+            match runs.last_mut() {
+                Some((count, last)) if *last == line => *count += 1,
+                _ => runs.push((1, line)),
+            }
+        }
+        let lines = runs
+            .into_iter()
+            .map(|(count, line)| {
+                if with_counts {
+                    format!("{count:>7} {line}")
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+
+        filter_result(lines, errors)
+    }
+}
+
+/// Parse a `cut -f` list such as `1,3` or `2-4,6-` into inclusive, 1-based ranges
+fn parse_field_list(list: &str) -> Result<Vec<(usize, usize)>, String> {
+    list.split(',')
+        .map(|item| {
+            let invalid = || format!("cut: invalid field value '{item}'");
+            let parse = |n: &str| n.parse::<usize>().map_err(|_| invalid());
+            let (start, end) = match item.split_once('-') {
+                Some(("", "")) => return Err(invalid()),
+                Some(("", end)) => (1, parse(end)?),
+                Some((start, "")) => (parse(start)?, usize::MAX),
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                None => (parse(item)?, parse(item)?),
+            };
+            if start == 0 || end == 0 {
+                return Err("cut: fields are numbered from 1".to_string());
+            }
+            if start > end {
+                return Err("cut: invalid decreasing range".to_string());
+            }
+            Ok((start, end))
+        })
+        .collect()
+}
+
+pub struct CutCommand;
+
+impl CutCommand {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VfsCommand for CutCommand {
+    fn execute(
+        &self,
+        vfs: &mut VirtualFilesystem,
+        current_dir: NodeId,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, files) = match parse_args(Cmd::Cut, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let value = |flag: char| {
+            options
+                .iter()
+                .rev()
+                .find(|(c, _)| *c == flag)
+                .and_then(|(_, value)| *value)
+        };
+
+        let Some(list) = value('f') else {
+            let error_msg =
+                "cut: you must specify a list of fields\nusage: cut -f list [-d delim] [file ...]";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        };
+        let ranges = match parse_field_list(list) {
+            Ok(ranges) => ranges,
+            Err(error_msg) => return CommandRes::new().with_error().with_stderr(error_msg),
+        };
+        let delimiter = value('d').unwrap_or("\t");
+        if delimiter.chars().count() != 1 {
+            let error_msg = "cut: the delimiter must be a single character";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        }
+
+        let (inputs, errors) = match read_inputs("cut", vfs, current_dir, &files, stdin) {
+            Ok(read) => read,
+            Err(res) => return res,
+        };
+        let lines = inputs
+            .iter()
+            .flat_map(|input| input.text.lines())
+            .map(|line| {
+                // Lines without the delimiter are printed whole
+                if !line.contains(delimiter) {
+                    return line.to_string();
+                }
+                line.split(delimiter)
+                    .enumerate()
+                    .filter(|(i, _)| {
+                        ranges
+                            .iter()
+                            .any(|(start, end)| (*start..=*end).contains(&(i + 1)))
+                    })
+                    .map(|(_, field)| field)
+                    .collect::<Vec<_>>()
+                    .join(delimiter)
+            })
+            .collect();
+
+        filter_result(lines, errors)
+    }
+}
+
+/// The characters in a `tr` class such as `[:upper:]`
+fn class_members(name: &str) -> Option<Vec<char>> {
+    let members = match name {
+        "[:lower:]" => ('a'..='z').collect(),
+        "[:upper:]" => ('A'..='Z').collect(),
+        "[:digit:]" => ('0'..='9').collect(),
+        "[:alpha:]" => ('A'..='Z').chain('a'..='z').collect(),
+        "[:alnum:]" => ('0'..='9').chain('A'..='Z').chain('a'..='z').collect(),
+        "[:space:]" => vec![' ', '\t', '\n', '\r'],
+        _ => return None,
+    };
+    Some(members)
+}
+
+/// Expand a `tr` set such as `a-z`, `[:upper:]` or `\n` into its characters
+fn expand_set(set: &str) -> Vec<char> {
+    let chars = set.chars().collect::<Vec<_>>();
+    let mut expanded = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '[' {
+            let class = chars[i..].iter().position(|c| *c == ']').and_then(|end| {
+                let name = chars[i..=i + end].iter().collect::<String>();
+                class_members(&name).map(|members| (members, end + 1))
+            });
+            if let Some((members, len)) = class {
+                expanded.extend(members);
+                i += len;
+                continue;
+            }
+        }
+
+        let (c, len) = match (chars[i], chars.get(i + 1)) {
+            ('\\', Some('n')) => ('\n', 2),
+            ('\\', Some('t')) => ('\t', 2),
+            ('\\', Some(c)) => (*c, 2),
+            (c, _) => (c, 1),
+        };
+        i += len;
+        match (chars.get(i), chars.get(i + 1)) {
+            (Some('-'), Some(end)) if *end >= c => {
+                expanded.extend(c..=*end);
+                i += 2;
+            }
+            _ => expanded.push(c),
+        }
+    }
+
+    expanded
+}
+
+pub struct TrCommand;
+
+impl Command for TrCommand {
+    fn execute(
+        &self,
+        _path: &str,
+        args: Vec<&str>,
+        stdin: Option<&str>,
+        _is_tty: bool,
+    ) -> CommandRes {
+        let (options, sets) = match parse_args(Cmd::Tr, args) {
+            Ok(parsed) => parsed,
+            Err(res) => return res,
+        };
+        let has = |flag: char| options.iter().any(|(c, _)| *c == flag);
+        let (delete, squeeze) = (has('d'), has('s'));
+
+        let (set1, set2) = match (sets.as_slice(), delete) {
+            ([set1], true) => (expand_set(set1), Vec::new()),
+            ([set1], false) if squeeze => (expand_set(set1), Vec::new()),
+            ([set1, set2], false) => (expand_set(set1), expand_set(set2)),
+            ([set1, set2], true) if squeeze => (expand_set(set1), expand_set(set2)),
+            ([], _) => {
+                let error_msg = "tr: missing operand\nusage: tr [-ds] set1 [set2]";
+                return CommandRes::new().with_error().with_stderr(error_msg);
+            }
+            _ => {
+                let error_msg = "tr: wrong number of operands\nusage: tr [-ds] set1 [set2]";
+                return CommandRes::new().with_error().with_stderr(error_msg);
+            }
+        };
+        let Some(input) = stdin else {
+            let error_msg = "tr: missing input\nThis version of tr only reads from a pipe";
+            return CommandRes::new().with_error().with_stderr(error_msg);
+        };
+
+        // Translate (or delete), then squeeze runs of the characters in the last set given
+        let mut output = String::with_capacity(input.len());
+        for c in input.chars() {
+            let position = set1.iter().position(|s| *s == c);
+            let c = match position {
+                Some(_) if delete => continue,
+                // A shorter second set is padded with its last character
+                Some(i) if !set2.is_empty() => set2[i.min(set2.len() - 1)],
+                _ => c,
+            };
+            output.push(c);
+        }
+        if squeeze {
+            let squeeze_set = if set2.is_empty() || (delete && sets.len() == 1) {
+                &set1
+            } else {
+                &set2
+            };
+            let mut squeezed = String::with_capacity(output.len());
+            for c in output.chars() {
+                if squeezed.ends_with(c) && squeeze_set.contains(&c) {
+                    continue;
+                }
+                squeezed.push(c);
+            }
+            output = squeezed;
+        }
+
+        CommandRes::new().with_stdout_text(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::command::test_helpers::{output, run, vfs_with_files};
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        ("fruit.txt", "banana\napple\ncherry\napple\napple\n"),
+        (
+            "libs.csv",
+            "name,lang,year\nleptos,rust,2022\nreact,js,2013\n",
+        ),
+    ];
+
+    /// What `command` prints for whitespace-separated `args`, run next to the fixture files
+    fn filter(command: &dyn VfsCommand, args: &str, stdin: Option<&str>) -> String {
+        let (mut vfs, root) = vfs_with_files(FILES);
+        let args = args.split_whitespace().collect::<Vec<_>>();
+        output(&run(command, &mut vfs, root, &args, stdin)).to_string()
+    }
+
+    #[test]
+    fn test_head_and_tail() {
+        let head = HeadCommand::new();
+        let tail = TailCommand::new();
+        assert_eq!(filter(&head, "-n 2 fruit.txt", None), "banana\napple");
+        assert_eq!(filter(&head, "-1", Some("a\nb")), "a");
+        assert_eq!(filter(&tail, "-n2 fruit.txt", None), "apple\napple");
+        assert_eq!(filter(&tail, "-n +4 fruit.txt", None), "apple\napple");
+        assert_eq!(
+            filter(&head, "-n 1 fruit.txt libs.csv", None),
+            "==> fruit.txt <==\nbanana\n\n==> libs.csv <==\nname,lang,year"
+        );
+        assert_eq!(
+            filter(&head, "-n x fruit.txt", None),
+            "head: invalid number of lines: 'x'"
+        );
+        assert_eq!(
+            filter(&head, "-n", None),
+            "head: option requires an argument -- 'n'"
+        );
+        assert_eq!(
+            filter(&tail, "-f", None),
+            "tail: invalid option -- 'f'\nThis version of tail only supports options 'n count'"
+        );
+        assert_eq!(
+            filter(&tail, "missing", None),
+            "tail: missing: No such file or directory"
+        );
+    }
+
+    #[test]
+    fn test_wc() {
+        let wc = WcCommand::new();
+        assert_eq!(filter(&wc, "-l fruit.txt", None), "5 fruit.txt");
+        assert_eq!(filter(&wc, "", Some("hello world")), " 1  2 12");
+        assert_eq!(filter(&wc, "-w", Some("one two three")), "3");
+        assert_eq!(
+            filter(&wc, "-lc fruit.txt libs.csv", None),
+            " 5 32 fruit.txt\n 3 46 libs.csv\n 8 78 total"
+        );
+    }
+
+    #[test]
+    fn test_sort_and_uniq() {
+        let sort = SortCommand::new();
+        let uniq = UniqCommand::new();
+        assert_eq!(
+            filter(&sort, "fruit.txt", None),
+            "apple\napple\napple\nbanana\ncherry"
+        );
+        assert_eq!(
+            filter(&sort, "-ru fruit.txt", None),
+            "cherry\nbanana\napple"
+        );
+        assert_eq!(
+            filter(&sort, "-n", Some("10\n9\n-1\nx\n2.5")),
+            "-1\nx\n2.5\n9\n10"
+        );
+        assert_eq!(filter(&sort, "", Some("10\n9")), "10\n9");
+        assert_eq!(
+            filter(&uniq, "fruit.txt", None),
+            "banana\napple\ncherry\napple"
+        );
+        assert_eq!(filter(&uniq, "-c", Some("a\na\nb")), "      2 a\n      1 b");
+    }
+
+    #[test]
+    fn test_cut() {
+        let cut = CutCommand::new();
+        assert_eq!(
+            filter(&cut, "-d , -f 1,3 libs.csv", None),
+            "name,year\nleptos,2022\nreact,2013"
+        );
+        assert_eq!(filter(&cut, "-d, -f2-", Some("a,b,c")), "b,c");
+        assert_eq!(filter(&cut, "-f -2", Some("a\tb\tc")), "a\tb");
+        assert_eq!(filter(&cut, "-f 1", Some("no tabs here")), "no tabs here");
+        assert_eq!(
+            filter(&cut, "-f 0", Some("a")),
+            "cut: fields are numbered from 1"
+        );
+        assert_eq!(
+            filter(&cut, "-f x", Some("a")),
+            "cut: invalid field value 'x'"
+        );
+        assert_eq!(
+            filter(&cut, "-d ab -f 1", Some("a")),
+            "cut: the delimiter must be a single character"
+        );
+    }
+
+    #[test]
+    fn test_tr() {
+        let tr = |args: &str, stdin: Option<&str>| {
+            let args = args.split_whitespace().collect();
+            output(&TrCommand.execute("/", args, stdin, false)).to_string()
+        };
+        assert_eq!(tr("a-z A-Z", Some("hello, world")), "HELLO, WORLD");
+        assert_eq!(tr("[:upper:] [:lower:]", Some("Hi There")), "hi there");
+        assert_eq!(tr("abc x", Some("aabbcc")), "xxxxxx");
+        assert_eq!(tr("-d aeiou", Some("leptos")), "lpts");
+        assert_eq!(tr("-s l", Some("hello")), "helo");
+        assert_eq!(tr(r"\n ,", Some("a\nb")), "a,b");
+        assert_eq!(
+            tr("a b", None),
+            "tr: missing input\nThis version of tr only reads from a pipe"
+        );
+        assert_eq!(
+            tr("a", Some("a")),
+            "tr: wrong number of operands\nusage: tr [-ds] set1 [set2]"
+        );
+        assert_eq!(
+            expand_set("a-c[:digit:]x"),
+            "abc0123456789x".chars().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,888 @@
+use super::command::Cmd;
+
+/// Width of a rendered man page, used to lay out its header line
+const MAN_WIDTH: usize = 80;
+const MAN_INDENT: &str = "       ";
+const MAN_OPTION_INDENT: &str = "              ";
+
+/// One documented option, e.g. `-L level`
+pub struct OptionSpec {
+    pub flag: &'static str,
+    pub value: Option<&'static str>,
+    pub description: &'static str,
+}
+
+const fn flag(flag: &'static str, description: &'static str) -> OptionSpec {
+    OptionSpec {
+        flag,
+        value: None,
+        description,
+    }
+}
+
+const fn valued(flag: &'static str, value: &'static str, description: &'static str) -> OptionSpec {
+    OptionSpec {
+        flag,
+        value: Some(value),
+        description,
+    }
+}
+
+impl OptionSpec {
+    /// The option as written on a command line, e.g. `-n count`
+    fn label(&self) -> String {
+        match self.value {
+            Some(value) => format!("{} {value}", self.flag),
+            None => self.flag.to_string(),
+        }
+    }
+
+    /// The single-character flag for short options like `-a`
+    fn short(&self) -> Option<char> {
+        let mut chars = self.flag.strip_prefix('-')?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c != '-' => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// Structured usage of a command. `help`, `man`, `--help` and the
+/// invalid-option errors are all rendered from it.
+pub struct Usage {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub synopsis: &'static str,
+    pub description: &'static str,
+    pub options: &'static [OptionSpec],
+}
+
+impl Usage {
+    /// Whether `c` is one of this command's short options
+    pub fn supports(&self, c: char) -> bool {
+        self.options.iter().any(|option| option.short() == Some(c))
+    }
+
+    /// Whether the short option `c` takes a value, like `-n 5`
+    pub fn takes_value(&self, c: char) -> bool {
+        self.options
+            .iter()
+            .any(|option| option.short() == Some(c) && option.value.is_some())
+    }
+
+    /// `This version of ls only supports options 'a' and 'l'`
+    pub fn supported_options(&self) -> String {
+        let name = self.name;
+        let options = self
+            .options
+            .iter()
+            .map(|option| {
+                let label = option.label();
+                match option.short() {
+                    Some(_) => format!("'{}'", &label[1..]),
+                    None => format!("'{label}'"),
+                }
+            })
+            .collect::<Vec<_>>();
+        match options.as_slice() {
+            [] => format!("This version of {name} doesn't support any options"),
+            [only] => format!("This version of {name} only supports options {only}"),
+            [rest @ .., last] => format!(
+                "This version of {name} only supports options {} and {last}",
+                rest.join(", ")
+            ),
+        }
+    }
+
+    /// The error for an unsupported short option `c`
+    pub fn invalid_option(&self, c: char) -> String {
+        format!(
+            "{}: invalid option -- '{c}'\n{}",
+            self.name,
+            self.supported_options()
+        )
+    }
+
+    /// `usage: grep [-cilnorvE] pattern [file ...]`
+    pub fn usage_line(&self) -> String {
+        format!("usage: {}", self.synopsis)
+    }
+
+    /// The short help printed by `<cmd> --help`
+    pub fn help_text(&self) -> String {
+        let mut lines = vec![self.usage_line(), String::new(), self.summary.to_string()];
+        let width = self
+            .options
+            .iter()
+            .map(|option| option.label().len())
+            .max()
+            .unwrap_or(0);
+        if width > 0 {
+            lines.push(String::new());
+        }
+        for option in self.options {
+            lines.push(format!(
+                "  {:<width$}  {}",
+                option.label(),
+                option.description
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// A man(1) style page with NAME, SYNOPSIS, DESCRIPTION and OPTIONS sections
+    pub fn man_page(&self) -> String {
+        let title = format!("{}(1)", self.name.to_uppercase());
+        let section = "User Commands";
+        let gap = MAN_WIDTH.saturating_sub(2 * title.len() + section.len()) / 2;
+        let gap = " ".repeat(gap.max(1));
+        let mut lines = vec![
+            format!("{title}{gap}{section}{gap}{title}"),
+            String::new(),
+            "NAME".to_string(),
+            format!("{MAN_INDENT}{} - {}", self.name, self.summary),
+            String::new(),
+            "SYNOPSIS".to_string(),
+            format!("{MAN_INDENT}{}", self.synopsis),
+            String::new(),
+            "DESCRIPTION".to_string(),
+        ];
+        lines.extend(
+            self.description
+                .lines()
+                .map(|line| format!("{MAN_INDENT}{line}").trim_end().to_string()),
+        );
+
+        if !self.options.is_empty() {
+            lines.push(String::new());
+            lines.push("OPTIONS".to_string());
+            for (i, option) in self.options.iter().enumerate() {
+                if i > 0 {
+                    lines.push(String::new());
+                }
+                // Like man, short labels share a line with their description
+                let label = option.label();
+                if label.len() < MAN_INDENT.len() {
+                    let width = MAN_INDENT.len();
+                    lines.push(format!("{MAN_INDENT}{label:<width$}{}", option.description));
+                } else {
+                    lines.push(format!("{MAN_INDENT}{label}"));
+                    lines.push(format!("{MAN_OPTION_INDENT}{}", option.description));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// The list of commands shown by `help`, generated from their usage specs
+pub fn help_list() -> String {
+    let mut cmds = Cmd::all()
+        .into_iter()
+        .filter_map(|name| Cmd::from(name).usage())
+        .collect::<Vec<_>>();
+    cmds.sort_by_key(|usage| usage.name);
+    let width = cmds.iter().map(|usage| usage.name.len()).max().unwrap_or(0);
+
+    let mut lines = vec![
+        "This is Hans Baker's personal website.  Use this terminal to navigate the site."
+            .to_string(),
+        "The commands should feel familiar:".to_string(),
+    ];
+    lines.extend(
+        cmds.iter()
+            .map(|usage| format!("    {:<width$}  {}", usage.name, usage.summary)),
+    );
+    lines.push(String::new());
+    lines.push("Use `man <command>` or `<command> --help` to learn more about one.".to_string());
+    lines.join("\n")
+}
+
+impl Cmd {
+    /// The usage spec for this command, `None` only for unknown commands
+    pub fn usage(&self) -> Option<&'static Usage> {
+        let usage = match self {
+            Self::Help => &HELP,
+            Self::Man => &MAN,
+            Self::Pwd => &PWD,
+            Self::Ls => &LS,
+            Self::Cd => &CD,
+            Self::Cat => &CAT,
+            Self::Clear => &CLEAR,
+            Self::Cp => &CP,
+            Self::Date => &DATE,
+            Self::Echo => &ECHO,
+            Self::History => &HISTORY,
+            Self::Mines => &MINES,
+            Self::MkDir => &MKDIR,
+            Self::Mv => &MV,
+            Self::Rm => &RM,
+            Self::Neofetch => &NEOFETCH,
+            Self::Touch => &TOUCH,
+            Self::Which => &WHICH,
+            Self::WhoAmI => &WHOAMI,
+            Self::Sudo => &SUDO,
+            Self::Uptime => &UPTIME,
+            Self::Ps => &PS,
+            Self::Kill => &KILL,
+            Self::Export => &EXPORT,
+            Self::Unset => &UNSET,
+            Self::Env => &ENV,
+            Self::Printenv => &PRINTENV,
+            Self::Set => &SET,
+            Self::Alias => &ALIAS,
+            Self::Unalias => &UNALIAS,
+            Self::Source => &SOURCE,
+            Self::Grep => &GREP,
+            Self::Find => &FIND,
+            Self::Head => &HEAD,
+            Self::Tail => &TAIL,
+            Self::Wc => &WC,
+            Self::Sort => &SORT,
+            Self::Uniq => &UNIQ,
+            Self::Cut => &CUT,
+            Self::Tr => &TR,
+            Self::Tree => &TREE,
+            Self::Less => &LESS,
+            Self::More => &MORE,
+            Self::Ln => &LN,
+            Self::Readlink => &READLINK,
+            Self::Df => &DF,
+            Self::Du => &DU,
+            Self::Chmod => &CHMOD,
+            Self::Chown => &CHOWN,
+            Self::Umask => &UMASK,
+            Self::Stat => &STAT,
+            Self::Unknown => return None,
+        };
+        Some(usage)
+    }
+}
+
+static HELP: Usage = Usage {
+    name: "help",
+    summary: "list the available commands",
+    synopsis: "help",
+    description: "Print a short introduction to this terminal and a one-line summary of each
+command it understands.",
+    options: &[],
+};
+
+static MAN: Usage = Usage {
+    name: "man",
+    summary: "display the manual page of a command",
+    synopsis: "man command",
+    description: "Format and display the manual page for command, describing what it does and
+which options this version of it supports.",
+    options: &[],
+};
+
+static PWD: Usage = Usage {
+    name: "pwd",
+    summary: "print name of the current/working directory (current URL path)",
+    synopsis: "pwd [-LP]",
+    description: "Print the absolute path of the working directory. The working directory of
+this terminal follows the page being viewed.",
+    options: &[
+        flag(
+            "-L",
+            "print the path as navigated, through any symbolic links",
+        ),
+        flag("-P", "print the path with all symbolic links resolved"),
+    ],
+};
+
+static LS: Usage = Usage {
+    name: "ls",
+    summary: "list directory contents (sitemap)",
+    synopsis: "ls [-ahlrSt] [file ...]",
+    description: "List information about each file operand, or the contents of each directory
+operand. With no operands, list the working directory. Entries are sorted
+alphabetically unless -t or -S says otherwise, and links to pages of the site
+can be followed. Blog posts are dated from when they were published.",
+    options: &[
+        flag("-a", "do not ignore entries starting with ."),
+        flag("-h", "with -l, print sizes like 1K 234M 2G"),
+        flag("-l", "use a long listing format"),
+        flag("-r", "reverse order while sorting"),
+        flag("-S", "sort by file size, largest first"),
+        flag("-t", "sort by modification time, newest first"),
+    ],
+};
+
+static CD: Usage = Usage {
+    name: "cd",
+    summary: "change directory (navigate site)",
+    synopsis: "cd [dir]",
+    description: "Change the working directory to dir, or to the home directory when none is
+given. Changing into a directory that is a page of the site navigates there.
+Symbolic links are kept in the path, so .. leads back out of them.",
+    options: &[],
+};
+
+static CAT: Usage = Usage {
+    name: "cat",
+    summary: "concatenate files and print to the standard output",
+    synopsis: "cat [file ...]",
+    description: "Print each file in turn. With no files, copy the standard input, such as the
+output of the previous command in a pipeline.",
+    options: &[],
+};
+
+static CLEAR: Usage = Usage {
+    name: "clear",
+    summary: "clear the terminal screen",
+    synopsis: "clear",
+    description: "Clear the terminal screen, keeping the command history.",
+    options: &[],
+};
+
+static CP: Usage = Usage {
+    name: "cp",
+    summary: "copy files and directories",
+    synopsis: "cp [-rf] source ... target",
+    description: "Copy source to target, or each source into the directory target.
+Directories are only copied with -r.",
+    options: &[
+        flag("-r", "copy directories recursively"),
+        flag("-f", "accepted for compatibility"),
+    ],
+};
+
+static LN: Usage = Usage {
+    name: "ln",
+    summary: "make links between files",
+    synopsis: "ln -s target link_name",
+    description: "Create a symbolic link named link_name that points at target, or a link
+with the target's name when link_name is a directory. The target is stored as
+given and needn't exist; relative targets are resolved from the link's directory.",
+    options: &[flag("-s", "make a symbolic link (the only kind supported)")],
+};
+
+static DF: Usage = Usage {
+    name: "df",
+    summary: "report file system space usage",
+    synopsis: "df [-hi] [file ...]",
+    description: "Show how much of the space for your own files is used and how much is left.
+Files are kept in the browser's local storage, within a quota on their total
+size, their number and how deeply they're nested.",
+    options: &[
+        flag("-h", "print sizes in powers of 1024 (e.g., 1023K)"),
+        flag("-i", "list inode information instead of block usage"),
+    ],
+};
+
+static DU: Usage = Usage {
+    name: "du",
+    summary: "estimate file space usage",
+    synopsis: "du [-ahs] [file ...]",
+    description: "Summarize the size of each file, the working directory by default, and of
+each directory below it, in 1K blocks. Sizes are apparent sizes: what the files
+hold. Symbolic links are measured, not followed.",
+    options: &[
+        flag("-a", "write counts for all files, not just directories"),
+        flag("-h", "print sizes in human readable format (e.g., 1K 2M)"),
+        flag("-s", "display only a total for each argument"),
+    ],
+};
+
+static CHMOD: Usage = Usage {
+    name: "chmod",
+    summary: "change file mode bits",
+    synopsis: "chmod [-R] mode file ...",
+    description: "Change the permissions of each file, which only its owner (or root) may do.
+The mode is octal, like 755, or symbolic: [ugoa][+-=][rwxX], several joined
+by commas, like u+x,go-w. Without ugoa, the umask bits are left alone.
+The site's own files can't be changed.",
+    options: &[flag("-R", "change files and directories recursively")],
+};
+
+static CHOWN: Usage = Usage {
+    name: "chown",
+    summary: "change file owner and group",
+    synopsis: "chown [-R] [owner][:group] file ...",
+    description: "Change the owner and/or group of each file. Only root may change the owner;
+the owner may change the group to one they belong to. Who you are is taken
+from $USER, so USER=root chown hans file works.",
+    options: &[flag("-R", "operate on files and directories recursively")],
+};
+
+static UMASK: Usage = Usage {
+    name: "umask",
+    summary: "get or set the file mode creation mask",
+    synopsis: "umask [-S] [mode]",
+    description: "Print the mask of permission bits that new files and directories leave out,
+or set it to mode, given in octal like 022 or symbolically like u=rwx,g=rx,o=rx.",
+    options: &[flag("-S", "print the mask in symbolic form")],
+};
+
+static STAT: Usage = Usage {
+    name: "stat",
+    summary: "display file status",
+    synopsis: "stat [-L] file ...",
+    description: "Show the size, type, permissions, owner and times of each file. Symbolic links
+are described themselves unless -L is given.",
+    options: &[flag("-L", "follow links")],
+};
+
+static DATE: Usage = Usage {
+    name: "date",
+    summary: "print the system date and time",
+    synopsis: "date [+format]",
+    description: "Print the current date and time, formatted by the strftime(3) style format
+when one is given.",
+    options: &[],
+};
+
+static ECHO: Usage = Usage {
+    name: "echo",
+    summary: "display a line of text",
+    synopsis: "echo [string ...]",
+    description: "Write the strings, separated by spaces, to the standard output.",
+    options: &[],
+};
+
+static HISTORY: Usage = Usage {
+    name: "history",
+    summary: "display or clear the command history",
+    synopsis: "history [-c | count]",
+    description: "List the commands entered so far, or only the last count of them. Commands
+can be recalled with !n, !! and !prefix.",
+    options: &[flag("-c", "clear the history")],
+};
+
+static MINES: Usage = Usage {
+    name: "mines",
+    summary: "minesweeper app",
+    synopsis: "mines",
+    description: "Open the minesweeper app.",
+    options: &[],
+};
+
+static MKDIR: Usage = Usage {
+    name: "mkdir",
+    summary: "make directories",
+    synopsis: "mkdir directory ...",
+    description: "Create each directory. Its parent directory must already exist.",
+    options: &[],
+};
+
+static MV: Usage = Usage {
+    name: "mv",
+    summary: "move (rename) files",
+    synopsis: "mv source ... target",
+    description: "Rename source to target, or move each source into the directory target.",
+    options: &[],
+};
+
+static RM: Usage = Usage {
+    name: "rm",
+    summary: "remove files or directories",
+    synopsis: "rm [-rf] file ...",
+    description: "Remove each file. Directories are only removed with -r. Files that are part
+of the site itself cannot be removed.",
+    options: &[
+        flag("-r", "remove directories and their contents recursively"),
+        flag("-f", "accepted for compatibility"),
+    ],
+};
+
+static NEOFETCH: Usage = Usage {
+    name: "neofetch",
+    summary: "display information about the site",
+    synopsis: "neofetch",
+    description: "Print an avatar next to a summary of the author and this site.",
+    options: &[],
+};
+
+static TOUCH: Usage = Usage {
+    name: "touch",
+    summary: "change file timestamps",
+    synopsis: "touch [-d date | -t stamp] file ...",
+    description: "Set the access and modification times of each file to now, or to the given
+time, creating the file empty if it does not exist yet. A date is like
+2024-01-31, 2024-01-31 09:30[:00], an RFC 3339 time or @seconds since 1970.",
+    options: &[
+        valued("-d", "date", "use date instead of the current time"),
+        valued(
+            "-t",
+            "stamp",
+            "use [[CC]YY]MMDDhhmm[.ss] instead of the current time",
+        ),
+    ],
+};
+
+static WHICH: Usage = Usage {
+    name: "which",
+    summary: "locate a command",
+    synopsis: "which command ...",
+    description: "Print what each command resolves to: an alias, a shell builtin or the path
+of an executable.",
+    options: &[],
+};
+
+static WHOAMI: Usage = Usage {
+    name: "whoami",
+    summary: "print effective user name",
+    synopsis: "whoami",
+    description: "Print the name of the current user.",
+    options: &[],
+};
+
+static SUDO: Usage = Usage {
+    name: "sudo",
+    summary: "execute a command as another user",
+    synopsis: "sudo command",
+    description: "Run command as the superuser, for users in the sudoers file.",
+    options: &[],
+};
+
+static UPTIME: Usage = Usage {
+    name: "uptime",
+    summary: "tell how long the system has been running",
+    synopsis: "uptime",
+    description: "Print the current time, how long the site has been up since it was last
+deployed, and the system load averages.",
+    options: &[],
+};
+
+static PS: Usage = Usage {
+    name: "ps",
+    summary: "report a snapshot of the current processes",
+    synopsis: "ps [aux]",
+    description: "List the running processes. The aux form shows every process with its owner
+and resource usage.",
+    options: &[],
+};
+
+static KILL: Usage = Usage {
+    name: "kill",
+    summary: "send a signal to a process",
+    synopsis: "kill [-n signum | -SIGNAL] pid",
+    description: "Send a signal, TERM by default, to the process pid.",
+    options: &[],
+};
+
+static EXPORT: Usage = Usage {
+    name: "export",
+    summary: "set environment variables",
+    synopsis: "export [-p] [name[=value] ...]",
+    description: "Mark each name for export to the environment, assigning value first when
+given. With no names, or with -p, list the exported variables.",
+    options: &[flag("-p", "list the exported variables")],
+};
+
+static UNSET: Usage = Usage {
+    name: "unset",
+    summary: "unset shell and environment variables",
+    synopsis: "unset name ...",
+    description: "Remove each named variable from the shell and the environment.",
+    options: &[],
+};
+
+static ENV: Usage = Usage {
+    name: "env",
+    summary: "print the environment",
+    synopsis: "env",
+    description: "Print each exported variable as name=value.",
+    options: &[],
+};
+
+static PRINTENV: Usage = Usage {
+    name: "printenv",
+    summary: "print all or part of the environment",
+    synopsis: "printenv [name ...]",
+    description: "Print the value of each named environment variable, or every variable as
+name=value when none are given.",
+    options: &[],
+};
+
+static SET: Usage = Usage {
+    name: "set",
+    summary: "list shell variables or set shell options",
+    synopsis: "set [-e | +e | -o errexit | +o errexit]",
+    description: "With no arguments, list the shell and environment variables. Otherwise turn
+the errexit option on (-e) or off (+e); with errexit on, a script or command
+list stops at the first failing command, unless && or || tests it.",
+    options: &[
+        flag("-e", "stop at the first failure"),
+        flag("+e", "keep going after failures"),
+    ],
+};
+
+static ALIAS: Usage = Usage {
+    name: "alias",
+    summary: "define or display aliases",
+    synopsis: "alias [name[=value] ...]",
+    description: "Define each name as an alias for value, or print the named aliases. With no
+arguments, list every alias.",
+    options: &[],
+};
+
+static UNALIAS: Usage = Usage {
+    name: "unalias",
+    summary: "remove aliases",
+    synopsis: "unalias [-a] name ...",
+    description: "Remove each named alias.",
+    options: &[flag("-a", "remove every alias")],
+};
+
+static SOURCE: Usage = Usage {
+    name: "source",
+    summary: "run commands from a file",
+    synopsis: "source file [arg ...]",
+    description: "Read and run the commands in file in the current shell, with $1, $2, ...
+set to the args. Also available as `.`.",
+    options: &[],
+};
+
+static GREP: Usage = Usage {
+    name: "grep",
+    summary: "print lines that match patterns",
+    synopsis: "grep [-cilnorvE] pattern [file ...]",
+    description: "Search each file, or the standard input, for lines matching pattern, a basic
+regular expression unless -E is given.",
+    options: &[
+        flag("-i", "ignore case distinctions"),
+        flag("-c", "print only a count of matching lines per file"),
+        flag("-n", "prefix each line with its line number"),
+        flag("-l", "print only the names of files with matches"),
+        flag("-r", "read all files under each directory, recursively"),
+        flag("-v", "select non-matching lines"),
+        flag("-E", "interpret pattern as an extended regular expression"),
+        flag("-o", "print only the matched parts of a line"),
+    ],
+};
+
+static FIND: Usage = Usage {
+    name: "find",
+    summary: "search for files in a directory hierarchy",
+    synopsis: "find [path ...] [expression]",
+    description: "Walk each path, the working directory by default, and print every file
+matching all the tests of the expression.",
+    options: &[
+        valued("-name", "pattern", "base name matches the shell pattern"),
+        valued("-type", "c", "file is of type c: f (file) or d (directory)"),
+        valued(
+            "-maxdepth",
+            "levels",
+            "descend at most levels below the paths",
+        ),
+        valued("-mindepth", "levels", "ignore files less than levels deep"),
+        valued(
+            "-newer",
+            "file",
+            "file was modified more recently than file",
+        ),
+        valued(
+            "-exec",
+            "command {} ;",
+            "run command for each file found, or once for all with +",
+        ),
+    ],
+};
+
+static HEAD: Usage = Usage {
+    name: "head",
+    summary: "output the first part of files",
+    synopsis: "head [-n count] [file ...]",
+    description: "Print the first 10 lines of each file, or of the standard input.",
+    options: &[valued("-n", "count", "print the first count lines instead")],
+};
+
+static TAIL: Usage = Usage {
+    name: "tail",
+    summary: "output the last part of files",
+    synopsis: "tail [-n count] [file ...]",
+    description: "Print the last 10 lines of each file, or of the standard input.",
+    options: &[valued(
+        "-n",
+        "count",
+        "print the last count lines instead, or from line count with +count",
+    )],
+};
+
+static WC: Usage = Usage {
+    name: "wc",
+    summary: "print newline, word, and byte counts",
+    synopsis: "wc [-clw] [file ...]",
+    description: "Print the line, word and byte counts of each file, or of the standard
+input, with a total when there are several files.",
+    options: &[
+        flag("-l", "print the line counts"),
+        flag("-w", "print the word counts"),
+        flag("-c", "print the byte counts"),
+    ],
+};
+
+static SORT: Usage = Usage {
+    name: "sort",
+    summary: "sort lines of text",
+    synopsis: "sort [-nru] [file ...]",
+    description: "Print the lines of all files, or of the standard input, in sorted order.",
+    options: &[
+        flag("-r", "reverse the result of comparisons"),
+        flag("-n", "compare according to numerical value"),
+        flag("-u", "output only the first of equal lines"),
+    ],
+};
+
+static UNIQ: Usage = Usage {
+    name: "uniq",
+    summary: "report or omit repeated lines",
+    synopsis: "uniq [-c] [file]",
+    description: "Print the input with adjacent repeated lines collapsed into one.",
+    options: &[flag("-c", "prefix lines by the number of occurrences")],
+};
+
+static CUT: Usage = Usage {
+    name: "cut",
+    summary: "remove sections from each line of files",
+    synopsis: "cut -f list [-d delim] [file ...]",
+    description: "Print the selected fields of each line. list is a comma separated list of
+field numbers and ranges, like 1,3-5.",
+    options: &[
+        valued(
+            "-d",
+            "delim",
+            "use delim instead of tab as the field delimiter",
+        ),
+        valued("-f", "list", "select only these fields"),
+    ],
+};
+
+static TR: Usage = Usage {
+    name: "tr",
+    summary: "translate or delete characters",
+    synopsis: "tr [-ds] set1 [set2]",
+    description: "Copy the standard input to the standard output, replacing characters in
+set1 with the matching ones in set2. Sets may use ranges like a-z, classes
+like [:upper:] and escapes like \\n.",
+    options: &[
+        flag("-d", "delete characters in set1"),
+        flag("-s", "squeeze repeated characters into one"),
+    ],
+};
+
+static TREE: Usage = Usage {
+    name: "tree",
+    summary: "list contents of directories in a tree-like format",
+    synopsis: "tree [-adJ] [-L level] [--charset=ascii] [directory ...]",
+    description: "List the contents of each directory, the working directory by default, as
+a tree, followed by a count of the directories and files listed.",
+    options: &[
+        flag("-a", "list all files, including those starting with ."),
+        flag("-d", "list directories only"),
+        valued("-L", "level", "descend only level directories deep"),
+        flag("-J", "print the tree as JSON"),
+        flag("--charset=ascii", "draw the tree with ASCII characters"),
+    ],
+};
+
+static READLINK: Usage = Usage {
+    name: "readlink",
+    summary: "print the target of a symbolic link",
+    synopsis: "readlink file ...",
+    description: "Print the path each symbolic link points at. Files that aren't links print
+nothing and make readlink fail.",
+    options: &[],
+};
+
+static LESS: Usage = Usage {
+    name: "less",
+    summary: "view text one screen at a time",
+    synopsis: "less [file ...]",
+    description: "Show each file, or the standard input, one screen at a time, away from the
+scrollback. Output from other commands that is too tall for the screen is
+paged the same way.
+
+Commands:
+  SPACE, f    forward one screen
+  b           backward one screen
+  j, ENTER    forward one line
+  k           backward one line
+  g, G        go to the first or last line
+  /pattern    search forward for a regular expression
+  n, N        repeat the search forward or backward
+  q           quit",
+    options: &[],
+};
+
+static MORE: Usage = Usage {
+    name: "more",
+    summary: "view text one screen at a time",
+    synopsis: "more [file ...]",
+    description: "The same pager as less(1), with the same commands.",
+    options: &[],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_specs() {
+        // Every command has a spec under its own name
+        for name in Cmd::all() {
+            let usage = Cmd::from(name)
+                .usage()
+                .expect("every command has a usage spec");
+            assert_eq!(usage.name, name);
+        }
+        assert!(Cmd::Unknown.usage().is_none());
+
+        let ls = Cmd::Ls.usage().unwrap();
+        assert!(ls.supports('a') && ls.supports('l') && !ls.supports('x'));
+        assert_eq!(
+            ls.invalid_option('x'),
+            "ls: invalid option -- 'x'\nThis version of ls only supports options 'a', 'h', 'l', 'r', 'S' and 't'"
+        );
+        assert_eq!(
+            Cmd::Cat.usage().unwrap().supported_options(),
+            "This version of cat doesn't support any options"
+        );
+        assert_eq!(
+            Cmd::Tree.usage().unwrap().supported_options(),
+            "This version of tree only supports options 'a', 'd', 'L level', 'J' and '--charset=ascii'"
+        );
+        assert!(Cmd::Cut.usage().unwrap().takes_value('f'));
+        assert!(!Cmd::Sort.usage().unwrap().takes_value('n'));
+    }
+
+    #[test]
+    fn test_usage_rendering() {
+        let ls = Cmd::Ls.usage().unwrap();
+        assert_eq!(
+            ls.help_text(),
+            "usage: ls [-ahlrSt] [file ...]
+
+list directory contents (sitemap)
+
+  -a  do not ignore entries starting with .
+  -h  with -l, print sizes like 1K 234M 2G
+  -l  use a long listing format
+  -r  reverse order while sorting
+  -S  sort by file size, largest first
+  -t  sort by modification time, newest first"
+        );
+
+        let page = Cmd::Tree.usage().unwrap().man_page();
+        let lines = page.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("TREE(1)") && lines[0].ends_with("TREE(1)"));
+        assert!(lines[0].contains("User Commands"));
+        assert!(lines.contains(&"NAME"));
+        assert!(lines.contains(&"       tree - list contents of directories in a tree-like format"));
+        assert!(lines.contains(&"       -a     list all files, including those starting with ."));
+        assert!(lines.contains(&"       -L level"));
+        assert!(lines.contains(&"              descend only level directories deep"));
+
+        // Commands without options have no OPTIONS section
+        assert!(!Cmd::Cd.usage().unwrap().man_page().contains("OPTIONS"));
+
+        let help = help_list();
+        assert!(help.starts_with("This is Hans Baker's personal website."));
+        assert!(help.contains("\n    ls        list directory contents (sitemap)\n"));
+        assert!(help.contains("\n    man       display the manual page of a command\n"));
+        assert!(!help.contains("sudo"));
+    }
+}
@@ -1,6 +1,15 @@
 #![allow(dead_code)]
-use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Local};
 use indextree::{Arena, NodeId};
+use serde::{Deserialize, Serialize};
+
+use super::disk_tools::human_size;
+use super::ps_tools::Process;
+use super::simple_tools::boot_time;
+use crate::blog::{get_post_source, PostMeta};
 
 // Re-use the same static file contents from the original VFS
 const MINES_SH: &str = r#"#!/bin/bash
@@ -11,10 +20,24 @@ set -e
 mines
 "#;
 
+/// Who the terminal's user is until `$USER` says otherwise
+pub const DEFAULT_USER: &str = "user";
+
+/// New files are `rw-r--r--` and new directories `rwxr-xr-x`
+const DEFAULT_UMASK: u16 = 0o022;
+
+/// How many symbolic links one path may go through before it's taken to be a loop, as on Linux
+const MAX_SYMLINKS: usize = 40;
+
 const THANKS_TXT: &str =
     "Thank you to my wife and my daughter for bringing immense joy to my life.";
 
+/// The robbyrussell prompt set by the default `.zshrc`, which the header renders with its own theme
+pub const DEFAULT_PROMPT: &str =
+    "%(?:%{$fg_bold[green]%}➜ :%{$fg_bold[red]%}➜ )%{$fg[cyan]%}%c%{$reset_color%} $(git_prompt_info)";
+
 const ZSHRC_CONTENT: &str = r#"# Simple zsh configuration
+# Sourced when the terminal starts. Copy it, edit the copy and `source` it to try changes.
 unsetopt beep
 
 # Basic completion
@@ -51,6 +74,8 @@ bindkey '^[[B' history-substring-search-down # or '\eOB'
 HISTORY_SUBSTRING_SEARCH_ENSURE_UNIQUE=1
 HISTORY_SUBSTRING_SEARCH_HIGHLIGHT_FOUND=0
 HISTORY_SUBSTRING_SEARCH_HIGHLIGHT_NOT_FOUND=0
+
+export EDITOR=vim
 "#;
 
 #[derive(Debug, Clone)]
@@ -62,32 +87,41 @@ pub struct VfsNode {
 }
 
 impl VfsNode {
-    pub fn long_meta_string(&self, link_count: usize) -> String {
-        let is_directory = matches!(self.node_type, VfsNodeType::Directory);
-        let is_executable = self.permissions.execute;
+    /// Everything `ls -l` shows before the name. With `human`, sizes are like `1.5K`.
+    pub fn long_meta_string(&self, link_count: usize, human: bool) -> String {
+        let type_char = match self.node_type {
+            VfsNodeType::Directory => "d",
+            VfsNodeType::File { .. } => "-",
+            // A link's own permissions are never checked, so they always show as lrwxrwxrwx
+            VfsNodeType::Link { .. } => return self.meta_string("lrwxrwxrwx", link_count, human),
+        };
         // Generate permissions string (similar to Unix ls -l format)
-        let permissions = format!(
-            "{}{}{}{}{}{}{}{}{}{}",
-            if is_directory { "d" } else { "-" },
-            if self.permissions.read { "r" } else { "-" },
-            if self.permissions.write { "w" } else { "-" },
-            if is_executable { "x" } else { "-" },
-            if self.permissions.read { "r" } else { "-" },
-            if self.permissions.write { "w" } else { "-" },
-            if is_executable { "x" } else { "-" },
-            if self.permissions.read { "r" } else { "-" },
-            if self.permissions.write { "w" } else { "-" },
-            if is_executable { "x" } else { "-" },
-        );
-        {
-            format!(
-                "{} {:2} {:6} {:6} {:>6} ",
-                permissions,
-                link_count,
-                self.metadata.owner,
-                self.metadata.group,
-                self.metadata.size
-            )
+        let permissions = format!("{type_char}{}", self.permissions.mode_string());
+        self.meta_string(&permissions, link_count, human)
+    }
+
+    fn meta_string(&self, permissions: &str, link_count: usize, human: bool) -> String {
+        let size = if human {
+            human_size(self.size())
+        } else {
+            self.size().to_string()
+        };
+        format!(
+            "{} {:2} {:6} {:6} {:>6} {} ",
+            permissions,
+            link_count,
+            self.metadata.owner,
+            self.metadata.group,
+            size,
+            ls_time(self.metadata.modified)
+        )
+    }
+
+    /// Where a symbolic link points, or `None` for anything else
+    pub fn link_target(&self) -> Option<&str> {
+        match &self.node_type {
+            VfsNodeType::Link { target } => Some(target),
+            _ => None,
         }
     }
 
@@ -95,8 +129,32 @@ impl VfsNode {
         matches!(self.node_type, VfsNodeType::Directory)
     }
 
+    /// The size in bytes. Blog files are only fetched once something reads them.
+    pub fn size(&self) -> u64 {
+        match &self.node_type {
+            VfsNodeType::File { content } if content.is_blog_file() => content.size(),
+            _ => self.metadata.size,
+        }
+    }
+
     pub fn is_executable(&self) -> bool {
-        self.permissions.execute
+        self.permissions.mode & 0o111 != 0
+    }
+
+    /// Whether `user` may use this node in all the `access` ways (`READ`, `WRITE`, `EXECUTE`).
+    /// Root may do anything.
+    pub fn allows(&self, user: &str, access: u16) -> bool {
+        if user == ROOT {
+            return true;
+        }
+        let shift = if self.metadata.owner == user {
+            6
+        } else if in_group(user, &self.metadata.group) {
+            3
+        } else {
+            0
+        };
+        (self.permissions.mode >> shift) & access == access
     }
 
     pub fn is_hidden(&self) -> bool {
@@ -116,63 +174,144 @@ pub enum FileContent {
     Static(&'static str),
     Dynamic(String),
     NavFile(String),
+    BlogPost(String), // A post's markdown, by post name
+    BlogMeta(String), // A post's metadata as YAML, by post name
+    Generated(Generator),
 }
 
-#[derive(Debug, Clone)]
+/// Computes a file's contents from the terminal's live state each time it's read, for `/proc`
+#[derive(Clone)]
+pub struct Generator(Arc<dyn Fn(&ProcState) -> String + Send + Sync>);
+
+impl Generator {
+    pub fn new(generate: impl Fn(&ProcState) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(generate))
+    }
+}
+
+impl std::fmt::Debug for Generator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Generator")
+    }
+}
+
+/// The terminal state `/proc` describes, kept up to date by the terminal
+#[derive(Debug, Clone, Default)]
+pub struct ProcState {
+    pub processes: Vec<Process>,
+    pub self_pid: u32, // The process `/proc/self` is, i.e. the terminal
+    pub env_vars: HashMap<String, String>,
+}
+
+impl FileContent {
+    fn size(&self) -> u64 {
+        match self {
+            FileContent::Static(s) => s.len() as u64,
+            FileContent::Dynamic(s) => s.len() as u64,
+            FileContent::NavFile(_) => 512,
+            FileContent::BlogPost(post) => get_post_source(post)
+                .map(|source| source.markdown.len() as u64)
+                .unwrap_or_default(),
+            FileContent::BlogMeta(post) => get_post_source(post)
+                .map(|source| source.meta.to_yaml().len() as u64)
+                .unwrap_or_default(),
+            // As on Linux, generated files don't know how big they are until they're read
+            FileContent::Generated(_) => 0,
+        }
+    }
+
+    fn is_blog_file(&self) -> bool {
+        matches!(self, FileContent::BlogPost(_) | FileContent::BlogMeta(_))
+    }
+}
+
+/// Access bits for `VfsNode::allows`, as in each `rwx` triple of a mode
+pub const READ: u16 = 0o4;
+pub const WRITE: u16 = 0o2;
+pub const EXECUTE: u16 = 0o1;
+
+/// The superuser, whom modes don't apply to
+pub const ROOT: &str = "root";
+
+/// The users there are, and the groups each belongs to besides their own
+pub const USERS: [(&str, &[&str]); 3] = [
+    (ROOT, &["wheel"]),
+    ("hans", &["staff", "wheel"]),
+    (DEFAULT_USER, &[]),
+];
+
+/// Whether `user` is in `group`. Everyone is in the group named after them.
+pub fn in_group(user: &str, group: &str) -> bool {
+    user == group
+        || USERS
+            .iter()
+            .any(|(name, groups)| *name == user && groups.contains(&group))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Permissions {
-    pub read: bool,
-    pub write: bool,
-    pub execute: bool,
-    pub immutable: bool,
+    pub mode: u16,       // Owner, group and other rwx bits, e.g. 0o644
+    pub immutable: bool, // System nodes can't be changed or removed, whatever their mode
 }
 
 impl Default for Permissions {
     fn default() -> Self {
+        Self::new(0o644)
+    }
+}
+
+impl Permissions {
+    pub fn new(mode: u16) -> Self {
         Self {
-            read: true,
-            write: true,
-            execute: false,
+            mode,
             immutable: false,
         }
     }
-}
 
-impl Permissions {
     pub fn read_only() -> Self {
         Self {
-            read: true,
-            write: false,
-            execute: false,
+            mode: 0o444,
             immutable: true,
         }
     }
 
     pub fn executable() -> Self {
         Self {
-            read: true,
-            write: false,
-            execute: true,
+            mode: 0o555,
             immutable: true,
         }
     }
 
     pub fn system_dir() -> Self {
         Self {
-            read: true,
-            write: true, // Allow file creation in system directories
-            execute: true,
+            mode: 0o777,     // Allow file creation in system directories
             immutable: true, // But prevent deletion of the directory itself
         }
     }
+
+    /// The mode as `ls -l` shows it, e.g. `rw-r--r--`
+    pub fn mode_string(&self) -> String {
+        (0..9)
+            .map(|i| {
+                if self.mode & (0o400 >> i) != 0 {
+                    ['r', 'w', 'x'][i % 3]
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NodeMetadata {
     pub size: u64,
     pub owner: String,
     pub group: String,
-    pub created: DateTime<Local>,
-    pub modified: DateTime<Local>,
+    pub created: DateTime<Local>,  // Birth time
+    pub accessed: DateTime<Local>, // Last read (atime)
+    pub modified: DateTime<Local>, // Last change to the contents (mtime)
+    pub changed: DateTime<Local>,  // Last change to the contents, name or permissions (ctime)
 }
 
 impl Default for NodeMetadata {
@@ -183,11 +322,33 @@ impl Default for NodeMetadata {
             owner: "hans".to_string(),
             group: "staff".to_string(),
             created: now,
+            accessed: now,
             modified: now,
+            changed: now,
         }
     }
 }
 
+impl NodeMetadata {
+    /// Set every time to `time`, as if the node had been made then and left alone since
+    fn set_all_times(&mut self, time: DateTime<Local>) {
+        self.created = time;
+        self.accessed = time;
+        self.modified = time;
+        self.changed = time;
+    }
+}
+
+/// A time as `ls -l` shows it: the time of day if it's from the last six months, else the year
+fn ls_time(time: DateTime<Local>) -> String {
+    let now = Local::now();
+    if time <= now && now - time < Duration::days(183) {
+        time.format("%b %e %H:%M").to_string()
+    } else {
+        time.format("%b %e  %Y").to_string()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum VfsError {
     NotFound,
@@ -197,32 +358,89 @@ pub enum VfsError {
     AlreadyExists,
     QuotaExceeded,
     InvalidPath,
+    TooManyLinks,
     SystemError(String),
 }
 
+impl std::fmt::Display for VfsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VfsError::NotFound => write!(f, "no such file or directory"),
+            VfsError::PermissionDenied => write!(f, "permission denied"),
+            VfsError::NotADirectory => write!(f, "not a directory"),
+            VfsError::NotAFile => write!(f, "is a directory"),
+            VfsError::AlreadyExists => write!(f, "file exists"),
+            VfsError::QuotaExceeded => write!(f, "disk quota exceeded"),
+            VfsError::InvalidPath => write!(f, "invalid path"),
+            VfsError::TooManyLinks => write!(f, "too many levels of symbolic links"),
+            VfsError::SystemError(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// Limits on what users can add, so the filesystem and its saved snapshot stay small
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quota {
+    pub max_bytes: u64,   // Total size of user files and links
+    pub max_nodes: usize, // Number of user files, directories and links
+    pub max_depth: usize, // Components in the path of any user node
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024,
+            max_nodes: 1000,
+            max_depth: 16,
+        }
+    }
+}
+
+/// How much of the quota user nodes are taking up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskUsage {
+    pub bytes: u64,
+    pub nodes: usize,
+}
+
 pub struct VirtualFilesystem {
     arena: Arena<VfsNode>,
     root: NodeId,
+    quota: Quota,
+    user: String,  // Who permissions are checked for, and who owns new nodes
+    umask: u16,    // Mode bits left out of new nodes
+    changed: bool, // Whether user nodes have changed since the last `take_changed`
+    proc: ProcState,
 }
 
 impl VirtualFilesystem {
     pub fn new(blog_posts: Vec<String>) -> Self {
         let mut arena = Arena::new();
 
-        // Create root directory (writable by users)
+        // Create root directory, which is the user's home
         let root_node = VfsNode {
             name: String::new(),
             node_type: VfsNodeType::Directory,
-            permissions: Permissions::default(),
+            permissions: Permissions::new(0o755),
             metadata: NodeMetadata {
                 size: 4096,
+                owner: DEFAULT_USER.to_string(),
+                group: DEFAULT_USER.to_string(),
                 ..Default::default()
             },
         };
 
         let root = arena.new_node(root_node);
 
-        let mut vfs = Self { arena, root };
+        let mut vfs = Self {
+            arena,
+            root,
+            quota: Quota::default(),
+            user: DEFAULT_USER.to_string(),
+            umask: DEFAULT_UMASK,
+            changed: false,
+            proc: ProcState::default(),
+        };
 
         // Initialize the filesystem structure
         vfs.initialize_system_structure(blog_posts);
@@ -265,6 +483,20 @@ impl VirtualFilesystem {
                 post_dir_id,
             )
             .unwrap();
+            self.create_system_file_in(
+                &format!("{post}.md"),
+                FileContent::BlogPost(post.clone()),
+                false,
+                post_dir_id,
+            )
+            .unwrap();
+            self.create_system_file_in(
+                "meta.yaml",
+                FileContent::BlogMeta(post.clone()),
+                false,
+                post_dir_id,
+            )
+            .unwrap();
         }
 
         // Create cv directory files
@@ -277,6 +509,115 @@ impl VirtualFilesystem {
         .unwrap();
     }
 
+    /// Date each post's directory and files from when it was published. Until the browser has
+    /// the posts' metadata, they keep the time the filesystem was built.
+    pub fn seed_post_times(&mut self, posts: &[PostMeta]) {
+        for post in posts {
+            let Ok(post_dir) = self.resolve_path(self.root, &format!("/blog/{}", post.name)) else {
+                continue;
+            };
+            let date = post.date.with_timezone(&Local);
+            let nodes = post_dir.descendants(&self.arena).collect::<Vec<_>>();
+            for node_id in nodes {
+                let node = self.arena[node_id].get_mut();
+                if node.permissions.immutable {
+                    node.metadata.set_all_times(date);
+                }
+            }
+        }
+    }
+
+    /// Add `/proc`, whose files describe the terminal's processes and environment as they are
+    /// whenever they're read
+    pub fn mount_proc(&mut self, processes: Vec<Process>, self_pid: u32) {
+        let proc_dir = self.create_proc_node(self.root, "proc", VfsNodeType::Directory, ROOT);
+        self.create_proc_file(proc_dir, "uptime", |_| proc_uptime());
+        self.create_proc_file(proc_dir, "version", |_| proc_version());
+        for process in &processes {
+            let pid = process.pid;
+            let pid_dir = self.create_proc_node(
+                proc_dir,
+                &pid.to_string(),
+                VfsNodeType::Directory,
+                &process.user,
+            );
+            self.create_proc_file(pid_dir, "cmdline", move |state| {
+                state
+                    .process(pid)
+                    .map(|process| process.command.clone())
+                    .unwrap_or_default()
+            });
+            self.create_proc_file(pid_dir, "status", move |state| {
+                state
+                    .process(pid)
+                    .map(|process| proc_status(process, state.self_pid))
+                    .unwrap_or_default()
+            });
+            if pid == self_pid {
+                self.create_proc_file(pid_dir, "environ", |state| proc_environ(&state.env_vars));
+            }
+        }
+        let target = self_pid.to_string();
+        self.create_proc_node(proc_dir, "self", VfsNodeType::Link { target }, ROOT);
+
+        self.proc.processes = processes;
+        self.proc.self_pid = self_pid;
+    }
+
+    /// Keep `/proc/self/environ` in step with the terminal's exported variables
+    pub fn set_environment(&mut self, env_vars: &HashMap<String, String>) {
+        if self.proc.env_vars != *env_vars {
+            self.proc.env_vars = env_vars.clone();
+        }
+    }
+
+    /// A generated file's contents as of now
+    pub fn generate(&self, generator: &Generator) -> String {
+        (generator.0)(&self.proc)
+    }
+
+    fn create_proc_file(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        generate: impl Fn(&ProcState) -> String + Send + Sync + 'static,
+    ) {
+        let content = FileContent::Generated(Generator::new(generate));
+        let owner = self.arena[parent].get().metadata.owner.clone();
+        self.create_proc_node(parent, name, VfsNodeType::File { content }, &owner);
+    }
+
+    /// Like Linux's, `/proc` can't be written to, and everything in it dates from boot
+    fn create_proc_node(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        node_type: VfsNodeType,
+        owner: &str,
+    ) -> NodeId {
+        let (permissions, size) = match &node_type {
+            VfsNodeType::Directory => (Permissions::executable(), 0),
+            VfsNodeType::File { .. } => (Permissions::read_only(), 0),
+            VfsNodeType::Link { target } => (Permissions::read_only(), target.len() as u64),
+        };
+        let mut metadata = NodeMetadata {
+            size,
+            owner: owner.to_string(),
+            group: owner.to_string(),
+            ..Default::default()
+        };
+        metadata.set_all_times(boot_time());
+
+        let node_id = self.arena.new_node(VfsNode {
+            name: name.to_string(),
+            node_type,
+            permissions,
+            metadata,
+        });
+        parent.append(node_id, &mut self.arena);
+        node_id
+    }
+
     fn create_system_directory(&mut self, name: &str) -> Result<NodeId, VfsError> {
         let dir_node = VfsNode {
             name: name.to_string(),
@@ -329,11 +670,7 @@ impl VirtualFilesystem {
         executable: bool,
         parent: NodeId,
     ) -> Result<NodeId, VfsError> {
-        let size = match &content {
-            FileContent::Static(s) => s.len() as u64,
-            FileContent::Dynamic(s) => s.len() as u64,
-            FileContent::NavFile(_) => 512,
-        };
+        let size = content.size();
 
         let permissions = if executable {
             Permissions::executable()
@@ -360,7 +697,23 @@ impl VirtualFilesystem {
     }
 
     // Path resolution
+    /// Resolve `path` from `base`, following symbolic links all the way (like `stat`)
     pub fn resolve_path(&self, base: NodeId, path: &str) -> Result<NodeId, VfsError> {
+        self.resolve(base, path, true, &mut 0)
+    }
+
+    /// Resolve `path` from `base`, but return a link named by the last component itself (like `lstat`)
+    pub fn resolve_path_nofollow(&self, base: NodeId, path: &str) -> Result<NodeId, VfsError> {
+        self.resolve(base, path, false, &mut 0)
+    }
+
+    fn resolve(
+        &self,
+        base: NodeId,
+        path: &str,
+        follow_last: bool,
+        links: &mut usize,
+    ) -> Result<NodeId, VfsError> {
         if path.is_empty() || path == "." {
             return Ok(base);
         }
@@ -377,21 +730,28 @@ impl VirtualFilesystem {
         if expanded_path.starts_with('/') {
             let stripped = expanded_path.strip_prefix('/').unwrap();
             // Absolute path
-            self.resolve_path_from(self.root, stripped)
+            self.resolve_path_from(self.root, stripped, follow_last, links)
         } else {
             // Relative path
-            self.resolve_path_from(base, expanded_path)
+            self.resolve_path_from(base, expanded_path, follow_last, links)
         }
     }
 
-    fn resolve_path_from(&self, mut current: NodeId, path: &str) -> Result<NodeId, VfsError> {
+    fn resolve_path_from(
+        &self,
+        mut current: NodeId,
+        path: &str,
+        follow_last: bool,
+        links: &mut usize,
+    ) -> Result<NodeId, VfsError> {
         if path.is_empty() {
             return Ok(current);
         }
 
         let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let last = parts.len().saturating_sub(1);
 
-        for part in parts {
+        for (i, part) in parts.into_iter().enumerate() {
             match part {
                 "." => continue,
                 ".." => {
@@ -416,6 +776,10 @@ impl VirtualFilesystem {
                     if !found {
                         return Err(VfsError::NotFound);
                     }
+                    // Links are followed on the way through, and at the end unless asked not to
+                    if i < last || follow_last {
+                        current = self.follow_links(current, links)?;
+                    }
                 }
             }
         }
@@ -423,6 +787,23 @@ impl VirtualFilesystem {
         Ok(current)
     }
 
+    /// Follow `node` to whatever it finally points at, or return it as is if it isn't a link.
+    /// `links` counts the links followed so far for the whole path, to catch cycles.
+    fn follow_links(&self, node: NodeId, links: &mut usize) -> Result<NodeId, VfsError> {
+        let VfsNodeType::Link { target } = &self.arena[node].get().node_type else {
+            return Ok(node);
+        };
+
+        *links += 1;
+        if *links > MAX_SYMLINKS {
+            return Err(VfsError::TooManyLinks);
+        }
+
+        // Relative targets are relative to the directory holding the link
+        let parent = self.arena[node].parent().unwrap_or(self.root);
+        self.resolve(parent, target, true, links)
+    }
+
     // CRUD operations
     pub fn create_file(
         &mut self,
@@ -434,7 +815,7 @@ impl VirtualFilesystem {
         let parent_node = self.arena.get(parent).ok_or(VfsError::NotFound)?;
 
         // Check permissions
-        if !parent_node.get().permissions.write {
+        if !parent_node.get().allows(&self.user, WRITE | EXECUTE) {
             return Err(VfsError::PermissionDenied);
         }
 
@@ -453,27 +834,25 @@ impl VirtualFilesystem {
         }
 
         // Calculate file size
-        let size = match &content {
-            FileContent::Static(s) => s.len() as u64,
-            FileContent::Dynamic(s) => s.len() as u64,
-            FileContent::NavFile(_) => 512,
-        };
+        let size = content.size();
+        self.check_quota(parent, size)?;
 
         // Create the file node
         let file_node = VfsNode {
             name: name.to_string(),
             node_type: VfsNodeType::File { content },
-            permissions: Permissions::default(),
+            permissions: Permissions::new(0o666 & !self.umask),
             metadata: NodeMetadata {
                 size,
-                owner: "user".to_string(),
-                group: "user".to_string(),
+                owner: self.user.clone(),
+                group: self.user.clone(),
                 ..Default::default()
             },
         };
 
         let file_id = self.arena.new_node(file_node);
         parent.append(file_id, &mut self.arena);
+        self.entries_changed(parent);
 
         Ok(file_id)
     }
@@ -483,7 +862,7 @@ impl VirtualFilesystem {
         let parent_node = self.arena.get(parent).ok_or(VfsError::NotFound)?;
 
         // Check permissions
-        if !parent_node.get().permissions.write {
+        if !parent_node.get().allows(&self.user, WRITE | EXECUTE) {
             return Err(VfsError::PermissionDenied);
         }
 
@@ -501,47 +880,165 @@ impl VirtualFilesystem {
             }
         }
 
+        self.check_quota(parent, 0)?;
+
         // Create the directory node
         let dir_node = VfsNode {
             name: name.to_string(),
             node_type: VfsNodeType::Directory,
-            permissions: Permissions::default(),
+            permissions: Permissions::new(0o777 & !self.umask),
             metadata: NodeMetadata {
                 size: 4096,
-                owner: "user".to_string(),
-                group: "user".to_string(),
+                owner: self.user.clone(),
+                group: self.user.clone(),
                 ..Default::default()
             },
         };
 
         let dir_id = self.arena.new_node(dir_node);
         parent.append(dir_id, &mut self.arena);
+        self.entries_changed(parent);
 
         Ok(dir_id)
     }
 
-    pub fn read_file(&self, node: NodeId) -> Result<String, VfsError> {
+    /// Create a symbolic link named `name` pointing at `target`, which needn't exist
+    pub fn create_symlink(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        target: &str,
+    ) -> Result<NodeId, VfsError> {
+        // Validate parent is a directory
+        let parent_node = self.arena.get(parent).ok_or(VfsError::NotFound)?;
+
+        // Check permissions
+        if !parent_node.get().allows(&self.user, WRITE | EXECUTE) {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        // Ensure it's a directory
+        if !matches!(parent_node.get().node_type, VfsNodeType::Directory) {
+            return Err(VfsError::NotADirectory);
+        }
+
+        // Check if name already exists
+        for child_id in parent.children(&self.arena) {
+            if let Some(child_node) = self.arena.get(child_id) {
+                if child_node.get().name == name {
+                    return Err(VfsError::AlreadyExists);
+                }
+            }
+        }
+
+        // A link's size is the length of the path it holds
+        self.check_quota(parent, target.len() as u64)?;
+        let link_node = VfsNode {
+            name: name.to_string(),
+            node_type: VfsNodeType::Link {
+                target: target.to_string(),
+            },
+            permissions: Permissions::new(0o777),
+            metadata: NodeMetadata {
+                size: target.len() as u64,
+                owner: self.user.clone(),
+                group: self.user.clone(),
+                ..Default::default()
+            },
+        };
+
+        let link_id = self.arena.new_node(link_node);
+        parent.append(link_id, &mut self.arena);
+        self.entries_changed(parent);
+
+        Ok(link_id)
+    }
+
+    /// Read a file's contents, following links, and note that it's been accessed. A change in
+    /// access time alone isn't worth saving, so it's saved along with the next real change.
+    pub fn read_file(&mut self, node: NodeId) -> Result<String, VfsError> {
         let node_ref = self.arena.get(node).ok_or(VfsError::NotFound)?;
         let node_data = node_ref.get();
 
         // Check read permission
-        if !node_data.permissions.read {
+        if !node_data.allows(&self.user, READ) {
             return Err(VfsError::PermissionDenied);
         }
 
-        match &node_data.node_type {
+        let text = match &node_data.node_type {
             VfsNodeType::File { content } => match content {
                 FileContent::Static(s) => Ok(s.to_string()),
                 FileContent::Dynamic(s) => Ok(s.clone()),
                 FileContent::NavFile(path) => Ok(generate_nav_content(path)),
+                FileContent::BlogPost(post) => get_post_source(post)
+                    .map(|source| source.markdown)
+                    .ok_or_else(blog_unavailable),
+                FileContent::BlogMeta(post) => get_post_source(post)
+                    .map(|source| source.meta.to_yaml())
+                    .ok_or_else(blog_unavailable),
+                FileContent::Generated(generator) => Ok(self.generate(generator)),
             },
             VfsNodeType::Directory => Err(VfsError::NotAFile),
-            VfsNodeType::Link { target } => {
+            VfsNodeType::Link { .. } => {
                 // Follow the link and read the target
-                let target_node = self.resolve_path(self.root, target)?;
-                self.read_file(target_node)
+                let target_node = self.follow_links(node, &mut 0)?;
+                return self.read_file(target_node);
             }
+        }?;
+
+        self.arena[node].get_mut().metadata.accessed = Local::now();
+        Ok(text)
+    }
+
+    /// Replace (or append to) a file's contents, turning it into a `FileContent::Dynamic` file
+    pub fn write_file(&mut self, node: NodeId, text: &str, append: bool) -> Result<(), VfsError> {
+        let node_ref = self.arena.get(node).ok_or(VfsError::NotFound)?;
+        let node_data = node_ref.get();
+
+        // Follow links to the file they point at
+        if let VfsNodeType::Link { .. } = &node_data.node_type {
+            let target_node = self.follow_links(node, &mut 0)?;
+            return self.write_file(target_node, text, append);
         }
+
+        let VfsNodeType::File { content } = &node_data.node_type else {
+            return Err(VfsError::NotAFile);
+        };
+
+        // Check write permission
+        if !node_data.allows(&self.user, WRITE) || node_data.permissions.immutable {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        let mut new_content = match content {
+            _ if !append => String::new(),
+            FileContent::Static(s) => s.to_string(),
+            FileContent::Dynamic(s) => s.clone(),
+            FileContent::NavFile(_)
+            | FileContent::BlogPost(_)
+            | FileContent::BlogMeta(_)
+            | FileContent::Generated(_) => return Err(VfsError::PermissionDenied),
+        };
+        new_content.push_str(text);
+
+        // Only the growth counts against the quota
+        let old_size = node_data.size();
+        let new_size = new_content.len() as u64;
+        if new_size > old_size && self.usage().bytes + new_size - old_size > self.quota.max_bytes {
+            return Err(VfsError::QuotaExceeded);
+        }
+
+        let node_data = self.arena[node].get_mut();
+        let now = Local::now();
+        node_data.metadata.size = new_content.len() as u64;
+        node_data.metadata.modified = now;
+        node_data.metadata.changed = now;
+        node_data.node_type = VfsNodeType::File {
+            content: FileContent::Dynamic(new_content),
+        };
+        self.changed = true;
+
+        Ok(())
     }
 
     pub fn list_directory(&self, node: NodeId) -> Result<Vec<DirEntry>, VfsError> {
@@ -549,7 +1046,7 @@ impl VirtualFilesystem {
         let node_data = node_ref.get();
 
         // Check read permission
-        if !node_data.permissions.read {
+        if !node_data.allows(&self.user, READ) {
             return Err(VfsError::PermissionDenied);
         }
 
@@ -564,7 +1061,7 @@ impl VirtualFilesystem {
                             name: child_data.name.clone(),
                             node_id: child_id,
                             is_directory: matches!(child_data.node_type, VfsNodeType::Directory),
-                            is_executable: child_data.permissions.execute,
+                            is_executable: child_data.is_executable(),
                         });
                     }
                 }
@@ -573,9 +1070,9 @@ impl VirtualFilesystem {
                 Ok(entries)
             }
             VfsNodeType::File { .. } => Err(VfsError::NotADirectory),
-            VfsNodeType::Link { target } => {
+            VfsNodeType::Link { .. } => {
                 // Follow the link and list the target
-                let target_node = self.resolve_path(self.root, target)?;
+                let target_node = self.follow_links(node, &mut 0)?;
                 self.list_directory(target_node)
             }
         }
@@ -591,8 +1088,8 @@ impl VirtualFilesystem {
         let node_ref = self.arena.get(node).ok_or(VfsError::NotFound)?;
         let node_data = node_ref.get();
 
-        // Check permissions
-        if node_data.permissions.immutable {
+        // Check permissions: removing a node changes its directory
+        if node_data.permissions.immutable || !self.parent_allows_changes(node) {
             return Err(VfsError::PermissionDenied);
         }
 
@@ -604,7 +1101,11 @@ impl VirtualFilesystem {
         }
 
         // Remove the node from the tree (indextree handles parent cleanup!)
+        let parent = self.arena[node].parent();
         node.remove(&mut self.arena);
+        if let Some(parent) = parent {
+            self.entries_changed(parent);
+        }
 
         Ok(())
     }
@@ -619,8 +1120,8 @@ impl VirtualFilesystem {
         let node_ref = self.arena.get(node).ok_or(VfsError::NotFound)?;
         let node_data = node_ref.get();
 
-        // Check permissions
-        if node_data.permissions.immutable {
+        // Check permissions: removing a node changes its directory
+        if node_data.permissions.immutable || !self.parent_allows_changes(node) {
             return Err(VfsError::PermissionDenied);
         }
 
@@ -635,8 +1136,200 @@ impl VirtualFilesystem {
         }
 
         // Remove the node from the tree (indextree handles parent cleanup!)
+        let parent = self.arena[node].parent();
         node.remove(&mut self.arena);
+        if let Some(parent) = parent {
+            self.entries_changed(parent);
+        }
+
+        Ok(())
+    }
+
+    /// Move `node` to be called `name` in `parent`, keeping everything else about it, as
+    /// `rename(2)` does. Anything already there is replaced, as long as directories only
+    /// replace empty directories and other nodes only replace other non-directories.
+    pub fn rename(&mut self, node: NodeId, parent: NodeId, name: &str) -> Result<(), VfsError> {
+        // Can't move root
+        if node == self.root {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        // Moving a node changes the directories it leaves and joins
+        let node_data = self.arena.get(node).ok_or(VfsError::NotFound)?.get();
+        if node_data.permissions.immutable || !self.parent_allows_changes(node) {
+            return Err(VfsError::PermissionDenied);
+        }
+        let is_directory = node_data.is_directory();
+        let parent_data = self.arena.get(parent).ok_or(VfsError::NotFound)?.get();
+        if !parent_data.is_directory() {
+            return Err(VfsError::NotADirectory);
+        }
+        if !parent_data.allows(&self.user, WRITE | EXECUTE) {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        // A directory can't be moved inside itself
+        if parent
+            .ancestors(&self.arena)
+            .any(|ancestor| ancestor == node)
+        {
+            return Err(VfsError::InvalidPath);
+        }
+
+        let existing = parent
+            .children(&self.arena)
+            .find(|child_id| self.arena[*child_id].get().name == name);
+        if existing == Some(node) {
+            return Ok(());
+        }
+        if let Some(existing) = existing {
+            let existing_data = self.arena[existing].get();
+            if existing_data.permissions.immutable {
+                return Err(VfsError::PermissionDenied);
+            }
+            match (is_directory, existing_data.is_directory()) {
+                (false, true) => return Err(VfsError::NotAFile),
+                (true, false) => return Err(VfsError::NotADirectory),
+                (true, true) if existing.children(&self.arena).next().is_some() => {
+                    return Err(VfsError::SystemError("Directory not empty".to_string()))
+                }
+                _ => {}
+            }
+        }
+
+        // Nothing may end up nested deeper than the quota allows
+        let node_depth = node.ancestors(&self.arena).count();
+        let height = node
+            .descendants(&self.arena)
+            .map(|descendant| descendant.ancestors(&self.arena).count() - node_depth)
+            .max()
+            .unwrap_or_default();
+        if parent.ancestors(&self.arena).count() + height > self.quota.max_depth {
+            return Err(VfsError::QuotaExceeded);
+        }
+
+        if let Some(existing) = existing {
+            existing.remove(&mut self.arena);
+        }
+        let old_parent = self.arena[node].parent();
+        node.detach(&mut self.arena);
+        parent.append(node, &mut self.arena);
+
+        let node_data = self.arena[node].get_mut();
+        node_data.name = name.to_string();
+        node_data.metadata.changed = Local::now();
+        if let Some(old_parent) = old_parent {
+            self.entries_changed(old_parent);
+        }
+        self.entries_changed(parent);
+
+        Ok(())
+    }
 
+    /// Set a node's access and modification times, following links, as `touch` does. Anyone
+    /// who may write to it may set them to now, but only its owner (or root) to another time.
+    pub fn set_times(
+        &mut self,
+        node: NodeId,
+        time: Option<DateTime<Local>>,
+    ) -> Result<(), VfsError> {
+        let node = self.follow_links(node, &mut 0)?;
+        let node_data = self.arena.get(node).ok_or(VfsError::NotFound)?.get();
+        let is_owner = self.user == ROOT || node_data.metadata.owner == self.user;
+        let allowed = match time {
+            Some(_) => is_owner,
+            None => is_owner || node_data.allows(&self.user, WRITE),
+        };
+        if node_data.permissions.immutable || !allowed {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        let now = Local::now();
+        let metadata = &mut self.arena[node].get_mut().metadata;
+        metadata.accessed = time.unwrap_or(now);
+        metadata.modified = time.unwrap_or(now);
+        metadata.changed = now;
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Note that entries have been added to or removed from a directory
+    fn entries_changed(&mut self, dir: NodeId) {
+        let now = Local::now();
+        let metadata = &mut self.arena[dir].get_mut().metadata;
+        metadata.modified = now;
+        metadata.changed = now;
+        self.changed = true;
+    }
+
+    fn parent_allows_changes(&self, node: NodeId) -> bool {
+        self.arena[node]
+            .parent()
+            .is_some_and(|parent| self.arena[parent].get().allows(&self.user, WRITE | EXECUTE))
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Act as `user` from now on, as the terminal does whenever `$USER` changes
+    pub fn set_user(&mut self, user: &str) {
+        if self.user != user {
+            self.user = user.to_string();
+        }
+    }
+
+    pub fn umask(&self) -> u16 {
+        self.umask
+    }
+
+    pub fn set_umask(&mut self, umask: u16) {
+        self.umask = umask & 0o777;
+    }
+
+    /// Change a node's mode, which only its owner (or root) may do
+    pub fn set_mode(&mut self, node: NodeId, mode: u16) -> Result<(), VfsError> {
+        let node_data = self.arena.get(node).ok_or(VfsError::NotFound)?.get();
+        if node_data.permissions.immutable
+            || (self.user != ROOT && node_data.metadata.owner != self.user)
+        {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        let node_data = self.arena[node].get_mut();
+        node_data.permissions.mode = mode & 0o777;
+        node_data.metadata.changed = Local::now();
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Change a node's owner and/or group. Only root may give a node away, but an owner may
+    /// change its group to one they're in.
+    pub fn set_owner(
+        &mut self,
+        node: NodeId,
+        owner: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<(), VfsError> {
+        let node_data = self.arena.get(node).ok_or(VfsError::NotFound)?.get();
+        let metadata = &node_data.metadata;
+        let allowed = self.user == ROOT
+            || (metadata.owner == self.user
+                && owner.is_none_or(|owner| owner == self.user)
+                && group.is_none_or(|group| in_group(&self.user, group)));
+        if node_data.permissions.immutable || !allowed {
+            return Err(VfsError::PermissionDenied);
+        }
+
+        let metadata = &mut self.arena[node].get_mut().metadata;
+        if let Some(owner) = owner {
+            metadata.owner = owner.to_string();
+        }
+        if let Some(group) = group {
+            metadata.group = group.to_string();
+        }
+        metadata.changed = Local::now();
+        self.changed = true;
         Ok(())
     }
 
@@ -671,6 +1364,120 @@ impl VirtualFilesystem {
         }
     }
 
+    pub fn quota(&self) -> &Quota {
+        &self.quota
+    }
+
+    pub fn set_quota(&mut self, quota: Quota) {
+        self.quota = quota;
+    }
+
+    /// What user nodes take up. System nodes are part of the site and don't count.
+    pub fn usage(&self) -> DiskUsage {
+        self.root
+            .descendants(&self.arena)
+            .skip(1)
+            .map(|node_id| self.arena[node_id].get())
+            .filter(|node| !node.permissions.immutable)
+            .fold(DiskUsage { bytes: 0, nodes: 0 }, |usage, node| DiskUsage {
+                bytes: usage.bytes + if node.is_directory() { 0 } else { node.size() },
+                nodes: usage.nodes + 1,
+            })
+    }
+
+    /// Check there's room for one more node of `size` bytes in `parent`
+    fn check_quota(&self, parent: NodeId, size: u64) -> Result<(), VfsError> {
+        let usage = self.usage();
+        // The root counts among a node's ancestors, but not among its path components
+        let depth = parent.ancestors(&self.arena).count();
+        if usage.nodes + 1 > self.quota.max_nodes
+            || usage.bytes + size > self.quota.max_bytes
+            || depth > self.quota.max_depth
+        {
+            return Err(VfsError::QuotaExceeded);
+        }
+        Ok(())
+    }
+
+    /// Whether any user node was created, written or deleted since the last call
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Everything users have added, parents before children, for saving between visits.
+    /// System nodes are rebuilt on every load, so they're left out.
+    pub fn snapshot(&self) -> VfsSnapshot {
+        let nodes = self
+            .root
+            .descendants(&self.arena)
+            .skip(1)
+            .filter_map(|node_id| {
+                let node = self.arena[node_id].get();
+                if node.permissions.immutable {
+                    return None;
+                }
+                let kind = match &node.node_type {
+                    VfsNodeType::Directory => SavedKind::Directory,
+                    VfsNodeType::File { content } => SavedKind::File {
+                        content: SavedContent::from(content),
+                    },
+                    VfsNodeType::Link { target } => SavedKind::Link {
+                        target: target.clone(),
+                    },
+                };
+                Some(SavedNode {
+                    path: self.get_node_path(node_id),
+                    kind,
+                    permissions: node.permissions.clone(),
+                    metadata: node.metadata.clone(),
+                })
+            })
+            .collect();
+
+        VfsSnapshot {
+            version: SNAPSHOT_VERSION,
+            nodes,
+        }
+    }
+
+    /// Add the nodes from a saved snapshot. A snapshot from another version is ignored, as is
+    /// any node whose directory is gone or whose name is now taken.
+    pub fn restore(&mut self, snapshot: VfsSnapshot) {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return;
+        }
+
+        for saved in snapshot.nodes {
+            let (parent_path, name) = saved.path.rsplit_once('/').unwrap_or(("", &saved.path));
+            let Ok(parent) = self.resolve_path_nofollow(self.root, parent_path) else {
+                continue;
+            };
+            let parent_is_dir =
+                matches!(self.arena[parent].get().node_type, VfsNodeType::Directory);
+            let name_taken = parent
+                .children(&self.arena)
+                .any(|child_id| self.arena[child_id].get().name == name);
+            if name.is_empty() || !parent_is_dir || name_taken {
+                continue;
+            }
+
+            let node_type = match saved.kind {
+                SavedKind::Directory => VfsNodeType::Directory,
+                SavedKind::File { content } => VfsNodeType::File {
+                    content: content.into(),
+                },
+                SavedKind::Link { target } => VfsNodeType::Link { target },
+            };
+            let node_id = self.arena.new_node(VfsNode {
+                name: name.to_string(),
+                node_type,
+                permissions: saved.permissions,
+                metadata: saved.metadata,
+            });
+            parent.append(node_id, &mut self.arena);
+        }
+    }
+
     pub fn get_node(&self, node: NodeId) -> Option<&VfsNode> {
         self.arena.get(node).map(|node_ref| node_ref.get())
     }
@@ -692,6 +1499,114 @@ pub struct DirEntry {
     pub is_executable: bool,
 }
 
+/// Bump whenever the saved shape changes, so older saves are dropped instead of misread
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// The user-created part of the filesystem, as saved in the browser's local storage
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VfsSnapshot {
+    version: u32,
+    nodes: Vec<SavedNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedNode {
+    path: String,
+    kind: SavedKind,
+    permissions: Permissions,
+    metadata: NodeMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SavedKind {
+    Directory,
+    File { content: SavedContent },
+    Link { target: String },
+}
+
+/// File contents as saved: text is kept as is, generated contents by what they're generated from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SavedContent {
+    Text(String),
+    NavFile(String),
+    BlogPost(String),
+    BlogMeta(String),
+}
+
+impl From<&FileContent> for SavedContent {
+    fn from(content: &FileContent) -> Self {
+        match content {
+            FileContent::Static(text) => SavedContent::Text(text.to_string()),
+            FileContent::Dynamic(text) => SavedContent::Text(text.clone()),
+            FileContent::NavFile(path) => SavedContent::NavFile(path.clone()),
+            FileContent::BlogPost(post) => SavedContent::BlogPost(post.clone()),
+            FileContent::BlogMeta(post) => SavedContent::BlogMeta(post.clone()),
+            // Only `/proc` is generated, and system nodes aren't saved
+            FileContent::Generated(_) => SavedContent::Text(String::new()),
+        }
+    }
+}
+
+impl From<SavedContent> for FileContent {
+    fn from(content: SavedContent) -> Self {
+        match content {
+            SavedContent::Text(text) => FileContent::Dynamic(text),
+            SavedContent::NavFile(path) => FileContent::NavFile(path),
+            SavedContent::BlogPost(post) => FileContent::BlogPost(post),
+            SavedContent::BlogMeta(post) => FileContent::BlogMeta(post),
+        }
+    }
+}
+
+impl ProcState {
+    fn process(&self, pid: u32) -> Option<&Process> {
+        self.processes.iter().find(|process| process.pid == pid)
+    }
+}
+
+fn proc_status(process: &Process, self_pid: u32) -> String {
+    let state = if process.pid == self_pid {
+        "R (running)"
+    } else {
+        "S (sleeping)"
+    };
+    // Everything but init was started by init
+    let parent = if process.pid == 1 { 0 } else { 1 };
+    format!(
+        "Name:\t{}\nState:\t{state}\nPid:\t{}\nPPid:\t{parent}\n",
+        process.command, process.pid
+    )
+}
+
+/// Seconds since boot, then seconds spent idle, which is most of them
+fn proc_uptime() -> String {
+    let seconds = (Local::now() - boot_time()).num_milliseconds() as f64 / 1000.0;
+    format!("{seconds:.2} {:.2}\n", seconds * 0.97)
+}
+
+fn proc_version() -> String {
+    format!(
+        "Linux version {} (hans@hansbaker.com) (rustc) #1 SMP {}\n",
+        env!("CARGO_PKG_VERSION"),
+        boot_time().format("%a %b %e %H:%M:%S %Y")
+    )
+}
+
+/// One `NAME=value` per line, rather than NUL-separated as on Linux, so it can be read with `cat`
+fn proc_environ(env_vars: &HashMap<String, String>) -> String {
+    let mut vars = env_vars
+        .iter()
+        .map(|(name, value)| format!("{name}={value}\n"))
+        .collect::<Vec<_>>();
+    vars.sort();
+    vars.concat()
+}
+
+// The browser fetches a post's files the first time they're read, so may not have them yet
+fn blog_unavailable() -> VfsError {
+    VfsError::SystemError("Resource temporarily unavailable".to_string())
+}
+
 // Helper function to create nav.rs content
 fn generate_nav_content(path: &str) -> String {
     let path = if path.is_empty() { "/" } else { path };
@@ -762,7 +1677,7 @@ mod tests {
     #[test]
     fn test_file_reading() {
         let blog_posts = vec![];
-        let vfs = VirtualFilesystem::new(blog_posts);
+        let mut vfs = VirtualFilesystem::new(blog_posts);
         let root = vfs.get_root();
 
         // Test reading static files
@@ -1235,4 +2150,454 @@ mod tests {
         let zigzag = vfs.resolve_path(c, "../../../a/b/../b/c/../c").unwrap();
         assert_eq!(zigzag, c);
     }
+
+    #[test]
+    fn test_write_file() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+
+        // Truncating and appending both leave a dynamic file behind
+        let file = vfs
+            .create_file(root, "notes.txt", FileContent::Dynamic(String::new()))
+            .unwrap();
+        vfs.write_file(file, "hello\n", false).unwrap();
+        vfs.write_file(file, "world\n", true).unwrap();
+        assert_eq!(vfs.read_file(file).unwrap(), "hello\nworld\n");
+        assert_eq!(vfs.get_node(file).unwrap().metadata.size, 12);
+
+        vfs.write_file(file, "reset\n", false).unwrap();
+        assert_eq!(vfs.read_file(file).unwrap(), "reset\n");
+
+        // System files and directories cannot be written
+        let nav = vfs.resolve_path(root, "/nav.rs").unwrap();
+        assert!(matches!(
+            vfs.write_file(nav, "x", false),
+            Err(VfsError::PermissionDenied)
+        ));
+        let thanks = vfs.resolve_path(root, "/thanks.txt").unwrap();
+        assert!(matches!(
+            vfs.write_file(thanks, "x", true),
+            Err(VfsError::PermissionDenied)
+        ));
+        let blog = vfs.resolve_path(root, "/blog").unwrap();
+        assert!(matches!(
+            vfs.write_file(blog, "x", false),
+            Err(VfsError::NotAFile)
+        ));
+    }
+
+    #[test]
+    fn test_blog_post_files() {
+        use crate::blog::{PostMeta, PostSource, GLOBAL_SOURCE_CACHE};
+
+        let markdown = "---\ntitle: Cached\n---\n# Cached post\n";
+        let meta = PostMeta {
+            name: "cached-post".to_string(),
+            title: "Cached: \"quoted\"".to_string(),
+            description: "A post".to_string(),
+            author: "Hans Baker".to_string(),
+            date: "2024-12-24T12:00:00Z".parse().unwrap(),
+            tags: vec!["rust".to_string()],
+        };
+        GLOBAL_SOURCE_CACHE.insert(
+            "cached-post".to_string(),
+            PostSource {
+                meta,
+                markdown: markdown.to_string(),
+            },
+        );
+
+        let blog_posts = vec!["cached-post".to_string(), "unfetched-post".to_string()];
+        let mut vfs = VirtualFilesystem::new(blog_posts);
+        let root = vfs.get_root();
+
+        let post = vfs
+            .resolve_path(root, "/blog/cached-post/cached-post.md")
+            .unwrap();
+        assert_eq!(vfs.read_file(post).unwrap(), markdown);
+        assert_eq!(vfs.get_node(post).unwrap().size(), markdown.len() as u64);
+        let meta = vfs
+            .resolve_path(root, "/blog/cached-post/meta.yaml")
+            .unwrap();
+        assert_eq!(
+            vfs.read_file(meta).unwrap(),
+            r#"name: "cached-post"
+title: "Cached: \"quoted\""
+description: "A post"
+author: "Hans Baker"
+date: 2024-12-24T12:00:00Z
+tags:
+  - "rust"
+"#
+        );
+
+        // Posts are read-only, and unreadable until they've been fetched
+        assert!(matches!(
+            vfs.write_file(post, "x", true),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.delete_node(post),
+            Err(VfsError::PermissionDenied)
+        ));
+        let unfetched = vfs
+            .resolve_path(root, "/blog/unfetched-post/unfetched-post.md")
+            .unwrap();
+        assert!(matches!(
+            vfs.read_file(unfetched),
+            Err(VfsError::SystemError(_))
+        ));
+        assert_eq!(vfs.get_node(unfetched).unwrap().size(), 0);
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let docs = vfs.create_directory(root, "docs").unwrap();
+        let thanks = vfs.resolve_path(root, "/thanks.txt").unwrap();
+        let blog = vfs.resolve_path(root, "/blog").unwrap();
+
+        // Relative targets are resolved from the link's own directory
+        let up = vfs.create_symlink(docs, "thanks", "../thanks.txt").unwrap();
+        vfs.create_symlink(root, "posts", "/blog").unwrap();
+        assert_eq!(vfs.resolve_path(root, "docs/thanks").unwrap(), thanks);
+        assert_eq!(vfs.resolve_path_nofollow(root, "docs/thanks").unwrap(), up);
+        assert_eq!(vfs.read_file(up).unwrap(), THANKS_TXT);
+        assert_eq!(vfs.resolve_path(root, "posts").unwrap(), blog);
+        assert_eq!(vfs.resolve_path_nofollow(root, "posts/.").unwrap(), blog);
+        assert!(vfs
+            .list_directory(vfs.resolve_path_nofollow(root, "posts").unwrap())
+            .is_ok());
+
+        let link = vfs.get_node(up).unwrap();
+        assert_eq!(link.link_target(), Some("../thanks.txt"));
+        assert_eq!(link.size(), 13);
+        assert!(link.long_meta_string(1, false).starts_with("lrwxrwxrwx"));
+        assert!(matches!(
+            vfs.create_symlink(docs, "thanks", "elsewhere"),
+            Err(VfsError::AlreadyExists)
+        ));
+
+        // Dangling links exist themselves but lead nowhere
+        let dangling = vfs.create_symlink(root, "dangling", "missing").unwrap();
+        assert_eq!(
+            vfs.resolve_path_nofollow(root, "dangling").unwrap(),
+            dangling
+        );
+        assert!(matches!(
+            vfs.resolve_path(root, "dangling"),
+            Err(VfsError::NotFound)
+        ));
+        assert!(matches!(vfs.read_file(dangling), Err(VfsError::NotFound)));
+
+        // Cycles give up instead of looping
+        vfs.create_symlink(root, "ping", "pong").unwrap();
+        vfs.create_symlink(root, "pong", "ping").unwrap();
+        assert!(matches!(
+            vfs.resolve_path(root, "ping"),
+            Err(VfsError::TooManyLinks)
+        ));
+        assert!(vfs.resolve_path_nofollow(root, "ping").is_ok());
+        assert!(matches!(
+            vfs.resolve_path_nofollow(root, "ping/x"),
+            Err(VfsError::TooManyLinks)
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut vfs = VirtualFilesystem::new(vec!["post".to_string()]);
+        let root = vfs.get_root();
+        assert!(!vfs.take_changed());
+
+        let notes = vfs.create_directory(root, "notes").unwrap();
+        let todo = vfs
+            .create_file(notes, "todo.txt", FileContent::Dynamic(String::new()))
+            .unwrap();
+        vfs.write_file(todo, "write tests\n", false).unwrap();
+        vfs.create_symlink(root, "todo", "notes/todo.txt").unwrap();
+        let post = vfs.resolve_path(root, "/blog/post").unwrap();
+        vfs.create_file(post, "draft.md", FileContent::BlogPost("post".to_string()))
+            .unwrap();
+        assert!(vfs.take_changed());
+        assert!(!vfs.take_changed());
+
+        // Only user nodes are saved, and they survive a trip through JSON
+        let snapshot = vfs.snapshot();
+        assert_eq!(snapshot.nodes.len(), 4);
+        assert_eq!(snapshot.nodes[0].path, "/blog/post/draft.md");
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: VfsSnapshot = serde_json::from_str(&json).unwrap();
+
+        let mut restored = VirtualFilesystem::new(vec!["post".to_string()]);
+        let root = restored.get_root();
+        restored.restore(snapshot.clone());
+        let todo = restored.resolve_path(root, "todo").unwrap();
+        assert_eq!(restored.read_file(todo).unwrap(), "write tests\n");
+        let original = vfs
+            .get_node(vfs.resolve_path(root, "/notes").unwrap())
+            .unwrap();
+        let notes = restored.resolve_path(root, "/notes").unwrap();
+        assert_eq!(
+            restored.get_node(notes).unwrap().metadata,
+            original.metadata
+        );
+        let draft = restored.resolve_path(root, "/blog/post/draft.md").unwrap();
+        assert!(matches!(
+            &restored.get_node(draft).unwrap().node_type,
+            VfsNodeType::File {
+                content: FileContent::BlogPost(post)
+            } if post == "post"
+        ));
+        assert!(!restored.take_changed());
+
+        // Nodes whose directory is gone are dropped
+        let mut without_post = VirtualFilesystem::new(vec![]);
+        without_post.restore(snapshot);
+        assert!(without_post.resolve_path(root, "/notes/todo.txt").is_ok());
+        assert!(without_post.resolve_path(root, "/blog/post").is_err());
+
+        // A save from another version is ignored
+        let old = json.replace("\"version\":1", "\"version\":0");
+        let mut fresh = VirtualFilesystem::new(vec![]);
+        fresh.restore(serde_json::from_str(&old).unwrap());
+        assert!(fresh.resolve_path(root, "/notes").is_err());
+    }
+
+    #[test]
+    fn test_quota() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        vfs.set_quota(Quota {
+            max_bytes: 10,
+            max_nodes: 4,
+            max_depth: 2,
+        });
+        assert_eq!(vfs.usage(), DiskUsage { bytes: 0, nodes: 0 });
+
+        // Bytes: creating and growing files both count
+        let file = vfs
+            .create_file(root, "a.txt", FileContent::Dynamic("12345678".to_string()))
+            .unwrap();
+        assert!(matches!(
+            vfs.create_file(root, "b.txt", FileContent::Dynamic("123".to_string())),
+            Err(VfsError::QuotaExceeded)
+        ));
+        assert!(matches!(
+            vfs.write_file(file, "abc", true),
+            Err(VfsError::QuotaExceeded)
+        ));
+        vfs.write_file(file, "shorter", false).unwrap();
+        assert_eq!(vfs.usage(), DiskUsage { bytes: 7, nodes: 1 });
+
+        // Depth
+        let dir = vfs.create_directory(root, "a").unwrap();
+        let nested = vfs.create_directory(dir, "b").unwrap();
+        assert!(matches!(
+            vfs.create_directory(nested, "c"),
+            Err(VfsError::QuotaExceeded)
+        ));
+
+        // Node count
+        vfs.create_symlink(root, "link", "a").unwrap();
+        assert!(matches!(
+            vfs.create_directory(root, "d"),
+            Err(VfsError::QuotaExceeded)
+        ));
+        vfs.delete_node(nested).unwrap();
+        assert!(vfs.create_directory(root, "d").is_ok());
+    }
+
+    #[test]
+    fn test_permissions() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+
+        // New nodes are owned by the current user and respect the umask
+        let file = vfs
+            .create_file(root, "notes.txt", FileContent::Dynamic("hi".to_string()))
+            .unwrap();
+        let dir = vfs.create_directory(root, "private").unwrap();
+        let node = vfs.get_node(file).unwrap();
+        assert_eq!(node.permissions.mode, 0o644);
+        assert_eq!(node.metadata.owner, "user");
+        assert_eq!(vfs.get_node(dir).unwrap().permissions.mode, 0o755);
+        assert!(vfs
+            .get_node(dir)
+            .unwrap()
+            .long_meta_string(2, false)
+            .starts_with("drwxr-xr-x"));
+        vfs.set_umask(0o077);
+        let secret = vfs
+            .create_file(dir, "secret", FileContent::Dynamic(String::new()))
+            .unwrap();
+        assert_eq!(vfs.get_node(secret).unwrap().permissions.mode, 0o600);
+
+        // Owner bits apply to the owner, other bits to everyone else
+        vfs.set_mode(file, 0o200).unwrap();
+        assert!(matches!(
+            vfs.read_file(file),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(vfs.write_file(file, "bye", false).is_ok());
+        vfs.set_mode(file, 0o604).unwrap();
+        vfs.set_user("hans");
+        assert!(vfs.read_file(file).is_ok());
+        assert!(matches!(
+            vfs.write_file(file, "bye", false),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.create_file(dir, "mine", FileContent::Dynamic(String::new())),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.delete_node(secret),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.set_mode(file, 0o777),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.set_owner(file, Some("hans"), None),
+            Err(VfsError::PermissionDenied)
+        ));
+
+        // Root may do anything, except change system nodes
+        vfs.set_user("root");
+        vfs.set_mode(dir, 0o700).unwrap();
+        vfs.set_owner(file, Some("hans"), Some("staff")).unwrap();
+        assert_eq!(vfs.get_node(file).unwrap().metadata.owner, "hans");
+        assert!(vfs.delete_node(secret).is_ok());
+        let blog = vfs.resolve_path(root, "/blog").unwrap();
+        assert!(matches!(
+            vfs.set_mode(blog, 0o700),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.delete_node(blog),
+            Err(VfsError::PermissionDenied)
+        ));
+
+        vfs.set_user("hans");
+        assert!(matches!(
+            vfs.list_directory(dir),
+            Err(VfsError::PermissionDenied)
+        ));
+    }
+
+    #[test]
+    fn test_times() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let old = Local::now() - Duration::days(400);
+        let dir = vfs.create_directory(root, "dir").unwrap();
+        let file = vfs
+            .create_file(dir, "file", FileContent::Dynamic("hi".to_string()))
+            .unwrap();
+        vfs.set_times(dir, Some(old)).unwrap();
+        vfs.set_times(file, Some(old)).unwrap();
+
+        // Reading only touches the access time
+        vfs.read_file(file).unwrap();
+        let metadata = &vfs.get_node(file).unwrap().metadata;
+        assert!(metadata.accessed > old);
+        assert_eq!(metadata.modified, old);
+        assert!(vfs
+            .get_node(file)
+            .unwrap()
+            .long_meta_string(1, false)
+            .ends_with(&format!("{} ", old.format("%b %e  %Y"))));
+
+        // Writing a file, or changing a directory's entries, updates its modification time
+        vfs.write_file(file, "bye", false).unwrap();
+        assert!(vfs.get_node(file).unwrap().metadata.modified > old);
+        assert_eq!(vfs.get_node(dir).unwrap().metadata.modified, old);
+        vfs.create_directory(dir, "sub").unwrap();
+        assert!(vfs.get_node(dir).unwrap().metadata.modified > old);
+
+        // Renaming moves the node itself and only changes its status time
+        vfs.set_times(file, Some(old)).unwrap();
+        vfs.rename(file, root, "moved").unwrap();
+        assert_eq!(vfs.resolve_path(root, "/moved").ok(), Some(file));
+        let metadata = &vfs.get_node(file).unwrap().metadata;
+        assert_eq!(metadata.modified, old);
+        assert!(metadata.changed > old);
+        assert!(matches!(
+            vfs.rename(dir, dir, "inside"),
+            Err(VfsError::InvalidPath)
+        ));
+        assert!(matches!(
+            vfs.rename(file, root, "dir"),
+            Err(VfsError::NotAFile)
+        ));
+        let blog = vfs.resolve_path(root, "/blog").unwrap();
+        assert!(matches!(
+            vfs.rename(blog, dir, "blog"),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.set_times(blog, None),
+            Err(VfsError::PermissionDenied)
+        ));
+
+        // Blog posts are dated from when they were published
+        let mut vfs = VirtualFilesystem::new(vec!["post".to_string()]);
+        let published = Local::now() - Duration::days(30);
+        vfs.seed_post_times(&[PostMeta {
+            name: "post".to_string(),
+            title: "Post".to_string(),
+            description: String::new(),
+            author: String::new(),
+            date: published.to_utc(),
+            tags: vec![],
+        }]);
+        let post = vfs
+            .resolve_path(vfs.get_root(), "/blog/post/post.md")
+            .unwrap();
+        assert_eq!(vfs.get_node(post).unwrap().metadata.modified, published);
+    }
+
+    #[test]
+    fn test_proc() {
+        let mut vfs = VirtualFilesystem::new(vec![]);
+        let root = vfs.get_root();
+        let process = Process {
+            pid: 7,
+            user: "app".to_string(),
+            cpu_percent: 0.0,
+            mem_percent: 0.0,
+            command: "shell".to_string(),
+        };
+        vfs.mount_proc(vec![process], 7);
+        vfs.set_environment(&HashMap::from([("A".to_string(), "1".to_string())]));
+
+        // Files are generated whenever they're read, so they have no size
+        let environ = vfs.resolve_path(root, "/proc/self/environ").unwrap();
+        assert_eq!(vfs.get_node(environ).unwrap().size(), 0);
+        assert_eq!(vfs.read_file(environ).unwrap(), "A=1\n");
+        vfs.set_environment(&HashMap::new());
+        assert_eq!(vfs.read_file(environ).unwrap(), "");
+        let cmdline = vfs.resolve_path(root, "/proc/7/cmdline").unwrap();
+        assert_eq!(vfs.read_file(cmdline).unwrap(), "shell");
+        assert_eq!(vfs.get_node(cmdline).unwrap().metadata.owner, "app");
+
+        // Nothing in it can be changed
+        let proc_dir = vfs.resolve_path(root, "/proc").unwrap();
+        assert!(matches!(
+            vfs.write_file(cmdline, "other", false),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.create_file(proc_dir, "new", FileContent::Dynamic(String::new())),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(matches!(
+            vfs.delete_node_recursive(proc_dir),
+            Err(VfsError::PermissionDenied)
+        ));
+        assert!(vfs.snapshot().nodes.is_empty());
+    }
 }
@@ -1,5 +1,7 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use dashmap::DashMap;
+#[cfg(feature = "hydrate")]
+use dashmap::DashSet;
 use rust_embed::Embed;
 use serde::{Deserialize, Serialize};
 use std::sync::LazyLock;
@@ -18,6 +20,9 @@ use crate::highlight::highlight;
 pub static GLOBAL_POST_CACHE: LazyLock<DashMap<String, Option<Post>>> = LazyLock::new(DashMap::new);
 pub static GLOBAL_META_CACHE: LazyLock<DashMap<String, Vec<PostMeta>>> =
     LazyLock::new(DashMap::new);
+pub static GLOBAL_SOURCE_CACHE: LazyLock<DashMap<String, PostSource>> = LazyLock::new(DashMap::new);
+#[cfg(feature = "hydrate")]
+static SOURCE_REQUESTS: LazyLock<DashSet<String>> = LazyLock::new(DashSet::new);
 
 #[derive(Embed)]
 #[folder = "blog"]
@@ -44,6 +49,33 @@ pub struct PostMeta {
     pub tags: Vec<String>,
 }
 
+impl PostMeta {
+    /// The metadata as a YAML document, for the terminal's `meta.yaml` files
+    pub fn to_yaml(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| format!("  - {}\n", quote(tag)))
+            .collect::<String>();
+        format!(
+            "name: {}\ntitle: {}\ndescription: {}\nauthor: {}\ndate: {}\ntags:\n{tags}",
+            quote(&self.name),
+            quote(&self.title),
+            quote(&self.description),
+            quote(&self.author),
+            self.date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )
+    }
+}
+
+/// A post's markdown as written, which the terminal shows under `/blog/<post>/`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostSource {
+    pub meta: PostMeta,
+    pub markdown: String,
+}
+
 #[derive(Error, Debug, Clone)]
 pub enum BlogError {
     #[error("Blog post not found")]
@@ -150,3 +182,60 @@ pub async fn get_post(name: String) -> Option<Post> {
         })
         .clone()
 }
+
+/// The source of a post (named without the `.md`). On the server it's read from the embedded
+/// assets. The browser only embeds metadata, so the first time it's asked for a post it
+/// fetches the source in the background, and has it for the reads after that.
+pub fn get_post_source(name: &str) -> Option<PostSource> {
+    let cache = &*GLOBAL_SOURCE_CACHE;
+    if let Some(source) = cache.get(name) {
+        return Some(source.clone());
+    }
+
+    #[cfg(any(feature = "ssr", feature = "rss"))]
+    {
+        let content = Assets::get(&format!("{name}.md"))?;
+        let markdown = String::from_utf8(content.data.into()).ok()?;
+        let matter = Matter::<YAML>::new();
+        let fm = matter.parse_with_struct::<FrontMatter>(&markdown)?;
+        let source = PostSource {
+            meta: PostMeta {
+                name: name.to_string(),
+                title: fm.data.title,
+                description: fm.data.description,
+                author: fm.data.author,
+                date: fm.data.date,
+                tags: fm.data.tags,
+            },
+            markdown,
+        };
+        cache.insert(name.to_string(), source.clone());
+        Some(source)
+    }
+    #[cfg(not(any(feature = "ssr", feature = "rss")))]
+    {
+        #[cfg(feature = "hydrate")]
+        request_post_source(name);
+        None
+    }
+}
+
+/// Fetch a post's source into the cache, unless it's already on its way
+#[cfg(feature = "hydrate")]
+fn request_post_source(name: &str) {
+    if !SOURCE_REQUESTS.insert(name.to_string()) {
+        return;
+    }
+    let name = name.to_string();
+    leptos::task::spawn_local(async move {
+        match crate::app::blog::get_post_source_server(name.clone()).await {
+            Ok(source) => {
+                GLOBAL_SOURCE_CACHE.insert(name, source);
+            }
+            // Let the next read try again
+            Err(_) => {
+                SOURCE_REQUESTS.remove(&name);
+            }
+        }
+    });
+}